@@ -0,0 +1,61 @@
+use chrono::{Duration, NaiveDate};
+use std::{fmt, str::FromStr};
+
+/// Why someone was away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AbsenceKind {
+    Vacation,
+    Sick,
+    /// A public holiday.
+    Holiday,
+    Unpaid,
+}
+
+impl FromStr for AbsenceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vacation" => Ok(AbsenceKind::Vacation),
+            "sick" => Ok(AbsenceKind::Sick),
+            "holiday" => Ok(AbsenceKind::Holiday),
+            "unpaid" => Ok(AbsenceKind::Unpaid),
+            _ => Err(format!(
+                "`{}` is not a kind of time off, expected vacation, sick, holiday or unpaid",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AbsenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AbsenceKind::Vacation => "vacation",
+            AbsenceKind::Sick => "sick",
+            AbsenceKind::Holiday => "holiday",
+            AbsenceKind::Unpaid => "unpaid",
+        })
+    }
+}
+
+/// Time off on a day, like vacation, which is not time worked.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Absence {
+    pub date: NaiveDate,
+    pub kind: AbsenceKind,
+    /// How much of the day was taken off, stored in seconds.
+    #[serde(with = "crate::duration::seconds")]
+    pub duration: Duration,
+}
+
+impl Absence {
+    pub fn new(date: NaiveDate, kind: AbsenceKind, duration: Duration) -> Self {
+        Absence {
+            date,
+            kind,
+            duration,
+        }
+    }
+}
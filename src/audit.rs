@@ -0,0 +1,95 @@
+use crate::{Entry, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use log::debug;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// A single change made to the log.
+///
+/// An entry that was created has no `before`, and one that was deleted has no `after`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuditRecord {
+    pub time: DateTime<FixedOffset>,
+    /// The command line that made the change.
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Entry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Entry>,
+}
+
+impl AuditRecord {
+    pub fn new(command: String, before: Option<Entry>, after: Option<Entry>) -> Self {
+        let id = after.as_ref().or(before.as_ref()).and_then(|e| e.id);
+        AuditRecord {
+            time: Local::now().fixed_offset(),
+            command,
+            id,
+            before,
+            after,
+        }
+    }
+
+    /// The path of the audit trail belonging to the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".audit");
+        PathBuf::from(path)
+    }
+
+    /// Appends `records` to the audit trail at `path`, one JSON object per line.
+    ///
+    /// The file is only ever appended to, so earlier records are never rewritten.
+    pub fn append<P: AsRef<Path>>(path: P, records: &[AuditRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut out = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut out, record)?;
+            out.push(b'\n');
+        }
+        let path = path.as_ref();
+        debug!("appending {} records to {}", records.len(), path.display());
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(&out)?;
+        Ok(())
+    }
+
+    /// Replaces the audit trail at `path` with `records`.
+    ///
+    /// This is only for when earlier records must not be kept, like when text is redacted from
+    /// the log.
+    pub fn rewrite<P: AsRef<Path>>(path: P, records: &[AuditRecord]) -> Result<()> {
+        let mut out = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut out, record)?;
+            out.push(b'\n');
+        }
+        let path = path.as_ref();
+        debug!(
+            "rewriting {} with {} records",
+            path.display(),
+            records.len()
+        );
+        File::create(path)?.write_all(&out)?;
+        Ok(())
+    }
+
+    /// Reads every record in the audit trail at `path`, oldest first.
+    pub fn read_all<P: AsRef<Path>>(path: P) -> Result<Vec<AuditRecord>> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::Deserializer::from_reader(BufReader::new(file))
+                .into_iter()
+                .collect::<Result<_, _>>()?),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    args::DurationArg,
+    config::{parse_option, Config},
+    events::{announce, run_hook, Event},
+    file::{get_entry_iter, get_index, update_last_entry, WriteOptions},
+    status::entry_json,
+    stop_entry, Result,
+};
+use chrono::{Duration, Local, Weekday};
+use log::{info, warn};
+use serde_derive::Deserialize;
+use std::{collections::BTreeMap, error::Error, fmt::Display};
+#[cfg(feature = "graphql")]
+use timelog::Timelog;
+use timelog::{Entry, Period, Range, Rate, Summary, Timezone};
+
+/// The JSON API of `timelog serve`.
+///
+/// Entries are read and written the same way the commands do, so the server works on the log
+/// file directly and through a daemon alike.
+pub struct Api {
+    pub log_file: String,
+    pub lenient: bool,
+    pub write: WriteOptions,
+    pub week_start: Weekday,
+    /// The config, with the tags new entries get unless the request has its own.
+    pub config: Config,
+    #[cfg(feature = "graphql")]
+    pub schema: timelog::GraphQlSchema,
+}
+
+/// The body of `POST /entries/start`, which may also be left empty.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StartRequest {
+    goal: String,
+    tags: Option<Vec<String>>,
+    meta: BTreeMap<String, String>,
+    #[serde(deserialize_with = "parse_option")]
+    estimate: Option<DurationArg>,
+    client: Option<String>,
+    billable: Option<bool>,
+    rate: Option<Rate>,
+}
+
+/// The body of `POST /entries/stop`, which may also be left empty.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StopRequest {
+    result: String,
+}
+
+/// An API error that is the client's fault, answered with `status`.
+#[derive(Debug)]
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new<S: Into<String>>(status: u16, message: S) -> HttpError {
+        HttpError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for HttpError {}
+
+impl Api {
+    /// Answers `request`, with errors as `{"error": "..."}`.
+    pub fn respond(&self, mut request: tiny_http::Request) {
+        let metrics = *request.method() == tiny_http::Method::Get
+            && request.url().split('?').next() == Some("/metrics");
+        let result = if metrics {
+            let content_type = "text/plain; version=0.0.4";
+            self.metrics().map(|body| (200, content_type, body))
+        } else {
+            let response = self.handle(&mut request);
+            response.map(|(status, body)| (status, "application/json", body.to_string()))
+        };
+        let (status, content_type, body) = result.unwrap_or_else(|e| {
+            let status = if let Some(e) = e.downcast_ref::<HttpError>() {
+                e.status
+            } else if let Some(
+                timelog::Error::NoEntries
+                | timelog::Error::NoActiveEntry
+                | timelog::Error::AlreadyStopped,
+            ) = e.downcast_ref()
+            {
+                409
+            } else {
+                500
+            };
+            let body = serde_json::json!({ "error": e.to_string() });
+            (status, "application/json", body.to_string())
+        });
+        info!("{} {} {}", request.method(), request.url(), status);
+        let content_type = tiny_http::Header::from_bytes("Content-Type", content_type)
+            .expect("the header is valid");
+        let response = tiny_http::Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            warn!("{}", e);
+        }
+    }
+
+    fn handle(&self, request: &mut tiny_http::Request) -> Result<(u16, serde_json::Value)> {
+        use tiny_http::Method::{Get, Post};
+
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let now = Local::now();
+        match (request.method(), path) {
+            (Get, "/entries") => {
+                let entries = get_entry_iter(&self.log_file, self.lenient)?
+                    .map(|e| e.map(|e| entry_json(&e, now)))
+                    .collect::<timelog::Result<Vec<_>>>()?;
+                Ok((200, entries.into()))
+            }
+            (Post, "/entries/start") => {
+                let mut body: StartRequest = read_json(request)?.unwrap_or_default();
+                let estimate = body.estimate.map(|DurationArg(d)| d);
+                if estimate.is_some_and(|d| d < Duration::zero()) {
+                    Err(HttpError::new(400, "the estimate can't be negative"))?;
+                }
+                let client = body.client.take();
+                let client = client.or_else(|| self.config.client_of(&body.meta));
+                let rate = body.rate.take();
+                let mut entry = Entry {
+                    goal: body.goal,
+                    meta: body.meta,
+                    estimate,
+                    tags: body.tags.unwrap_or_else(|| self.config.tags.clone()),
+                    client,
+                    user: self.config.user(),
+                    ..Entry::default()
+                };
+                entry.billable = body
+                    .billable
+                    .unwrap_or_else(|| self.config.billable_for(&entry));
+                entry.rate = rate.or_else(|| self.config.rate_for(&entry));
+                let mut started = None;
+                update_last_entry(&self.log_file, self.write, |log| {
+                    let entry = log.start_with(entry);
+                    if !self.write.dry_run {
+                        run_hook(&self.log_file, "pre", Event::Start, entry)?;
+                    }
+                    started = Some(entry.clone());
+                    Ok(())
+                })?;
+                let started = started.expect("an entry was started");
+                if !self.write.dry_run {
+                    announce(&self.log_file, &self.config, Event::Start, &started);
+                }
+                Ok((201, entry_json(&started, now)))
+            }
+            (Post, "/entries/stop") => {
+                let body: StopRequest = read_json(request)?.unwrap_or_default();
+                let mut stopped = None;
+                update_last_entry(&self.log_file, self.write, |log| {
+                    let entry = stop_entry(log, &self.config, body.result, now.fixed_offset())?;
+                    if !self.write.dry_run {
+                        run_hook(&self.log_file, "pre", Event::Stop, &entry)?;
+                    }
+                    stopped = Some(entry);
+                    Ok(())
+                })?;
+                let stopped = stopped.expect("an entry was stopped");
+                if !self.write.dry_run {
+                    announce(&self.log_file, &self.config, Event::Stop, &stopped);
+                }
+                Ok((200, entry_json(&stopped, now)))
+            }
+            (Get, "/summary") => self.summary(query),
+            #[cfg(feature = "graphql")]
+            (Post, "/graphql") => {
+                let graphql: async_graphql::Request = read_json(request)?
+                    .ok_or_else(|| HttpError::new(400, "the query is missing"))?;
+                let log: Timelog = get_entry_iter(&self.log_file, self.lenient)?
+                    .collect::<timelog::Result<_>>()?;
+                let response = futures_executor::block_on(self.schema.execute(graphql.data(log)));
+                Ok((200, serde_json::to_value(response)?))
+            }
+            (
+                _,
+                "/entries" | "/entries/start" | "/entries/stop" | "/summary" | "/graphql"
+                | "/metrics",
+            ) => Err(HttpError::new(405, "method not allowed"))?,
+            _ => Err(HttpError::new(404, format!("{} not found", path)))?,
+        }
+    }
+
+    /// Answers `GET /metrics` in the text format of Prometheus, with the time tracked per
+    /// `project` meta so far.
+    fn metrics(&self) -> Result<String> {
+        let now = Local::now().fixed_offset();
+        let mut entries = 0;
+        let mut running = None;
+        let mut projects = BTreeMap::<String, Duration>::new();
+        for entry in get_entry_iter(&self.log_file, self.lenient)? {
+            let entry = entry?;
+            entries += 1;
+            let duration = match (entry.start, entry.stop) {
+                (Some(start), Some(stop)) => stop - start,
+                (Some(start), None) if entry.is_active() => now - start,
+                _ => continue,
+            };
+            if entry.is_active() {
+                running = Some(duration);
+            }
+            let project = entry.meta.get("project").cloned().unwrap_or_default();
+            *projects.entry(project).or_insert_with(Duration::zero) += duration;
+        }
+
+        let header = |name: &str, kind: &str, help: &str| {
+            format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind)
+        };
+        let mut out = header("timelog_active", "gauge", "Whether an entry is running.");
+        out += &format!("timelog_active {}\n", u8::from(running.is_some()));
+        out += &header(
+            "timelog_active_seconds",
+            "gauge",
+            "How long the running entry has run.",
+        );
+        let seconds = running.map_or(0, |d| d.num_seconds());
+        out += &format!("timelog_active_seconds {}\n", seconds);
+        // Deleting entries makes both of these go down, so they are gauges rather than counters.
+        out += &header("timelog_entries", "gauge", "How many entries the log has.");
+        out += &format!("timelog_entries {}\n", entries);
+        out += &header(
+            "timelog_tracked_seconds",
+            "gauge",
+            "The time tracked per project meta.",
+        );
+        for (project, duration) in projects {
+            let project = project
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            out += &format!(
+                "timelog_tracked_seconds{{project=\"{}\"}} {}\n",
+                project,
+                duration.num_seconds()
+            );
+        }
+        Ok(out)
+    }
+
+    /// Answers `GET /summary?period=weekly`, optionally with a `timezone` like the one of
+    /// `summary --timezone`.
+    fn summary(&self, query: &str) -> Result<(u16, serde_json::Value)> {
+        let mut period = None;
+        let mut timezone = Timezone::Local;
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            match &*key {
+                "period" => period = Some(value),
+                "timezone" => timezone = value.parse().map_err(|e| HttpError::new(400, e))?,
+                _ => Err(HttpError::new(400, format!("unknown parameter `{}`", key)))?,
+            }
+        }
+        let name = period.ok_or_else(|| HttpError::new(400, "the period is missing"))?;
+        let period = Period::from_name(&name, self.week_start).ok_or_else(|| {
+            HttpError::new(
+                400,
+                format!(
+                    "`{}` is not a period, expected daily, weekly, monthly, quarterly or yearly",
+                    name
+                ),
+            )
+        })?;
+
+        let index = get_index(&self.log_file, self.lenient, timezone)?;
+        let summary = Summary::from_days(index.days(), period, Range::all());
+        let buckets: Vec<_> = summary
+            .buckets
+            .iter()
+            .map(|b| serde_json::json!({ "start": b.start, "duration": b.total.num_seconds() }))
+            .collect();
+        let body = serde_json::json!({
+            "period": name,
+            "buckets": buckets,
+            "duration": summary.total().num_seconds(),
+        });
+        Ok((200, body))
+    }
+}
+
+/// Reads the JSON body of `request`, if it isn't empty.
+fn read_json<T>(request: &mut tiny_http::Request) -> Result<Option<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    let value = serde_json::from_str(&body).map_err(|e| HttpError::new(400, e.to_string()))?;
+    Ok(Some(value))
+}
@@ -0,0 +1,396 @@
+use crate::{report::format_period, Result};
+use chrono::{
+    format::{Item, StrftimeItems},
+    Duration, NaiveDate, Weekday,
+};
+use itertools::Itertools;
+use serde_derive::Deserialize;
+use std::str::FromStr;
+use timelog::{
+    parse_dur, Bucket, DurationFormat, DurationStyle, Entry, Language, Money, Period, Rate,
+    Template, TimeFormat,
+};
+
+/// Whether `on-suspend` runs before going to sleep or after waking up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepPhase {
+    Pre,
+    Post,
+}
+
+impl FromStr for SleepPhase {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pre" => Ok(SleepPhase::Pre),
+            "post" => Ok(SleepPhase::Post),
+            _ => Err(format!("`{}` is not a phase, expected pre or post", s)),
+        }
+    }
+}
+
+/// A duration given on the command line, like `1h30m`, or a plain number of seconds.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct DurationArg(pub Duration);
+
+impl std::convert::TryFrom<String> for DurationArg {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for DurationArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(seconds) => Duration::try_seconds(seconds)
+                .map(DurationArg)
+                .ok_or_else(|| format!("`{}` seconds is too long", s)),
+            Err(_) => parse_dur(s).map(DurationArg),
+        }
+    }
+}
+
+/// An amount of money given on the command line, like `30 EUR`, or `30` without a currency.
+#[derive(Debug, Clone)]
+pub struct AmountArg {
+    pub cents: i64,
+    pub currency: Option<String>,
+}
+
+impl FromStr for AmountArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Ok(money) = s.parse::<Money>() {
+            return Ok(AmountArg {
+                cents: money.cents,
+                currency: Some(money.currency),
+            });
+        }
+        // Any code will do to read the amount, it is left out again.
+        match format!("{} XXX", s.trim()).parse::<Money>() {
+            Ok(money) => Ok(AmountArg {
+                cents: money.cents,
+                currency: None,
+            }),
+            Err(_) => Err(format!(
+                "`{}` is not an amount of money, expected one like 30 or 30 EUR",
+                s
+            )),
+        }
+    }
+}
+
+/// A line format for the periods of a summary.
+///
+/// The fields are `period`, the name of the period, `start`, its first day, which takes a
+/// `strftime` format, `duration`, which takes a duration style, and `hours`, which takes a
+/// precision like `.2`.
+#[derive(Debug, Clone)]
+pub struct SummaryTemplate(pub Template);
+
+impl FromStr for SummaryTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let template: Template = s.parse()?;
+        for (name, spec) in template.fields() {
+            match (name, spec) {
+                ("period", None) | ("start", None) | ("duration", None) | ("hours", None) => {}
+                ("start", Some(spec)) => {
+                    if StrftimeItems::new(spec).any(|i| i == Item::Error) {
+                        return Err(format!("`{}` is not a valid date format", spec));
+                    }
+                }
+                ("duration", Some(spec)) => {
+                    spec.parse::<DurationStyle>()?;
+                }
+                ("hours", Some(spec)) => {
+                    parse_precision(spec)?;
+                }
+                ("period", Some(_)) => return Err("`period` doesn't take a format".to_string()),
+                (name, _) => return Err(format!("`{}` is not a field of summaries", name)),
+            }
+        }
+        Ok(SummaryTemplate(template))
+    }
+}
+
+impl SummaryTemplate {
+    pub fn render(
+        &self,
+        period: Period,
+        bucket: Bucket,
+        duration: DurationFormat,
+        time_format: TimeFormat,
+        language: Language,
+    ) -> String {
+        self.0.render(|name, spec| match name {
+            "period" => format_period(period, bucket.start, time_format, language),
+            "start" => time_format.format_date(bucket.start, spec.unwrap_or("%F")),
+            "duration" => match spec.and_then(|s| s.parse().ok()) {
+                Some(style) => duration.style(style).format(bucket.total),
+                None => duration.format(bucket.total),
+            },
+            _ => {
+                let decimals = spec.and_then(|s| parse_precision(s).ok()).unwrap_or(2);
+                DurationFormat::new()
+                    .style(DurationStyle::Decimal)
+                    .decimals(decimals)
+                    .format(bucket.total)
+            }
+        })
+    }
+}
+
+/// Parses a precision like `.2`.
+fn parse_precision(s: &str) -> std::result::Result<usize, String> {
+    s.strip_prefix('.')
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| format!("`{}` is not a precision like .2", s))
+}
+
+/// What `print --sort` orders entries by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Start,
+    Stop,
+    Duration,
+    Goal,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "start" => Ok(SortKey::Start),
+            "stop" => Ok(SortKey::Stop),
+            "duration" => Ok(SortKey::Duration),
+            "goal" => Ok(SortKey::Goal),
+            _ => Err(format!(
+                "`{}` is not a sort key, expected start, stop, duration or goal",
+                s
+            )),
+        }
+    }
+}
+
+impl SortKey {
+    /// Sorts `entries`, which are sorted by start time, keeping that order for ties.
+    ///
+    /// Running entries come after stopped ones, since they have neither stopped nor have a
+    /// duration yet.
+    pub fn sort(self, entries: &mut [Entry]) {
+        match self {
+            SortKey::Start => {}
+            SortKey::Stop => entries.sort_by_key(|e| (e.stop.is_none(), e.stop)),
+            SortKey::Duration => entries.sort_by_key(|e| (e.is_active(), e.duration())),
+            SortKey::Goal => entries.sort_by(|a, b| a.goal.cmp(&b.goal)),
+        }
+    }
+}
+
+/// Parses `key=value` data given with `--meta`.
+pub fn parse_meta(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("`{}` is not of the form key=value", s)),
+    }
+}
+
+/// A `--meta` filter, which matches entries with the key, and the value if one is given.
+#[derive(Debug, Clone)]
+pub struct MetaFilter {
+    key: String,
+    value: Option<String>,
+}
+
+impl FromStr for MetaFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (key, value) = match s.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (s, None),
+        };
+        if key.is_empty() {
+            return Err(format!("`{}` has no key", s));
+        }
+        Ok(MetaFilter {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+impl MetaFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match (entry.meta.get(&self.key), &self.value) {
+            (Some(v), Some(value)) => v == value,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// What `timelog complete` lists, one value per line.
+#[derive(Debug, Clone, Copy)]
+pub enum CompletionKind {
+    Ids,
+    MetaKeys,
+    /// The values of the `project` meta.
+    Projects,
+}
+
+impl FromStr for CompletionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "ids" => Ok(CompletionKind::Ids),
+            "meta-keys" => Ok(CompletionKind::MetaKeys),
+            "projects" => Ok(CompletionKind::Projects),
+            _ => Err(format!("`{}` can't be completed", s)),
+        }
+    }
+}
+
+/// A field of an entry with a new value, given on the command line like `client=acme`.
+#[derive(Debug, Clone)]
+pub enum FieldValue {
+    Billable(bool),
+    Client(Option<String>),
+    Estimate(Option<Duration>),
+    Rate(Option<Rate>),
+    User(Option<String>),
+    Meta(String, Option<String>),
+}
+
+impl FromStr for FieldValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("`{}` is not like field=value", s))?;
+        let text = Some(value.to_string()).filter(|v| !v.is_empty());
+        match key {
+            "billable" => match value {
+                "true" | "yes" => Ok(FieldValue::Billable(true)),
+                "false" | "no" => Ok(FieldValue::Billable(false)),
+                _ => Err(format!("`{}` is not true or false", value)),
+            },
+            "client" => Ok(FieldValue::Client(text)),
+            "estimate" => {
+                let estimate = text.map(|e| e.parse::<DurationArg>()).transpose()?;
+                Ok(FieldValue::Estimate(estimate.map(|DurationArg(d)| d)))
+            }
+            "rate" => Ok(FieldValue::Rate(text.map(|r| r.parse()).transpose()?)),
+            "user" => Ok(FieldValue::User(text)),
+            _ => match key.strip_prefix("meta.") {
+                Some(key) if !key.is_empty() => Ok(FieldValue::Meta(key.to_string(), text)),
+                _ => Err(format!(
+                    "`{}` can't be set, expected billable, client, estimate, rate, user or meta.<key>",
+                    key
+                )),
+            },
+        }
+    }
+}
+
+impl FieldValue {
+    pub fn apply(self, entry: &mut Entry) {
+        match self {
+            FieldValue::Billable(billable) => entry.billable = billable,
+            FieldValue::Client(client) => entry.client = client,
+            FieldValue::Estimate(estimate) => entry.estimate = estimate,
+            FieldValue::Rate(rate) => entry.rate = rate,
+            FieldValue::User(user) => entry.user = user,
+            FieldValue::Meta(key, Some(value)) => {
+                entry.meta.insert(key, value);
+            }
+            FieldValue::Meta(key, None) => {
+                entry.meta.remove(&key);
+            }
+        }
+    }
+}
+
+/// An entry given on the command line, either by ID or by its 1-based position (`#3`).
+#[derive(Debug, Clone, Copy)]
+pub enum EntrySelector {
+    Id(u64),
+    Position(usize),
+}
+
+impl FromStr for EntrySelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = |_| format!("`{}` is neither an ID nor a position like #3", s);
+        if let Some(position) = s.strip_prefix('#') {
+            Ok(EntrySelector::Position(position.parse().map_err(invalid)?))
+        } else {
+            Ok(EntrySelector::Id(s.parse().map_err(invalid)?))
+        }
+    }
+}
+
+impl EntrySelector {
+    /// Finds the index of the selected entry in the sorted `entries`.
+    pub fn find(self, entries: &[Entry]) -> Result<usize> {
+        match self {
+            EntrySelector::Position(p) if p >= 1 && p <= entries.len() => Ok(p - 1),
+            EntrySelector::Position(p) => Err(format!("there is no entry #{}", p))?,
+            EntrySelector::Id(id) => {
+                let mut matching = entries.iter().positions(|e| e.id == Some(id));
+                match (matching.next(), matching.next()) {
+                    (Some(i), None) => Ok(i),
+                    (Some(_), Some(_)) => Err(format!(
+                        "several entries have the ID {}, select one by position instead",
+                        id
+                    ))?,
+                    (None, _) => Err(format!("there is no entry with the ID {}", id))?,
+                }
+            }
+        }
+    }
+}
+
+/// Parses a day like `2024-03-01`.
+pub fn parse_date(s: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| format!("`{}` is not a date like 2024-03-01", s))
+}
+
+/// A number of hours in a day, like `8` or `4.5`.
+pub fn parse_hours(s: &str) -> std::result::Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(hours) if hours > 0.0 && hours <= 24.0 => {
+            Ok(Duration::seconds((hours * 3600.0).round() as i64))
+        }
+        _ => Err(format!("`{}` is not a number of hours in a day, like 8", s)),
+    }
+}
+
+/// Parses an ISO week like `2024-W12` into its Monday.
+pub fn parse_week(s: &str) -> std::result::Result<NaiveDate, String> {
+    let invalid = || format!("`{}` is not a week like 2024-W12", s);
+    let (year, week) = s.split_once("-W").ok_or_else(invalid)?;
+    let year = year.parse().map_err(|_| invalid())?;
+    let week = week.parse().map_err(|_| invalid())?;
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).ok_or_else(invalid)
+}
+
+/// Parses a month like `2024-03` into its first day.
+pub fn parse_month(s: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d")
+        .map_err(|_| format!("`{}` is not a month like 2024-03", s))
+}
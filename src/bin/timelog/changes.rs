@@ -0,0 +1,246 @@
+use itertools::Itertools;
+use std::{collections::HashMap, fmt::Display};
+use timelog::{format_dur, Absence, Entry, Expense};
+
+/// Pairs up two versions of the same log.
+///
+/// Entries are matched by their start time, preferring identical entries, or only by being
+/// identical if they have no start time. Unmatched entries are paired with `None`.
+pub fn match_entries<'a>(
+    old: &'a [Entry],
+    new: &'a [Entry],
+) -> Vec<(Option<&'a Entry>, Option<&'a Entry>)> {
+    let mut by_start: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, n) in new.iter().enumerate() {
+        by_start.entry(n.start).or_default().push(i);
+    }
+    let mut new: Vec<_> = new.iter().map(Some).collect();
+
+    let mut pairs = Vec::new();
+    for o in old {
+        let found = by_start.get_mut(&o.start).and_then(|candidates| {
+            let i = candidates
+                .iter()
+                .position(|&i| new[i] == Some(o))
+                .or_else(|| o.start.and(candidates.first().map(|_| 0)))?;
+            Some(candidates.remove(i))
+        });
+        pairs.push((Some(o), found.and_then(|i| new[i].take())));
+    }
+    pairs.extend(new.into_iter().flatten().map(|n| (None, Some(n))));
+    pairs
+}
+
+/// Prints which entries were added (+), removed (-) or modified (~) between two versions of a log.
+pub fn print_changes(old: &[Entry], new: &[Entry]) {
+    for pair in match_entries(old, new) {
+        match pair {
+            (Some(o), Some(n)) => {
+                let changes = entry_changes(o, n);
+                if !changes.is_empty() {
+                    println!("~ {:#}", n);
+                    for change in changes {
+                        println!("    {}", change);
+                    }
+                }
+            }
+            (Some(o), None) => println!("- {:#}", o),
+            (None, Some(n)) => println!("+ {:#}", n),
+            (None, None) => {}
+        }
+    }
+}
+
+/// The fields that differ between two versions of an entry.
+pub fn entry_changes(old: &Entry, new: &Entry) -> Vec<String> {
+    fn change(name: &str, old: String, new: String) -> Option<String> {
+        if old == new {
+            None
+        } else {
+            Some(format!("{}: {} -> {}", name, old, new))
+        }
+    }
+    fn show<T: Display>(t: Option<T>) -> String {
+        t.map_or("--".to_string(), |t| t.to_string())
+    }
+    fn show_expense(e: &Expense) -> String {
+        format!("{} ({})", e.date, e.amount)
+    }
+    fn show_absence(a: &Absence) -> String {
+        format!("{} ({}, {})", a.date, a.kind, format_dur(a.duration))
+    }
+
+    let mut changes: Vec<_> = vec![
+        change("id", show(old.id), show(new.id)),
+        change("start", show(old.start), show(new.start)),
+        change("stop", show(old.stop), show(new.stop)),
+        change("goal", format!("{:?}", old.goal), format!("{:?}", new.goal)),
+        change(
+            "result",
+            format!("{:?}", old.result),
+            format!("{:?}", new.result),
+        ),
+        change(
+            "estimate",
+            show(old.estimate.map(format_dur)),
+            show(new.estimate.map(format_dur)),
+        ),
+        change(
+            "client",
+            show(old.client.as_ref()),
+            show(new.client.as_ref()),
+        ),
+        change(
+            "billable",
+            old.billable.to_string(),
+            new.billable.to_string(),
+        ),
+        change("rate", show(old.rate.as_ref()), show(new.rate.as_ref())),
+        change("user", show(old.user.as_ref()), show(new.user.as_ref())),
+        change(
+            "expense",
+            show(old.expense.as_ref().map(show_expense)),
+            show(new.expense.as_ref().map(show_expense)),
+        ),
+        change(
+            "absence",
+            show(old.absence.as_ref().map(show_absence)),
+            show(new.absence.as_ref().map(show_absence)),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    for note in &old.notes {
+        if !new.notes.contains(note) {
+            changes.push(format!("note removed: {:?}", note));
+        }
+    }
+    for note in &new.notes {
+        if !old.notes.contains(note) {
+            changes.push(format!("note added: {:?}", note));
+        }
+    }
+    for tag in &old.tags {
+        if !new.tags.contains(tag) {
+            changes.push(format!("tag removed: {:?}", tag));
+        }
+    }
+    for tag in &new.tags {
+        if !old.tags.contains(tag) {
+            changes.push(format!("tag added: {:?}", tag));
+        }
+    }
+    for key in old.meta.keys().chain(new.meta.keys()).unique() {
+        if let Some(change) = change(
+            &format!("meta {}", key),
+            show(old.meta.get(key).map(|v| format!("{:?}", v))),
+            show(new.meta.get(key).map(|v| format!("{:?}", v))),
+        ) {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_changes_of_billing_fields() {
+        let old = Entry {
+            client: Some("acme".to_string()),
+            billable: true,
+            ..Entry::default()
+        };
+        let new = Entry {
+            client: Some("globex".to_string()),
+            ..old.clone()
+        };
+        assert_eq!(entry_changes(&old, &new), ["client: acme -> globex"]);
+
+        let new = Entry {
+            billable: false,
+            ..old.clone()
+        };
+        assert_eq!(entry_changes(&old, &new), ["billable: true -> false"]);
+
+        let new = Entry {
+            client: None,
+            ..old.clone()
+        };
+        assert_eq!(entry_changes(&old, &new), ["client: acme -> --"]);
+        assert!(entry_changes(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn entry_changes_of_lists_and_meta() {
+        let mut old = Entry {
+            notes: vec!["kept".to_string(), "dropped".to_string()],
+            tags: vec!["work".to_string()],
+            ..Entry::default()
+        };
+        old.meta.insert("project".to_string(), "site".to_string());
+        old.meta.insert("ticket".to_string(), "12".to_string());
+        let mut new = Entry {
+            notes: vec!["kept".to_string(), "new".to_string()],
+            tags: vec!["home".to_string()],
+            ..Entry::default()
+        };
+        new.meta.insert("project".to_string(), "app".to_string());
+        new.meta.insert("sprint".to_string(), "4".to_string());
+        assert_eq!(
+            entry_changes(&old, &new),
+            [
+                "note removed: \"dropped\"",
+                "note added: \"new\"",
+                "tag removed: \"work\"",
+                "tag added: \"home\"",
+                "meta project: \"site\" -> \"app\"",
+                "meta ticket: \"12\" -> --",
+                "meta sprint: -- -> \"4\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn match_entries_by_start() {
+        let at = |h: u32| {
+            let start = format!("2024-03-04T{:02}:00:00Z", h);
+            Some(chrono::DateTime::parse_from_rfc3339(&start).unwrap())
+        };
+        let entry = |start, goal: &str| Entry {
+            start,
+            goal: goal.to_string(),
+            ..Entry::default()
+        };
+        let old = [
+            entry(at(9), "a"),
+            entry(at(10), "b"),
+            entry(None, "c"),
+            entry(None, "d"),
+        ];
+        let new = [
+            entry(at(10), "b, edited"),
+            entry(None, "d"),
+            entry(at(11), "e"),
+            entry(None, "f"),
+        ];
+        let goals: Vec<_> = match_entries(&old, &new)
+            .into_iter()
+            .map(|(o, n)| (o.map(|e| e.goal.as_str()), n.map(|e| e.goal.as_str())))
+            .collect();
+        assert_eq!(
+            goals,
+            [
+                (Some("a"), None),
+                (Some("b"), Some("b, edited")),
+                (Some("c"), None),
+                (Some("d"), Some("d")),
+                (None, Some("e")),
+                (None, Some("f")),
+            ]
+        );
+    }
+}
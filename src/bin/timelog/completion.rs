@@ -0,0 +1,84 @@
+use crate::Opt;
+use itertools::Itertools;
+use structopt::{clap::Shell, StructOpt};
+
+/// The completion script for `shell`.
+///
+/// The scripts clap generates only know the commands and options, so for bash, zsh and fish
+/// they are extended to complete entry IDs, the keys of `--meta` and the projects of
+/// `--meta project=` from the log by running `timelog complete`.
+pub fn completion_script(shell: Shell) -> String {
+    let mut script = Vec::new();
+    Opt::clap().gen_completions_to("timelog", shell, &mut script);
+    let mut script = String::from_utf8(script).expect("completion scripts are UTF-8");
+    match shell {
+        Shell::Bash => script.push_str(BASH_COMPLETION),
+        Shell::Zsh => {
+            // The functions have to be defined before the script calls `_timelog` at its end.
+            script = script
+                .replace(ZSH_ENTRY_SPEC.0, ZSH_ENTRY_SPEC.1)
+                .lines()
+                .map(|line| match line.strip_prefix("'*--meta=[") {
+                    Some(_) => line.replacen("]'", "]:meta:_timelog_meta'", 1),
+                    None => line.to_string(),
+                })
+                .join("\n")
+                .replace("\n_timelog \"$@\"", ZSH_COMPLETION);
+        }
+        Shell::Fish => script.push_str(FISH_COMPLETION),
+        Shell::PowerShell | Shell::Elvish => {}
+    }
+    script
+}
+
+const BASH_COMPLETION: &str = r#"
+_timelog_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "show" || "$prev" == "delete" ]]; then
+        COMPREPLY=($(compgen -W "$(timelog complete ids 2>/dev/null)" -- "$cur"))
+    elif [[ "$prev" == "--meta" && "$cur" == project=* ]]; then
+        COMPREPLY=($(compgen -P project= -W "$(timelog complete projects 2>/dev/null)" -- "${cur#project=}"))
+    elif [[ "$prev" == "=" && "${COMP_WORDS[COMP_CWORD-2]}" == "project" && "${COMP_WORDS[COMP_CWORD-3]}" == "--meta" ]]; then
+        # `=` splits words in bash by default, so the project is a word of its own.
+        COMPREPLY=($(compgen -W "$(timelog complete projects 2>/dev/null)" -- "$cur"))
+    elif [[ "$prev" == "--meta" ]]; then
+        compopt -o nospace
+        COMPREPLY=($(compgen -S = -W "$(timelog complete meta-keys 2>/dev/null)" -- "$cur"))
+    else
+        _timelog "$@"
+    fi
+}
+
+complete -F _timelog_dynamic -o bashdefault -o default timelog
+"#;
+
+const ZSH_ENTRY_SPEC: (&str, &str) = (
+    "either an ID or a position like #3:_files'",
+    "either an ID or a position like #3:_timelog_ids'",
+);
+
+const ZSH_COMPLETION: &str = r#"
+(( $+functions[_timelog_ids] )) ||
+_timelog_ids() {
+    local ids; ids=(${(f)"$(timelog complete ids 2>/dev/null)"})
+    _describe 'entry ID' ids
+}
+
+(( $+functions[_timelog_meta] )) ||
+_timelog_meta() {
+    if compset -P 'project='; then
+        local projects; projects=(${(f)"$(timelog complete projects 2>/dev/null)"})
+        _describe 'project' projects
+    else
+        local keys; keys=(${(f)"$(timelog complete meta-keys 2>/dev/null)"})
+        _describe 'meta key' keys -S =
+    fi
+}
+
+_timelog "$@""#;
+
+const FISH_COMPLETION: &str = r#"
+complete -c timelog -n "__fish_seen_subcommand_from show delete" -f -a "(timelog complete ids 2>/dev/null)"
+complete -c timelog -l meta -f -a "(timelog complete meta-keys 2>/dev/null | string replace -r '$' =)"
+complete -c timelog -l meta -f -a "(timelog complete projects 2>/dev/null | string replace -r '^' project=)"
+"#;
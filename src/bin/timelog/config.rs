@@ -0,0 +1,392 @@
+use crate::{
+    args::DurationArg,
+    discord::Discord,
+    events::{HomeAssistant, Slack, Webhook},
+    invoice::Invoice,
+    mqtt::Mqtt,
+    output::ColorChoice,
+    Clock, Result,
+};
+use chrono::{Duration, Weekday};
+use log::{debug, info};
+use serde::de::{self, Deserializer};
+use serde_derive::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs, io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+use timelog::{AbsenceKind, DurationStyle, Entry, Language, Rate, Rounding, Template};
+
+/// Defaults for options, read from `~/.config/timelog/config.toml` or `$TIMELOG_CONFIG`.
+///
+/// Keys are named like the options they set, and options given on the command line win.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub log_file: Option<String>,
+    #[serde(deserialize_with = "parse_option")]
+    pub week_start: Option<Weekday>,
+    #[serde(deserialize_with = "parse_option")]
+    pub color: Option<ColorChoice>,
+    #[serde(deserialize_with = "parse_option")]
+    pub clock: Option<Clock>,
+    pub locale: Option<String>,
+    #[serde(deserialize_with = "parse_option")]
+    pub language: Option<Language>,
+    #[serde(deserialize_with = "parse_option")]
+    pub duration_style: Option<DurationStyle>,
+    /// Who new entries are recorded for, `$USER` by default.
+    user: Option<String>,
+    /// The tags new entries start with.
+    pub tags: Vec<String>,
+    /// The client of each project, for new entries with that `project` meta but no client, and
+    /// how each client is billed.
+    clients: BTreeMap<String, ClientConfig>,
+    /// Whether new entries are billable, by project or client, with `default` for the rest.
+    billable: BTreeMap<String, bool>,
+    /// The hourly rate of new entries, by project or client, with `default` for the rest.
+    rates: BTreeMap<String, Rate>,
+    /// How much time each project may take, by `project` meta, like `site = "80h"`.
+    pub budgets: BTreeMap<String, DurationArg>,
+    /// How much of its budget in percent a project may use before `timelog budget` warns about
+    /// it, 100 by default.
+    pub budget_threshold: Option<f64>,
+    /// How much time off may be taken each year, by kind, like `vacation = "200h"`.
+    pub time_off: BTreeMap<AbsenceKind, DurationArg>,
+    /// Whether to act like `--yes` is always given.
+    pub yes: bool,
+    /// Whether to split entries that ran past midnight into one entry per day when they are
+    /// stopped or repaired.
+    pub split_at_midnight: bool,
+    /// Whether to log when entries are started, stopped or annotated to the systemd journal, or
+    /// to syslog without one.
+    pub journal: bool,
+    pub webhooks: Vec<Webhook>,
+    pub notifications: Notifications,
+    pub idle: Idle,
+    pub suspend: Suspend,
+    pub git: Git,
+    pub slack: Option<Slack>,
+    pub discord: Option<Discord>,
+    pub mqtt: Option<Mqtt>,
+    pub home_assistant: Option<HomeAssistant>,
+    pub invoice: Invoice,
+}
+
+impl Config {
+    /// `$TIMELOG_CONFIG`, or else `$XDG_CONFIG_HOME/timelog/config.toml`, falling back to
+    /// `~/.config`.
+    fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("TIMELOG_CONFIG").filter(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    /// Who new entries are recorded for, from `user` or else `$USER`.
+    pub fn user(&self) -> Option<String> {
+        let user = self.user.clone().or_else(|| env::var("USER").ok());
+        user.filter(|u| !u.is_empty())
+    }
+
+    /// The client from `clients` for an entry with `meta`.
+    pub fn client_of(&self, meta: &BTreeMap<String, String>) -> Option<String> {
+        match self.clients.get(meta.get("project")?)? {
+            ClientConfig::Project(client) => Some(client.clone()),
+            ClientConfig::Client(_) => None,
+        }
+    }
+
+    /// How long the billable `entry` is billed as, after the rounding of its client.
+    pub fn billed(&self, entry: &Entry, duration: Duration) -> Duration {
+        let client = entry.client.as_ref().and_then(|c| self.clients.get(c));
+        match client {
+            Some(ClientConfig::Client(billing)) => billing.apply(duration),
+            _ => duration,
+        }
+    }
+
+    /// Whether a new `entry` is billable according to `billable`, going by its project first.
+    pub fn billable_for(&self, entry: &Entry) -> bool {
+        let project = entry.meta.get("project");
+        [project, entry.client.as_ref()]
+            .iter()
+            .flatten()
+            .find_map(|name| self.billable.get(name.as_str()))
+            .or_else(|| self.billable.get("default"))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// The hourly rate of a new `entry` according to `rates`, going by its project first.
+    pub fn rate_for(&self, entry: &Entry) -> Option<Rate> {
+        let project = entry.meta.get("project");
+        [project, entry.client.as_ref()]
+            .iter()
+            .flatten()
+            .find_map(|name| self.rates.get(name.as_str()))
+            .or_else(|| self.rates.get("default"))
+            .cloned()
+    }
+
+    /// Reads the config file, if there is one.
+    pub fn load() -> Result<Config> {
+        let path = match Config::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                info!("reading the config {}", path.display());
+                let mut config: Config =
+                    toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+                config.log_file = config.log_file.map(|f| expand_home(&f));
+                // MQTT only allows a password along with a username.
+                if let Some(Mqtt {
+                    username: None,
+                    password: Some(_),
+                    ..
+                }) = &config.mqtt
+                {
+                    Err(format!(
+                        "{}: the mqtt password needs a username",
+                        path.display()
+                    ))?;
+                }
+                Ok(config)
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e))?,
+        }
+    }
+}
+
+/// What `clients` in the config says about a name: the client of a project, like
+/// `site = "acme"`, or how a client is billed, like `acme = { round = "15m up" }`.
+#[derive(Debug, Clone)]
+enum ClientConfig {
+    Project(String),
+    Client(Billing),
+}
+
+impl<'de> serde::Deserialize<'de> for ClientConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = ClientConfig;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("the client of a project, or a table of how a client is billed")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<Self::Value, E> {
+                Ok(ClientConfig::Project(s.to_string()))
+            }
+
+            fn visit_map<A>(self, map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let map = de::value::MapAccessDeserializer::new(map);
+                Ok(ClientConfig::Client(serde::Deserialize::deserialize(map)?))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// How the billable time of a client is billed, per entry.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct Billing {
+    /// How the time of each entry is rounded, like `15m up`.
+    #[serde(deserialize_with = "parse_option")]
+    round: Option<Rounding>,
+    /// The least time an entry is billed as, like `30m`.
+    #[serde(deserialize_with = "parse_option")]
+    min_increment: Option<DurationArg>,
+}
+
+impl Billing {
+    fn apply(&self, duration: Duration) -> Duration {
+        let rounded = self.round.map_or(duration, |r| r.round(duration));
+        match self.min_increment {
+            Some(DurationArg(min)) => rounded.max(min),
+            None => rounded,
+        }
+    }
+}
+
+/// When to show desktop notifications, which needs timelog to be built with the `notify` feature.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Notifications {
+    /// Whether to show how long an entry took when it is stopped.
+    pub stop: bool,
+    /// Lets `timelog daemon` notify every time the running entry has run this long again.
+    #[serde(deserialize_with = "parse_option")]
+    pub every: Option<DurationArg>,
+    /// How long an entry may run before `timelog remind` and `timelog daemon` remind you of it.
+    #[serde(deserialize_with = "parse_option")]
+    pub remind_after: Option<DurationArg>,
+}
+
+/// What `timelog daemon` does with the running entry once the session has been idle for a
+/// while, which needs timelog to be built with the `idle` feature.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Idle {
+    /// How long the session has to be idle, according to logind.
+    #[serde(deserialize_with = "parse_option")]
+    pub after: Option<DurationArg>,
+    pub action: IdleAction,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdleAction {
+    /// Stops the entry at the time the session became idle.
+    #[default]
+    Stop,
+    /// Only marks the entry for review, with the time the session became idle as its
+    /// `idle-since` meta.
+    Review,
+}
+
+/// What happens to the running entry when the system goes to sleep.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Suspend {
+    /// Whether `timelog daemon` listens to logind to stop the entry itself, which needs timelog to
+    /// be built with the `idle` feature. Otherwise, `timelog on-suspend` can be used as a
+    /// systemd-sleep hook.
+    pub daemon: bool,
+    /// Whether to start the entry again when waking up.
+    pub resume: bool,
+}
+
+/// The meta and tag that `start` derives from the git repository it is run in.
+///
+/// Both are templates with the fields `repo`, the name of the repository, and `branch`, the
+/// branch that is checked out. A template using `branch` is skipped on a detached HEAD.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Git {
+    /// The `project` meta, like `{repo}`.
+    #[serde(deserialize_with = "parse_option")]
+    project: Option<Template>,
+    /// A tag, like `branch:{branch}`.
+    #[serde(deserialize_with = "parse_option")]
+    tag: Option<Template>,
+}
+
+impl Git {
+    /// Adds the `project` meta and the tag to `entry` if the working directory is in a git
+    /// repository, keeping a `project` that was already given.
+    pub fn apply(&self, entry: &mut Entry) -> Result<()> {
+        if self.project.is_none() && self.tag.is_none() {
+            return Ok(());
+        }
+        let (repo, branch) = match git_repo() {
+            Some(repo) => repo,
+            None => return Ok(()),
+        };
+        let render = |template: &Option<Template>| match template {
+            Some(template) => render_git(template, &repo, branch.as_deref()),
+            None => Ok(None),
+        };
+        if let Some(project) = render(&self.project)? {
+            entry.meta.entry("project".to_string()).or_insert(project);
+        }
+        if let Some(tag) = render(&self.tag)? {
+            if !entry.tags.contains(&tag) {
+                entry.tags.push(tag);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fills in `template` with the name and branch of a git repository, or returns `None` if it
+/// needs a branch and there is none.
+fn render_git(template: &Template, repo: &str, branch: Option<&str>) -> Result<Option<String>> {
+    for (name, _) in template.fields() {
+        match name {
+            "repo" => {}
+            "branch" if branch.is_none() => return Ok(None),
+            "branch" => {}
+            _ => Err(format!(
+                "`{}` is not a git field, expected repo or branch",
+                name
+            ))?,
+        }
+    }
+    let value = template.render(|name, _| match name {
+        "repo" => repo.to_string(),
+        _ => branch.unwrap_or("").to_string(),
+    });
+    Ok(Some(value))
+}
+
+/// The name of the git repository of the working directory and its branch, unless the HEAD is
+/// detached, or `None` outside of a repository.
+fn git_repo() -> Option<(String, Option<String>)> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel", "--abbrev-ref", "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let output = String::from_utf8(output.stdout).ok()?;
+    let mut lines = output.lines();
+    let top = PathBuf::from(lines.next()?);
+    let repo = top.file_name()?.to_string_lossy().into_owned();
+    let branch = lines.next().filter(|&b| b != "HEAD").map(str::to_string);
+    debug!("in the git repository {} on {:?}", repo, branch);
+    Some((repo, branch))
+}
+
+/// `$XDG_CONFIG_HOME/timelog`, falling back to `~/.config/timelog`.
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("timelog"))
+}
+
+/// Parses an optional config value with the same `FromStr` implementation as its option.
+pub fn parse_option<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    value
+        .map(|s| s.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Replaces a leading `~/` with the home directory.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        _ => path.to_string(),
+    }
+}
+
+/// The first locale, like `de_DE.UTF-8`, that is set in one of the environment variables `names`.
+pub fn env_locale(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
@@ -0,0 +1,191 @@
+use crate::{
+    args::DurationArg, config::Config, events::notify, file::last_entry, first_line,
+    idle::handle_idle,
+};
+use chrono::{Duration, Local};
+use log::warn;
+use std::thread;
+use timelog::{format_dur, Entry};
+#[cfg(unix)]
+use {
+    crate::{
+        file::{get_file_reader, is_stdio, report_broken, save_entries, WriteOptions},
+        Result,
+    },
+    log::{debug, info},
+    std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixStream,
+        time::SystemTime,
+    },
+    timelog::{read_entries, read_entries_lenient, Client, Entries, Request, Response},
+};
+
+/// Watches the running entry for `timelog daemon`, checking every minute.
+///
+/// It is notified about each time it has run for another `every`, and once it has run for longer
+/// than `remind-after`. It is also stopped or marked once the session has been idle for too long.
+/// This runs alongside the daemon, so it reads the log through the daemon.
+pub fn watch_running(log_file: &str, lenient: bool, config: &Config) {
+    let every = config.notifications.every.map(|DurationArg(d)| d);
+    let remind_after = config.notifications.remind_after.map(|DurationArg(d)| d);
+    // The entry that was last notified about, after how many `every`s and whether it was
+    // reminded of.
+    let mut notified = (None, 0, false);
+    loop {
+        thread::sleep(std::time::Duration::from_secs(60));
+        let entry = match last_entry(log_file, lenient) {
+            Ok(Some(entry)) if entry.is_active() => entry,
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("{}", e);
+                continue;
+            }
+        };
+        let running = match entry.start {
+            Some(start) => Local::now().fixed_offset() - start,
+            None => continue,
+        };
+        if let Err(e) = handle_idle(log_file, config, &entry) {
+            warn!("{}", e);
+        }
+        if notified.0 != entry.id {
+            notified = (entry.id, 0, false);
+        }
+        if let Some(remind_after) = remind_after.filter(|&after| running > after) {
+            if !notified.2 {
+                notified.2 = true;
+                notify("Forgot to stop?", &overdue(&entry, running, remind_after));
+                continue;
+            }
+        }
+        let milestone = every.map_or(0, |every| running.num_seconds() / every.num_seconds());
+        if milestone > notified.1 {
+            notified.1 = milestone;
+            let summary = format!("Still running: {}", first_line(&entry.goal));
+            notify(&summary, &format!("Running for {}", format_dur(running)));
+        }
+    }
+}
+
+/// Says that `entry` has been `running` for longer than `limit`.
+pub fn overdue(entry: &Entry, running: Duration, limit: Duration) -> String {
+    format!(
+        "\"{}\" has been running for {}, longer than {}",
+        first_line(&entry.goal),
+        format_dur(running),
+        format_dur(limit)
+    )
+}
+
+/// Connects to the daemon keeping the log loaded, if one is running.
+#[cfg(unix)]
+pub fn daemon_client(filename: &str) -> Option<Client> {
+    if is_stdio(filename) {
+        return None;
+    }
+    Client::connect(Client::path_for(filename)).ok()
+}
+
+/// The log as kept by `timelog daemon`.
+///
+/// The log is only read again when the file changed, like when a command that rewrites the
+/// whole log was run.
+#[cfg(unix)]
+pub struct Daemon {
+    log_file: String,
+    lenient: bool,
+    entries: Entries,
+    /// The length and modification time of the log when it was last read or written.
+    version: Option<(u64, SystemTime)>,
+}
+
+#[cfg(unix)]
+impl Daemon {
+    pub fn load(log_file: &str, lenient: bool) -> Result<Daemon> {
+        let mut daemon = Daemon {
+            log_file: log_file.to_string(),
+            lenient,
+            entries: Entries::new(),
+            version: None,
+        };
+        daemon.reload()?;
+        Ok(daemon)
+    }
+
+    fn file_version(&self) -> Option<(u64, SystemTime)> {
+        let meta = fs::metadata(&self.log_file).ok()?;
+        Some((meta.len(), meta.modified().ok()?))
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let reader = get_file_reader(&self.log_file)?;
+        self.entries = match reader {
+            Some(reader) if self.lenient => {
+                let recovered = read_entries_lenient(reader)?;
+                report_broken(&recovered.broken);
+                recovered.entries.into_iter().collect()
+            }
+            reader => read_entries(reader)?,
+        };
+        self.version = self.file_version();
+        info!(
+            "loaded {} entries from {}",
+            self.entries.len(),
+            self.log_file
+        );
+        Ok(())
+    }
+
+    /// Answers the requests sent over `stream` until the client disconnects.
+    pub fn serve(&mut self, stream: UnixStream) -> Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle(request),
+                Err(e) => Err(e.into()),
+            };
+            let response = response.unwrap_or_else(|e| Response::Error {
+                message: e.to_string(),
+            });
+            let mut line = serde_json::to_vec(&response)?;
+            line.push(b'\n');
+            writer.write_all(&line)?;
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, request: Request) -> Result<Response> {
+        debug!("handling {:?}", request);
+        if self.file_version() != self.version {
+            self.reload()?;
+        }
+        Ok(match request {
+            Request::Last => Response::Last {
+                entry: self.entries.last().cloned().map(Box::new),
+            },
+            Request::Entries => Response::Entries {
+                entries: self.entries.to_vec(),
+            },
+            Request::ReplaceLast { last, entries } => {
+                if self.entries.last() != last.as_deref() {
+                    Err("the log changed in the meantime, try again")?;
+                }
+                let mut new = self.entries.clone();
+                new.pop();
+                new.extend(entries);
+                // The client checked the lock already, with its own --force-unlock.
+                let write = WriteOptions {
+                    force_unlock: true,
+                    ..WriteOptions::default()
+                };
+                save_entries(&self.log_file, &self.entries, new.clone(), write)?;
+                self.entries = new;
+                self.version = self.file_version();
+                Response::Done
+            }
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use serde_derive::Deserialize;
+#[cfg(unix)]
+use {
+    crate::{file::last_entry, first_line, status::shorten, Result},
+    chrono::Local,
+    log::{debug, info, warn},
+    std::{
+        env,
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        path::PathBuf,
+        thread,
+    },
+    timelog::Entry,
+};
+
+/// Shows the running entry as the Discord Rich Presence while `timelog daemon` runs, with the
+/// time it has been running.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Discord {
+    /// The ID of a Discord application, whose name is shown above the goal.
+    client_id: String,
+}
+
+/// A connection to the Rich Presence socket of the Discord client.
+#[cfg(unix)]
+struct DiscordIpc {
+    stream: UnixStream,
+}
+
+#[cfg(unix)]
+impl DiscordIpc {
+    const HANDSHAKE: u32 = 0;
+    const FRAME: u32 = 1;
+    const CLOSE: u32 = 2;
+
+    /// Connects to the Discord client as the application `client_id`.
+    fn connect(client_id: &str) -> Result<DiscordIpc> {
+        let dir = ["XDG_RUNTIME_DIR", "TMPDIR"]
+            .iter()
+            .find_map(|name| env::var_os(name).filter(|d| !d.is_empty()))
+            .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+        // Discord takes the first free socket of ten, so several clients can run at once.
+        let stream = (0..10)
+            .find_map(|i| UnixStream::connect(dir.join(format!("discord-ipc-{}", i))).ok())
+            .ok_or("Discord isn't running")?;
+        let mut ipc = DiscordIpc { stream };
+        let handshake = serde_json::json!({ "v": 1, "client_id": client_id });
+        ipc.send(DiscordIpc::HANDSHAKE, &handshake)?;
+        ipc.receive()?;
+        Ok(ipc)
+    }
+
+    /// Shows `entry` as the activity, or clears it for `None`.
+    fn set_activity(&mut self, entry: Option<&Entry>) -> Result<()> {
+        let activity = entry.map(|entry| {
+            serde_json::json!({
+                "details": shorten(first_line(&entry.goal), 128),
+                "timestamps": { "start": entry.start.map(|s| s.timestamp()) },
+            })
+        });
+        let command = serde_json::json!({
+            "cmd": "SET_ACTIVITY",
+            "args": { "pid": std::process::id(), "activity": activity },
+            "nonce": Local::now().timestamp_nanos_opt().unwrap_or_default().to_string(),
+        });
+        self.send(DiscordIpc::FRAME, &command)?;
+        let response = self.receive()?;
+        if response["evt"] == "ERROR" {
+            let message = response["data"]["message"].as_str();
+            let message = message.unwrap_or("an unknown error");
+            Err(format!("Discord said {}", message))?;
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, opcode: u32, payload: &serde_json::Value) -> Result<()> {
+        use std::convert::TryFrom;
+
+        let payload = payload.to_string();
+        let mut frame = opcode.to_le_bytes().to_vec();
+        frame.extend(u32::try_from(payload.len())?.to_le_bytes());
+        frame.extend(payload.as_bytes());
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<serde_json::Value> {
+        let mut header = [0; 8];
+        self.stream.read_exact(&mut header)?;
+        let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut payload = vec![0; length as usize];
+        self.stream.read_exact(&mut payload)?;
+        let payload: serde_json::Value = serde_json::from_slice(&payload)?;
+        if opcode == DiscordIpc::CLOSE {
+            let message = payload["message"].as_str().unwrap_or("no reason");
+            Err(format!("Discord closed the connection: {}", message))?;
+        }
+        Ok(payload)
+    }
+}
+
+/// Keeps the Discord Rich Presence up to date with the running entry, connecting to Discord
+/// again whenever it was closed.
+///
+/// This runs alongside the daemon, so it reads the log through the daemon.
+#[cfg(unix)]
+pub fn watch_discord(log_file: &str, lenient: bool, discord: &Discord) {
+    loop {
+        let mut ipc = match DiscordIpc::connect(&discord.client_id) {
+            Ok(ipc) => ipc,
+            Err(e) => {
+                debug!("can't connect to Discord: {}", e);
+                thread::sleep(std::time::Duration::from_secs(60));
+                continue;
+            }
+        };
+        info!("connected to Discord");
+        // The ID of the entry that is shown, if any, once something was shown.
+        let mut shown = None;
+        loop {
+            let entry = match last_entry(log_file, lenient) {
+                Ok(entry) => entry.filter(|e| e.is_active()),
+                Err(e) => {
+                    warn!("{}", e);
+                    None
+                }
+            };
+            let id = entry.as_ref().map(|e| e.id);
+            if shown != Some(id) {
+                if let Err(e) = ipc.set_activity(entry.as_ref()) {
+                    warn!("the Discord presence couldn't be updated: {}", e);
+                    break;
+                }
+                shown = Some(id);
+            }
+            thread::sleep(std::time::Duration::from_secs(15));
+        }
+    }
+}
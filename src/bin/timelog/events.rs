@@ -0,0 +1,351 @@
+#[cfg(feature = "slack")]
+use crate::status::shorten;
+use crate::{
+    config::{config_dir, Config},
+    first_line,
+    journal::log_to_journal,
+    status::entry_json,
+    Result,
+};
+use chrono::Local;
+use log::{debug, warn};
+use serde_derive::Deserialize;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use timelog::{format_dur, Entry};
+#[cfg(feature = "http")]
+use {
+    hmac::{Hmac, KeyInit, Mac},
+    log::info,
+    sha2::Sha256,
+    std::thread,
+};
+
+/// Shows a desktop notification, or only logs it when timelog was built without them.
+pub fn notify(summary: &str, body: &str) {
+    debug!("notifying: {}: {}", summary, body);
+    #[cfg(feature = "notify")]
+    {
+        let shown = notify_rust::Notification::new()
+            .appname("timelog")
+            .summary(summary)
+            .body(body)
+            .show();
+        if let Err(e) = shown {
+            warn!("the notification couldn't be shown: {}", e);
+        }
+    }
+}
+
+/// Something that happened to an entry, which hooks and webhooks are told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+    Start,
+    Stop,
+    Note,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Start => "start",
+            Event::Stop => "stop",
+            Event::Note => "note",
+        }
+    }
+}
+
+/// A URL that is sent `{"event": ..., "entry": ...}` as JSON when an entry is started, stopped
+/// or annotated.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+pub struct Webhook {
+    url: String,
+    /// Signs each payload with an `X-Timelog-Signature: sha256=<hex>` header, the HMAC-SHA256 of
+    /// the body.
+    secret: Option<String>,
+    /// The events to send, or every event.
+    events: Option<Vec<Event>>,
+}
+
+impl Webhook {
+    /// How often a payload is sent before giving up, waiting twice as long after every failure.
+    #[cfg(feature = "http")]
+    const ATTEMPTS: u32 = 3;
+    /// How long one attempt may take, so a stuck server doesn't hang the command.
+    pub const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+    #[cfg(feature = "http")]
+    fn send(&self, body: &str) -> Result<()> {
+        let signature = match &self.secret {
+            Some(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+                mac.update(body.as_bytes());
+                let hex: String = mac
+                    .finalize()
+                    .into_bytes()
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect();
+                Some(format!("sha256={}", hex))
+            }
+            None => None,
+        };
+        for attempt in 1.. {
+            let mut request = ureq::post(&self.url)
+                .config()
+                .timeout_global(Some(Webhook::TIMEOUT))
+                .build()
+                .header("Content-Type", "application/json");
+            if let Some(signature) = &signature {
+                request = request.header("X-Timelog-Signature", signature);
+            }
+            match request.send(body) {
+                Ok(_) => break,
+                Err(e) if attempt < Webhook::ATTEMPTS => {
+                    info!("sending to {} failed, trying again: {}", self.url, e);
+                    thread::sleep(std::time::Duration::from_secs(1 << (attempt - 1)));
+                }
+                Err(e) => Err(e)?,
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn send(&self, _body: &str) -> Result<()> {
+        Err("webhooks need timelog to be built with the http feature")?
+    }
+}
+
+/// The `token` from the `[section]` of the config, or else what its `token_command` prints.
+#[cfg(feature = "http")]
+fn secret(section: &str, token: &Option<String>, token_command: &Option<String>) -> Result<String> {
+    if let Some(token) = token {
+        return Ok(token.clone());
+    }
+    let command = token_command
+        .as_ref()
+        .ok_or_else(|| format!("the [{}] config needs a token or a token-command", section))?;
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        Err(format!("`{}` failed with {}", command, output.status))?;
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// The Home Assistant entity that `timelog sync hass` keeps up to date with the running entry.
+///
+/// Its state is `running` or `idle`, with the entry in its attributes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+pub struct HomeAssistant {
+    /// Where Home Assistant runs, like `http://homeassistant.local:8123`.
+    url: String,
+    /// A long-lived access token.
+    token: Option<String>,
+    /// A command that prints the token, to keep it in a keyring instead of the config.
+    token_command: Option<String>,
+    /// The entity to set, `sensor.timelog` by default.
+    entity: Option<String>,
+}
+
+impl HomeAssistant {
+    /// Sets the state of the entity to the running `entry`, or to idle for `None`.
+    #[cfg(feature = "http")]
+    pub fn push(&self, entry: Option<&Entry>) -> Result<()> {
+        let token = secret("home-assistant", &self.token, &self.token_command)?;
+        let entity = self.entity.as_deref().unwrap_or("sensor.timelog");
+        let state = match entry {
+            Some(entry) => {
+                let mut attributes = entry_json(entry, Local::now());
+                attributes["friendly_name"] = "Time log".into();
+                attributes["icon"] = "mdi:timer".into();
+                serde_json::json!({ "state": "running", "attributes": attributes })
+            }
+            None => serde_json::json!({
+                "state": "idle",
+                "attributes": { "friendly_name": "Time log", "icon": "mdi:timer-off" },
+            }),
+        };
+        let url = format!("{}/api/states/{}", self.url.trim_end_matches('/'), entity);
+        ureq::post(&url)
+            .config()
+            .timeout_global(Some(Webhook::TIMEOUT))
+            .build()
+            .header("Authorization", &format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .send(state.to_string())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "http"))]
+    pub fn push(&self, _entry: Option<&Entry>) -> Result<()> {
+        Err("Home Assistant needs timelog to be built with the http feature")?
+    }
+}
+
+/// Shows the goal of the running entry as the Slack status, which needs timelog to be built with
+/// the `slack` feature.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Slack {
+    /// A user token with the `users.profile:write` scope.
+    token: Option<String>,
+    /// A command that prints the token, like `secret-tool lookup service slack`, to keep it in a
+    /// keyring instead of the config.
+    token_command: Option<String>,
+    /// The emoji of the status, like `:hammer_and_wrench:`.
+    emoji: Option<String>,
+}
+
+#[cfg(feature = "slack")]
+impl Slack {
+    const URL: &'static str = "https://slack.com/api/users.profile.set";
+
+    /// Shows `entry` as the status, or clears it for `None`.
+    fn set_status(&self, entry: Option<&Entry>) -> Result<()> {
+        let token = secret("slack", &self.token, &self.token_command)?;
+        let (text, emoji) = match entry {
+            // Slack cuts statuses off at 100 characters.
+            Some(entry) => (
+                shorten(first_line(&entry.goal), 100),
+                self.emoji.as_deref().unwrap_or(""),
+            ),
+            None => (String::new(), ""),
+        };
+        let body = serde_json::json!({
+            "profile": {
+                "status_text": text,
+                "status_emoji": emoji,
+                "status_expiration": 0,
+            },
+        });
+        let response = ureq::post(Slack::URL)
+            .config()
+            .timeout_global(Some(Webhook::TIMEOUT))
+            .build()
+            .header("Authorization", &format!("Bearer {}", token))
+            .header("Content-Type", "application/json; charset=utf-8")
+            .send(body.to_string())?
+            .body_mut()
+            .read_to_string()?;
+        // Slack answers errors with a 200 as well.
+        let response: serde_json::Value = serde_json::from_str(&response)?;
+        if response["ok"] != true {
+            let error = response["error"].as_str().unwrap_or("an unknown error");
+            Err(format!("Slack said {}", error))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "slack"))]
+impl Slack {
+    fn set_status(&self, _entry: Option<&Entry>) -> Result<()> {
+        Err("the Slack status needs timelog to be built with the slack feature")?
+    }
+}
+
+/// Runs the hook named like `pre-start` from `~/.config/timelog/hooks`, if there is one, with
+/// `entry` as JSON on stdin.
+///
+/// Hooks also get the log file in `$TIMELOG_FILE` and their own name in `$TIMELOG_HOOK`. A hook
+/// that fails is an error.
+pub fn run_hook(log_file: &str, when: &str, event: Event, entry: &Entry) -> Result<()> {
+    let name = format!("{}-{}", when, event.name());
+    let path = match config_dir() {
+        Some(dir) => dir.join("hooks").join(&name),
+        None => return Ok(()),
+    };
+    if !path.is_file() {
+        return Ok(());
+    }
+    debug!("running the hook {}", path.display());
+    let mut child = Command::new(&path)
+        .env("TIMELOG_FILE", log_file)
+        .env("TIMELOG_HOOK", &name)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("the hook {} can't be run: {}", path.display(), e))?;
+    let json = entry_json(entry, Local::now());
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks don't have to read the entry, so they may have closed stdin already.
+        let _ = writeln!(stdin, "{}", json);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        Err(format!("the hook {} failed with {}", name, status))?;
+    }
+    Ok(())
+}
+
+/// Tells the post-hook, webhooks and notifications for `event` that it happened to `entry`.
+///
+/// Failures are only warned about, since the log was already changed.
+pub fn announce(log_file: &str, config: &Config, event: Event, entry: &Entry) {
+    if let Err(e) = run_hook(log_file, "post", event, entry) {
+        warn!("{}", e);
+    }
+    if config.journal {
+        if let Err(e) = log_to_journal(event, entry) {
+            warn!("the event couldn't be logged to the journal: {}", e);
+        }
+    }
+    send_webhooks(&config.webhooks, event, entry);
+    if let Some(mqtt) = &config.mqtt {
+        debug!("publishing the {} event to {}", event.name(), mqtt.broker);
+        if let Err(e) = mqtt.publish(&event_json(event, entry)) {
+            warn!("the event couldn't be published to {}: {}", mqtt.broker, e);
+        }
+    }
+    if let Some(slack) = &config.slack {
+        let result = match event {
+            Event::Start => slack.set_status(Some(entry)),
+            Event::Stop => slack.set_status(None),
+            Event::Note => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("the Slack status couldn't be updated: {}", e);
+        }
+    }
+    if event == Event::Stop && config.notifications.stop {
+        let took = entry.duration().map_or_else(String::new, format_dur);
+        let summary = format!("Stopped: {}", first_line(&entry.goal));
+        notify(&summary, &format!("Took {}", took));
+    }
+}
+
+/// Tells the `webhooks` that want to know about `event` that it happened to `entry`.
+///
+/// Webhooks that fail are only warned about, since the log was already changed.
+fn send_webhooks(webhooks: &[Webhook], event: Event, entry: &Entry) {
+    let body = event_json(event, entry);
+    for webhook in webhooks {
+        if webhook.events.as_ref().is_some_and(|e| !e.contains(&event)) {
+            continue;
+        }
+        debug!("sending the {} event to {}", event.name(), webhook.url);
+        if let Err(e) = webhook.send(&body) {
+            warn!("the webhook {} failed: {}", webhook.url, e);
+        }
+    }
+}
+
+/// What webhooks and MQTT are sent about `event`, as `{"event": ..., "entry": ...}`.
+fn event_json(event: Event, entry: &Entry) -> String {
+    serde_json::json!({
+        "event": event.name(),
+        "entry": entry_json(entry, Local::now()),
+    })
+    .to_string()
+}
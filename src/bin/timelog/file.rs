@@ -0,0 +1,358 @@
+#[cfg(unix)]
+use crate::daemon::daemon_client;
+use crate::{
+    changes::{match_entries, print_changes},
+    first_line, Result,
+};
+use itertools::{Either, Itertools};
+use log::{debug, info};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter::once,
+};
+use timelog::{
+    read_entries, read_entries_iter, read_entries_lenient, write_entries, AuditRecord, BrokenEntry,
+    Checksum, Entries, Entry, EntryIter, Index, Lock, Recovered, Tail, Timelog, Timezone,
+};
+
+/// Reads the latest entry of the log, only reading the end of the file when possible.
+pub fn last_entry(filename: &str, lenient: bool) -> Result<Option<Entry>> {
+    #[cfg(unix)]
+    if let Some(mut client) = daemon_client(filename) {
+        return Ok(client.last()?);
+    }
+    if !is_stdio(filename) {
+        match File::open(filename) {
+            Ok(mut file) => {
+                if let Some(tail) = Tail::read(&mut file)? {
+                    return Ok(Some(tail.entry));
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        }
+    }
+    Ok(get_entry_iter(filename, lenient)?.last().transpose()?)
+}
+
+/// Lets `f` change the last entry of the log, or add entries after it.
+///
+/// `f` is given a log holding only the last entry. When the log was written by us and the result
+/// keeps it sorted, only the tail of the file is rewritten. Otherwise the whole log is read and
+/// written back.
+pub fn update_last_entry<F>(filename: &str, write: WriteOptions, f: F) -> Result<()>
+where
+    F: FnOnce(&mut Timelog) -> Result<()>,
+{
+    if is_stdio(filename) {
+        Err("stdin is needed for input, so the log can't be read from it")?;
+    }
+    let reserved = highest_id_without_start(filename)?;
+
+    #[cfg(unix)]
+    if let Some(mut client) = daemon_client(filename) {
+        let last_entry = client.last()?;
+        let mut log: Timelog = last_entry.clone().into_iter().collect();
+        log.reserve_ids(reserved);
+        f(&mut log)?;
+        let new_entries = log.into_entries().into_vec();
+        let old = last_entry.as_slice();
+        check_lock(filename, old, &new_entries, write.force_unlock)?;
+        if write.dry_run {
+            print_changes(old, &new_entries);
+            println!("Would write {}", filename);
+        } else {
+            client.replace_last(last_entry, new_entries)?;
+        }
+        return Ok(());
+    }
+
+    let tail = match OpenOptions::new().read(true).write(true).open(filename) {
+        Ok(mut file) => Tail::read(&mut file)?.map(|tail| (file, tail)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => Err(e)?,
+    };
+
+    let index_path = Index::path_for(filename);
+    let index = Index::read(&index_path)
+        .ok()
+        .flatten()
+        .filter(|index| index.is_fresh(filename));
+
+    let (last_entry, new_entries) = if let Some((mut file, tail)) = tail {
+        let last_entry = tail.entry.clone();
+        let mut log = Timelog::from(vec![last_entry.clone()]);
+        log.reserve_ids(reserved);
+        f(&mut log)?;
+        let new_entries = log.into_entries().into_vec();
+        let old = std::slice::from_ref(&last_entry);
+        check_lock(filename, old, &new_entries, write.force_unlock)?;
+        let sorted = new_entries.windows(2).all(|w| w[0] <= w[1])
+            && new_entries.first().is_none_or(|e| *e >= last_entry);
+
+        if write.dry_run {
+            print_changes(old, &new_entries);
+            println!("Would write {}", filename);
+            return Ok(());
+        } else if sorted {
+            info!("rewriting only the last entry of {}", filename);
+            tail.replace(&mut file, &new_entries)?;
+            Checksum::touch(filename)?;
+            audit(filename, old, &new_entries)?;
+        } else {
+            info!(
+                "the entries moved out of order, so all of {} is rewritten",
+                filename
+            );
+            let old = read_entries(get_file_reader(filename)?)?;
+            let mut entries = old.clone();
+            entries.pop();
+            entries.extend(new_entries.iter().cloned());
+            save_entries(filename, &old, entries, write)?;
+        }
+        (Some(last_entry), new_entries)
+    } else {
+        let old = read_entries(get_file_reader(filename)?)?;
+        let mut entries = old.clone();
+        let last_entry = entries.pop();
+        let mut log: Timelog = last_entry.clone().into_iter().collect();
+        log.reserve_ids(reserved);
+        f(&mut log)?;
+        let new_entries = log.into_entries().into_vec();
+        entries.extend(new_entries.iter().cloned());
+        save_entries(filename, &old, entries, write)?;
+        (last_entry, new_entries)
+    };
+
+    if write.dry_run {
+        return Ok(());
+    }
+    if let Some(mut index) = index {
+        if let Some(last_entry) = &last_entry {
+            index.remove(last_entry);
+        }
+        for e in &new_entries {
+            index.add(e);
+        }
+        index.write(&index_path, filename)?;
+    }
+    Ok(())
+}
+
+/// The highest ID of the entries without a start, like expenses and absences.
+///
+/// They sort before the entries with one, so only the beginning of the log is read, which the
+/// commands that only read the end of it would miss.
+fn highest_id_without_start(filename: &str) -> Result<u64> {
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => Err(e)?,
+    };
+    let mut highest = 0;
+    for e in read_entries_iter(file) {
+        let e = e?;
+        if e.start.is_some() {
+            break;
+        }
+        highest = highest.max(e.id.unwrap_or(0));
+    }
+    Ok(highest)
+}
+
+/// Loads the summary index of the log for `timezone`, rebuilding it if it is missing or out of
+/// date.
+pub fn get_index(filename: &str, lenient: bool, timezone: Timezone) -> Result<Index> {
+    let path = Index::path_for(filename);
+    if is_stdio(filename) {
+        let mut index = Index::new(timezone);
+        for e in get_entry_iter(filename, lenient)? {
+            index.add(&e?);
+        }
+        return Ok(index);
+    }
+
+    if let Some(index) = Index::read(&path).ok().flatten() {
+        if index.is_fresh(filename) && index.timezone() == timezone {
+            return Ok(index);
+        }
+        info!("the index is out of date, rebuilding it");
+    }
+
+    let mut index = Index::new(timezone);
+    for e in get_entry_iter(filename, lenient)? {
+        index.add(&e?);
+    }
+    // The index is only a cache, so failing to save it shouldn't fail the summary.
+    let _ = index.write(&path, filename);
+    Ok(index)
+}
+
+/// Whether `filename` refers to stdin and stdout rather than an actual file.
+pub fn is_stdio(filename: &str) -> bool {
+    filename == "-"
+}
+
+pub fn get_file_reader(filename: &str) -> Result<Option<Box<dyn BufRead>>> {
+    if is_stdio(filename) {
+        return Ok(Some(Box::new(io::stdin().lock())));
+    }
+
+    let reader = File::open(filename);
+
+    if let Err(e) = reader {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            debug!("{} doesn't exist yet", filename);
+            Ok(None)
+        } else {
+            Err(e)?
+        }
+    } else {
+        debug!("opened {}", filename);
+        Ok(Some(Box::new(BufReader::new(reader?))))
+    }
+}
+
+/// Reads the entries of the log, from the daemon if one keeps it loaded.
+pub fn get_entry_iter(
+    filename: &str,
+    lenient: bool,
+) -> Result<impl Iterator<Item = timelog::Result<Entry>>> {
+    #[cfg(unix)]
+    if let Some(mut client) = daemon_client(filename) {
+        return Ok(Either::Left(client.entries()?.into_iter().map(Ok)));
+    }
+    let reader = get_file_reader(filename)?;
+    if lenient {
+        let recovered = match reader {
+            Some(reader) => read_entries_lenient(reader)?,
+            None => Recovered::default(),
+        };
+        report_broken(&recovered.broken);
+        info!(
+            "loaded {} entries, skipping {} broken ones",
+            recovered.entries.len(),
+            recovered.broken.len()
+        );
+        Ok(Either::Left(recovered.entries.into_iter().map(Ok)))
+    } else {
+        Ok(Either::Right(
+            reader.map(EntryIter::new).into_iter().flatten(),
+        ))
+    }
+}
+
+pub fn report_broken(broken: &[BrokenEntry]) {
+    for b in broken {
+        eprintln!(
+            "warning: skipping broken entry at line {}, column {}: {}",
+            b.line, b.column, b.error
+        );
+    }
+}
+
+/// How a command writes the log, from the global flags.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Only print the changes instead of writing them.
+    pub dry_run: bool,
+    /// Change entries in the days locked with `timelog lock` anyway.
+    pub force_unlock: bool,
+}
+
+/// Writes `entries` to the log in place of the `old` entries that were read from it, and updates
+/// its checksum and audit trail.
+///
+/// In a dry run, the changes are only printed.
+pub fn save_entries(
+    filename: &str,
+    old: &[Entry],
+    entries: Entries,
+    write: WriteOptions,
+) -> Result<()> {
+    check_lock(filename, old, &entries, write.force_unlock)?;
+    if write.dry_run {
+        print_changes(old, &entries);
+        println!("Would write {}", filename);
+        return Ok(());
+    }
+    if is_stdio(filename) {
+        write_entries(get_file_writer(filename)?, &entries)?;
+        println!();
+        return Ok(());
+    }
+
+    info!("writing {} entries to {}", entries.len(), filename);
+    write_entries(get_file_writer(filename)?, &entries)?;
+    Checksum::update(filename)?;
+    audit(filename, old, &entries)
+}
+
+/// Fails if going from the `old` to the `new` entries of the log changes an entry in the days
+/// locked with `timelog lock`, unless `force_unlock` is set.
+pub fn check_lock(filename: &str, old: &[Entry], new: &[Entry], force_unlock: bool) -> Result<()> {
+    if is_stdio(filename) || force_unlock {
+        return Ok(());
+    }
+    let lock = match Lock::read(Lock::path_for(filename))? {
+        Some(lock) => lock,
+        None => return Ok(()),
+    };
+    let locked = match_entries(old, new)
+        .into_iter()
+        .filter(|(o, n)| o != n)
+        .find_map(|(o, n)| o.into_iter().chain(n).find(|e| lock.covers(e)));
+    if let Some(e) = locked {
+        // Absences may have no goal, so they are named by their date and kind instead.
+        let name = match first_line(&e.goal) {
+            "" => format!("{:#}", e).split_whitespace().join(" "),
+            goal => goal.to_string(),
+        };
+        Err(format!(
+            "\"{}\" is in the days locked through {}, use --force-unlock to change it anyway",
+            name, lock.through
+        ))?;
+    }
+    Ok(())
+}
+
+/// Records the differences between the old and new entries of the log in its audit trail.
+fn audit(filename: &str, old: &[Entry], new: &[Entry]) -> Result<()> {
+    let command = once("timelog".to_string())
+        .chain(env::args().skip(1))
+        .join(" ");
+    let records: Vec<_> = match_entries(old, new)
+        .into_iter()
+        .filter(|(o, n)| o != n)
+        .map(|(o, n)| AuditRecord::new(command.clone(), o.cloned(), n.cloned()))
+        .collect();
+    AuditRecord::append(AuditRecord::path_for(filename), &records)?;
+    Ok(())
+}
+
+pub fn get_file_writer(filename: &str) -> Result<Box<dyn Write>> {
+    if is_stdio(filename) {
+        return Ok(Box::new(BufWriter::new(io::stdout())));
+    }
+
+    let writer = File::create(filename);
+    Ok(Box::new(BufWriter::new(writer?)))
+}
+
+pub fn backup_log(filename: &str, dry_run: bool) -> Result<()> {
+    if is_stdio(filename) {
+        return Ok(());
+    }
+
+    let backup = format!("{}.bak", filename);
+    if dry_run {
+        println!("Would save a backup of the log to {}", backup);
+        return Ok(());
+    }
+    fs::copy(filename, &backup)?;
+    info!("copied {} to {}", filename, backup);
+    println!("Saved a backup of the log to {}", backup);
+    Ok(())
+}
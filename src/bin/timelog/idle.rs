@@ -0,0 +1,216 @@
+use crate::{
+    args::DurationArg,
+    config::{Config, IdleAction},
+    events::{announce, notify, Event},
+    file::{last_entry, update_last_entry, WriteOptions},
+    first_line, stop_entry, Result,
+};
+use chrono::{DateTime, FixedOffset, Local};
+use log::info;
+#[cfg(feature = "idle")]
+use log::warn;
+use timelog::Entry;
+
+/// When the session of the caller became idle, according to logind, or `None` if it isn't idle.
+#[cfg(feature = "idle")]
+pub fn idle_since() -> Result<Option<DateTime<FixedOffset>>> {
+    use std::convert::TryFrom;
+
+    let connection = zbus::blocking::Connection::system()?;
+    let session = zbus::blocking::Proxy::new(
+        &connection,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1/session/auto",
+        "org.freedesktop.login1.Session",
+    )?;
+    if !session.get_property::<bool>("IdleHint")? {
+        return Ok(None);
+    }
+    let micros: u64 = session.get_property("IdleSinceHint")?;
+    let since = DateTime::from_timestamp_micros(i64::try_from(micros)?)
+        .ok_or("logind gave an idle time that is out of range")?;
+    Ok(Some(since.with_timezone(&Local).fixed_offset()))
+}
+
+#[cfg(not(feature = "idle"))]
+pub fn idle_since() -> Result<Option<DateTime<FixedOffset>>> {
+    Err("idle detection needs timelog to be built with the idle feature")?
+}
+
+/// The meta that marks an entry as stopped because the system went to sleep, with the time it
+/// was stopped at.
+const SUSPENDED: &str = "suspended";
+
+/// Stops the running entry because the system is going to sleep, marking it so that
+/// `resume_entry` can start it again. Returns whether there was an entry to stop.
+pub fn suspend_entry(log_file: &str, config: &Config, write: WriteOptions) -> Result<bool> {
+    if !last_entry(log_file, false)?.is_some_and(|e| e.is_active()) {
+        return Ok(false);
+    }
+    let mut stopped = None;
+    update_last_entry(log_file, write, |log| {
+        let now = Local::now().fixed_offset();
+        let mut entry = log.active_mut().ok_or(timelog::Error::NoActiveEntry)?;
+        entry.meta.insert(SUSPENDED.to_string(), now.to_rfc3339());
+        drop(entry);
+        stopped = Some(stop_entry(log, config, String::new(), now)?);
+        Ok(())
+    })?;
+    let stopped = stopped.expect("an entry was stopped");
+    info!("stopped {} for the suspend", first_line(&stopped.goal));
+    if !write.dry_run {
+        announce(log_file, config, Event::Stop, &stopped);
+    }
+    Ok(true)
+}
+
+/// Whether `entry` is still the way `suspend_entry` left it: stopped by the suspend, at the time
+/// the mark says.
+fn is_suspended(entry: &Entry) -> bool {
+    let suspended = entry.meta.get(SUSPENDED);
+    let suspended = suspended.and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+    !entry.is_active() && suspended.is_some() && suspended == entry.stop
+}
+
+/// Takes the mark of `suspend_entry` off the latest entry after waking up, and if `resume` is
+/// set, starts the entry again.
+///
+/// Only an entry that is still the latest one, and still stopped at the time of the suspend, is
+/// taken to be stopped by it.
+pub fn resume_entry(
+    log_file: &str,
+    config: &Config,
+    write: WriteOptions,
+    resume: bool,
+) -> Result<()> {
+    let suspended = match last_entry(log_file, false)? {
+        Some(e) if is_suspended(&e) => e,
+        _ => return Ok(()),
+    };
+    let mut started = None;
+    update_last_entry(log_file, write, |log| {
+        if let Some(mut last) = log.last_mut() {
+            last.meta.remove(SUSPENDED);
+        }
+        if !resume {
+            return Ok(());
+        }
+        let mut meta = suspended.meta;
+        meta.remove(SUSPENDED);
+        let entry = log.start_with(Entry {
+            goal: suspended.goal,
+            tags: suspended.tags,
+            meta,
+            estimate: suspended.estimate,
+            client: suspended.client,
+            billable: suspended.billable,
+            rate: suspended.rate,
+            user: suspended.user,
+            ..Entry::default()
+        });
+        started = Some(entry.clone());
+        Ok(())
+    })?;
+    if let Some(started) = started {
+        let goal = first_line(&started.goal);
+        info!("started {} again after the suspend", goal);
+        if !write.dry_run {
+            announce(log_file, config, Event::Start, &started);
+        }
+    }
+    Ok(())
+}
+
+/// Stops the running entry whenever logind says the system is going to sleep, and starts it
+/// again on waking up if `suspend.resume` is set.
+#[cfg(feature = "idle")]
+pub fn watch_sleep(log_file: &str, config: &Config) -> Result<()> {
+    let connection = zbus::blocking::Connection::system()?;
+    let manager = zbus::blocking::Proxy::new(
+        &connection,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )?;
+    let signals = manager.receive_signal("PrepareForSleep")?;
+    // logind only waits for the entry to be stopped while timelog holds a delay lock.
+    let mut inhibitor = Some(inhibit_sleep(&manager)?);
+    // Whether the last suspend stopped an entry, since only that one is to be resumed.
+    let mut suspended = false;
+    for signal in signals {
+        let going_to_sleep: bool = signal.body().deserialize()?;
+        let result = if going_to_sleep {
+            let result = suspend_entry(log_file, config, WriteOptions::default());
+            suspended = matches!(result, Ok(true));
+            // Closing the lock lets the system go to sleep.
+            drop(inhibitor.take());
+            result.map(|_| ())
+        } else {
+            match inhibit_sleep(&manager) {
+                Ok(lock) => inhibitor = Some(lock),
+                Err(e) => warn!("the next suspend can't be delayed: {}", e),
+            }
+            if std::mem::take(&mut suspended) {
+                let resume = config.suspend.resume;
+                resume_entry(log_file, config, WriteOptions::default(), resume)
+            } else {
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Takes a delay lock from logind, which holds off going to sleep until the returned file
+/// descriptor is closed, or for logind's `InhibitDelayMaxSec` at most.
+#[cfg(feature = "idle")]
+fn inhibit_sleep(manager: &zbus::blocking::Proxy) -> Result<zbus::zvariant::OwnedFd> {
+    let lock = ("sleep", "timelog", "Stop the running entry", "delay");
+    Ok(manager.call("Inhibit", &lock)?)
+}
+
+/// Stops or marks the running entry `entry` if the session has been idle for longer than
+/// `config.idle.after` since it started.
+pub fn handle_idle(log_file: &str, config: &Config, entry: &Entry) -> Result<()> {
+    let after = match config.idle.after {
+        Some(DurationArg(after)) => after,
+        None => return Ok(()),
+    };
+    let since = match idle_since()? {
+        Some(since) if Local::now().fixed_offset() - since >= after => since,
+        _ => return Ok(()),
+    };
+    if entry.start.is_none_or(|start| since < start) || entry.meta.contains_key("idle-since") {
+        return Ok(());
+    }
+    let goal = first_line(&entry.goal).to_string();
+    match config.idle.action {
+        IdleAction::Stop => {
+            let mut stopped = None;
+            update_last_entry(log_file, WriteOptions::default(), |log| {
+                log.note(format!("Stopped while idle since {}", since.to_rfc3339()))?;
+                stopped = Some(stop_entry(log, config, String::new(), since)?);
+                Ok(())
+            })?;
+            let stopped = stopped.expect("an entry was stopped");
+            info!("stopped {} while idle", goal);
+            announce(log_file, config, Event::Stop, &stopped);
+            notify(&format!("Stopped while idle: {}", goal), "");
+        }
+        IdleAction::Review => {
+            update_last_entry(log_file, WriteOptions::default(), |log| {
+                let mut entry = log.active_mut().ok_or(timelog::Error::NoActiveEntry)?;
+                let since = since.to_rfc3339();
+                entry.meta.insert("idle-since".to_string(), since);
+                Ok(())
+            })?;
+            info!("marked {} for review while idle", goal);
+            let summary = format!("Idle while running: {}", goal);
+            notify(&summary, "The entry was marked for review");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use crate::Result;
+use itertools::Itertools;
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read, Write},
+    process::Command,
+};
+use timelog::Entry;
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(matches!(line.trim(), "y" | "Y" | "yes"))
+}
+
+/// Asks whether to go ahead with a change that is hard to undo, after a preview of it was shown.
+///
+/// Dry runs and `--yes` go ahead without asking. Without a terminal to ask on, the change is
+/// refused instead of waiting for an answer on stdin.
+pub fn confirm_change(prompt: &str, yes: bool, dry_run: bool) -> Result<bool> {
+    if yes || dry_run {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() {
+        Err("stdin is not a terminal to confirm on, use --yes to go ahead anyway")?;
+    }
+    let confirmed = confirm(prompt)?;
+    if !confirmed {
+        println!("Nothing was changed.");
+    }
+    Ok(confirmed)
+}
+
+pub fn choose_theirs() -> Result<bool> {
+    loop {
+        print!("Keep (o)urs or take (t)heirs? ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            Err("no choice was made")?;
+        }
+        match line.trim() {
+            "o" | "ours" => return Ok(false),
+            "t" | "theirs" => return Ok(true),
+            _ => {}
+        }
+    }
+}
+
+/// Where the goal, result or note of an entry is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    /// Typed into the terminal after a prompt.
+    Prompt,
+    /// Piped into stdin, so scripts and cron jobs don't wait for a prompt.
+    Piped,
+    /// Written in `$VISUAL` or `$EDITOR`.
+    Editor,
+    /// Not read at all, leaving the text empty.
+    Empty,
+}
+
+impl Input {
+    pub fn detect(no_prompt: bool, edit: bool) -> Input {
+        if edit {
+            Input::Editor
+        } else if no_prompt {
+            Input::Empty
+        } else if io::stdin().is_terminal() {
+            Input::Prompt
+        } else {
+            Input::Piped
+        }
+    }
+
+    /// Reads the text, asking for it as `what` of `entry`, or of a new entry, if it is typed in.
+    pub fn read(self, what: &str, entry: Option<&Entry>) -> Result<String> {
+        match self {
+            Input::Empty => return Ok(String::new()),
+            Input::Editor => return edit_text(what, entry),
+            Input::Prompt => {
+                if let Some(entry) = entry {
+                    println!("{}", entry);
+                    println!();
+                }
+                println!(
+                    "Type a {} for this entry. Use EOF (Ctrl-D) to finish.",
+                    what
+                );
+            }
+            Input::Piped => {}
+        }
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+        let mut buf = Vec::new();
+        stdin.read_to_end(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+/// Lets the user write the `what` of `entry` in their editor, like `git commit` does.
+///
+/// The file starts out with instructions and the entry as comments, which are removed again.
+fn edit_text(what: &str, entry: Option<&Entry>) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let path = env::temp_dir().join(format!("timelog-{}-{}.txt", what, std::process::id()));
+    let mut template = format!(
+        "\n# Write the {} for this entry. Lines starting with # are ignored.\n",
+        what
+    );
+    if let Some(entry) = entry {
+        template.push_str("#\n");
+        for line in entry.to_string().lines() {
+            template.push_str(format!("# {}", line).trim_end());
+            template.push('\n');
+        }
+    }
+    fs::write(&path, template)?;
+    // Like git, the editor is run by the shell so that it may have arguments.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(&path)
+        .status();
+    let text = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    if !status?.success() {
+        Err(format!(
+            "{} exited with an error, nothing was changed",
+            editor
+        ))?;
+    }
+    let text = text?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .join("\n");
+    let text = text.trim();
+    // Text typed at the prompt ends with a newline, so edited text does too.
+    Ok(if text.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", text)
+    })
+}
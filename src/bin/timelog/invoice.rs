@@ -0,0 +1,310 @@
+use crate::{config::parse_option, first_line, Result};
+use chrono::{
+    format::{Item, StrftimeItems},
+    Duration, Local, Months, NaiveDate,
+};
+use itertools::Itertools;
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use timelog::{DurationFormat, DurationStyle, Entry, Money, MoneyFormat, Template, Timezone};
+
+/// How `timelog invoice` lays out invoices.
+///
+/// The header and footer are templates with the fields `client`, `month` and `date`, the day the
+/// invoice is made, which take a `strftime` format, and `total`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Invoice {
+    /// The tax added to the subtotal, in percent.
+    tax: f64,
+    /// The text before the items [default: `# Invoice for {client}, {month}`].
+    #[serde(deserialize_with = "parse_option")]
+    header: Option<Template>,
+    /// The text after the total, like payment details.
+    #[serde(deserialize_with = "parse_option")]
+    footer: Option<Template>,
+}
+
+/// A markdown invoice for the billable time and expenses of `client` in the `month` starting on
+/// the given day.
+///
+/// The entries are grouped into one item per project, goal and rate, and every item is billed at
+/// its rate for the time it took, to the cent. Expenses follow as items of their own.
+pub fn write_invoice(
+    entries: &[Entry],
+    client: &str,
+    month: NaiveDate,
+    layout: &Invoice,
+    duration_format: DurationFormat,
+    money_format: MoneyFormat,
+) -> Result<String> {
+    let next_month = month + Months::new(1);
+    let mut items = BTreeMap::<_, Duration>::new();
+    let mut expenses = Vec::new();
+    for e in entries {
+        if let Some(expense) = &e.expense {
+            let billed = e.billable && e.client.as_deref() == Some(client);
+            if billed && month <= expense.date && expense.date < next_month {
+                expenses.push((first_line(&e.goal), expense));
+            }
+            continue;
+        }
+        let start = match e.start {
+            Some(start) => Timezone::Local.date(&start),
+            None => continue,
+        };
+        let duration = match e.duration() {
+            Some(duration) if e.billable && e.client.as_deref() == Some(client) => duration,
+            _ => continue,
+        };
+        if start < month || next_month <= start {
+            continue;
+        }
+        let rate = e.rate.clone().ok_or_else(|| {
+            format!(
+                "entry {} has no rate, set one in [rates] in the config or with timelog set",
+                e.id.map_or_else(|| "without an ID".to_string(), |id| id.to_string())
+            )
+        })?;
+        let project = e.meta.get("project").cloned().unwrap_or_default();
+        let goal = first_line(&e.goal).to_string();
+        let item = items.entry((project, goal, rate));
+        *item.or_insert_with(Duration::zero) += duration;
+    }
+    let currencies: Vec<_> = items
+        .keys()
+        .map(|(_, _, r)| &r.currency)
+        .chain(expenses.iter().map(|(_, e)| &e.amount.currency))
+        .unique()
+        .collect();
+    let currency = match currencies.as_slice() {
+        [currency] => currency.to_string(),
+        [] => Err(format!(
+            "there is no billable time or expense for {} in {}",
+            client,
+            month.format("%Y-%m")
+        ))?,
+        _ => Err(format!("{} is billed in more than one currency", client))?,
+    };
+
+    let hours = duration_format.style(DurationStyle::Decimal).decimals(2);
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut table = String::from("| Item | Hours | Rate | Amount |\n|---|---:|---:|---:|\n");
+    let mut subtotal = Money::new(0, currency);
+    for ((project, goal, rate), duration) in &items {
+        let item = match (project.as_str(), goal.as_str()) {
+            ("", goal) => goal.to_string(),
+            (project, "") => project.to_string(),
+            (project, goal) => format!("{}: {}", project, goal),
+        };
+        let amount = rate.earnings(*duration);
+        subtotal.cents += amount.cents;
+        table += &format!(
+            "| {} | {} | {}/h | {} |\n",
+            cell(&item),
+            hours.format(*duration),
+            money_format.format(&rate.per_hour()),
+            money_format.format(&amount)
+        );
+    }
+    for (description, expense) in &expenses {
+        subtotal.cents += expense.amount.cents;
+        table += &format!(
+            "| {} ({}) | | | {} |\n",
+            cell(description),
+            expense.date,
+            money_format.format(&expense.amount)
+        );
+    }
+    let tax = subtotal.percent(layout.tax);
+    let total = Money::new(subtotal.cents + tax.cents, &*subtotal.currency);
+    table += &format!("| | | Subtotal | {} |\n", money_format.format(&subtotal));
+    if tax.cents != 0 {
+        let tax = money_format.format(&tax);
+        table += &format!("| | | Tax ({}%) | {} |\n", layout.tax, tax);
+    }
+    let total_text = money_format.format(&total);
+    table += &format!("| | | **Total** | **{}** |\n", total_text);
+
+    let today = Local::now().date_naive();
+    let render = |template: &Template| -> Result<String> {
+        for (name, spec) in template.fields() {
+            match (name, spec) {
+                ("client", None) | ("total", None) | ("month", None) | ("date", None) => {}
+                ("month", Some(spec)) | ("date", Some(spec)) => {
+                    if StrftimeItems::new(spec).any(|i| i == Item::Error) {
+                        Err(format!("`{}` is not a valid date format", spec))?;
+                    }
+                }
+                ("client", Some(_)) | ("total", Some(_)) => {
+                    Err(format!("`{}` doesn't take a format", name))?
+                }
+                _ => Err(format!(
+                    "`{}` is not an invoice field, expected client, month, date or total",
+                    name
+                ))?,
+            }
+        }
+        Ok(template.render(|name, spec| match name {
+            "client" => client.to_string(),
+            "month" => month.format(spec.unwrap_or("%B %Y")).to_string(),
+            "date" => today.format(spec.unwrap_or("%F")).to_string(),
+            _ => total_text.clone(),
+        }))
+    };
+    let header = match &layout.header {
+        Some(header) => render(header)?,
+        None => render(&"# Invoice for {client}, {month}".parse::<Template>()?)?,
+    };
+    let mut invoice = format!("{}\n\n{}", header.trim_end(), table);
+    if let Some(footer) = &layout.footer {
+        invoice += &format!("\n{}\n", render(footer)?.trim_end());
+    }
+    Ok(invoice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use timelog::{Expense, Rate};
+
+    fn entry(goal: &str, start: &str, stop: &str) -> Entry {
+        Entry {
+            start: Some(DateTime::parse_from_rfc3339(start).unwrap()),
+            stop: Some(DateTime::parse_from_rfc3339(stop).unwrap()),
+            goal: goal.to_string(),
+            client: Some("acme".to_string()),
+            billable: true,
+            rate: Some(Rate::new(10000, "EUR")),
+            ..Entry::default()
+        }
+    }
+
+    fn invoice(entries: &[Entry], layout: &Invoice) -> Result<String> {
+        let month = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let (duration_format, money_format) = (DurationFormat::default(), MoneyFormat::default());
+        write_invoice(
+            entries,
+            "acme",
+            month,
+            layout,
+            duration_format,
+            money_format,
+        )
+    }
+
+    #[test]
+    fn items() {
+        let mut site = entry("Fix login", "2024-03-05T12:00:00Z", "2024-03-05T12:30:00Z");
+        site.meta.insert("project".to_string(), "site".to_string());
+        let entries = vec![
+            entry("Call", "2024-03-04T12:00:00Z", "2024-03-04T13:00:00Z"),
+            entry("Call", "2024-03-06T12:00:00Z", "2024-03-06T12:30:00Z"),
+            site,
+            Entry {
+                rate: Some(Rate::new(5000, "EUR")),
+                ..entry("Call", "2024-03-07T12:00:00Z", "2024-03-07T13:00:00Z")
+            },
+            Entry {
+                billable: false,
+                ..entry("Lunch", "2024-03-08T12:00:00Z", "2024-03-08T13:00:00Z")
+            },
+            Entry {
+                client: Some("globex".to_string()),
+                ..entry("Call", "2024-03-08T12:00:00Z", "2024-03-08T13:00:00Z")
+            },
+            entry("Call", "2024-04-01T12:00:00Z", "2024-04-01T13:00:00Z"),
+            Entry {
+                start: None,
+                stop: None,
+                rate: None,
+                expense: Some(Expense::new(
+                    NaiveDate::from_ymd_opt(2024, 3, 9).unwrap(),
+                    "12.50 EUR".parse().unwrap(),
+                )),
+                ..entry(
+                    "Train | return",
+                    "2024-03-09T12:00:00Z",
+                    "2024-03-09T12:00:00Z",
+                )
+            },
+        ];
+        let layout = Invoice {
+            tax: 10.0,
+            header: Some("Invoice for {client}, {month:%m/%Y}".parse().unwrap()),
+            footer: Some("Please pay {total}.".parse().unwrap()),
+        };
+        assert_eq!(
+            invoice(&entries, &layout).unwrap(),
+            "Invoice for acme, 03/2024\n\n\
+             | Item | Hours | Rate | Amount |\n\
+             |---|---:|---:|---:|\n\
+             | Call | 1.00 | 50.00 EUR/h | 50.00 EUR |\n\
+             | Call | 1.50 | 100.00 EUR/h | 150.00 EUR |\n\
+             | site: Fix login | 0.50 | 100.00 EUR/h | 50.00 EUR |\n\
+             | Train \\| return (2024-03-09) | | | 12.50 EUR |\n\
+             | | | Subtotal | 262.50 EUR |\n\
+             | | | Tax (10%) | 26.25 EUR |\n\
+             | | | **Total** | **288.75 EUR** |\n\
+             \nPlease pay 288.75 EUR.\n"
+        );
+    }
+
+    #[test]
+    fn default_header() {
+        let entries = [entry(
+            "Call",
+            "2024-03-04T12:00:00Z",
+            "2024-03-04T13:00:00Z",
+        )];
+        let text = invoice(&entries, &Invoice::default()).unwrap();
+        assert!(text.starts_with("# Invoice for acme, March 2024\n\n"));
+        assert!(!text.contains("Tax"));
+        assert!(text.ends_with("| | | **Total** | **100.00 EUR** |\n"));
+    }
+
+    #[test]
+    fn errors() {
+        let layout = Invoice::default();
+        let unrated = Entry {
+            rate: None,
+            ..entry("Call", "2024-03-04T12:00:00Z", "2024-03-04T13:00:00Z")
+        };
+        assert!(invoice(&[unrated], &layout)
+            .unwrap_err()
+            .to_string()
+            .contains("has no rate"));
+
+        let entries = [
+            entry("Call", "2024-03-04T12:00:00Z", "2024-03-04T13:00:00Z"),
+            Entry {
+                rate: Some(Rate::new(10000, "USD")),
+                ..entry("Call", "2024-03-05T12:00:00Z", "2024-03-05T13:00:00Z")
+            },
+        ];
+        assert_eq!(
+            invoice(&entries, &layout).unwrap_err().to_string(),
+            "acme is billed in more than one currency"
+        );
+
+        assert_eq!(
+            invoice(&[], &layout).unwrap_err().to_string(),
+            "there is no billable time or expense for acme in 2024-03"
+        );
+
+        let layout = Invoice {
+            header: Some("{customer}".parse().unwrap()),
+            ..Invoice::default()
+        };
+        let entries = [entry(
+            "Call",
+            "2024-03-04T12:00:00Z",
+            "2024-03-04T13:00:00Z",
+        )];
+        assert!(invoice(&entries, &layout)
+            .unwrap_err()
+            .to_string()
+            .contains("is not an invoice field"));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{events::Event, Result};
+use timelog::Entry;
+#[cfg(unix)]
+use {crate::first_line, std::io};
+
+/// Logs `event` to the systemd journal, with the fields `TIMELOG_EVENT`, `GOAL`, `ENTRY_ID`,
+/// `TAGS` and, once stopped, `DURATION_SECONDS`, so that `journalctl -t timelog` shows them.
+///
+/// Without a journal, only the message goes to syslog.
+#[cfg(unix)]
+pub fn log_to_journal(event: Event, entry: &Entry) -> Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let message = format!("{}: {}", event.name(), first_line(&entry.goal));
+    let mut fields = vec![
+        ("MESSAGE", message.clone()),
+        ("PRIORITY", "6".to_string()),
+        ("SYSLOG_IDENTIFIER", "timelog".to_string()),
+        ("TIMELOG_EVENT", event.name().to_string()),
+        ("GOAL", entry.goal.trim().to_string()),
+    ];
+    if let Some(id) = entry.id {
+        fields.push(("ENTRY_ID", id.to_string()));
+    }
+    if !entry.tags.is_empty() {
+        fields.push(("TAGS", entry.tags.join(",")));
+    }
+    if let Some(duration) = entry.duration() {
+        fields.push(("DURATION_SECONDS", duration.num_seconds().to_string()));
+    }
+    let mut datagram = Vec::new();
+    for (name, value) in fields {
+        datagram.extend(name.as_bytes());
+        if value.contains('\n') {
+            // Values with newlines are written with their length instead of after a `=`.
+            datagram.push(b'\n');
+            datagram.extend((value.len() as u64).to_le_bytes());
+        } else {
+            datagram.push(b'=');
+        }
+        datagram.extend(value.as_bytes());
+        datagram.push(b'\n');
+    }
+
+    let socket = UnixDatagram::unbound()?;
+    let journal = socket.send_to(&datagram, "/run/systemd/journal/socket");
+    let missing = |e: &io::Error| {
+        matches!(
+            e.kind(),
+            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+        )
+    };
+    match journal {
+        Err(e) if missing(&e) => {
+            // The user facility at the info level.
+            let line = format!("<14>timelog[{}]: {}", std::process::id(), message);
+            socket.send_to(line.as_bytes(), "/dev/log")?;
+        }
+        result => {
+            result?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn log_to_journal(_event: Event, _entry: &Entry) -> Result<()> {
+    Err("there is no journal or syslog on this system")?
+}
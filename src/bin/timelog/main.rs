@@ -0,0 +1,2548 @@
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Locale, Months, NaiveDate, Weekday,
+};
+use itertools::{Either, Itertools};
+use log::{debug, warn, LevelFilter, Log, Metadata, Record};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    error::Error,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+use structopt::{
+    clap::{AppSettings, ArgGroup, Shell},
+    StructOpt,
+};
+use terminal_size::{terminal_size, Width};
+use timelog::{
+    parse_locale, read_entries, read_entries_iter, read_entries_lenient, summarize,
+    summarize_clients, summarize_earnings, summarize_estimates, summarize_projects,
+    summarize_users, validate, write_entries_to, Absence, AbsenceKind, AuditRecord, Checksum,
+    DurationFormat, DurationStyle, Earnings, Entry, EntryFormatter, EntryRef, EntryTemplate,
+    Expense, Format, Index, Label, Language, Layout, Lock, Money, MoneyFormat, Period, Range, Rate,
+    Recovered, Style, Summary, TimeFormat, Timelog, Timezone, Trash,
+};
+#[cfg(unix)]
+use {std::os::unix::net::UnixListener, timelog::Client};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Prints a message about what a command did, to stderr if the log itself goes to stdout.
+macro_rules! status {
+    ($log_file:expr, $($arg:tt)*) => {
+        if is_stdio($log_file) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod api;
+mod args;
+mod changes;
+mod completion;
+mod config;
+mod daemon;
+mod discord;
+mod events;
+mod file;
+mod idle;
+mod input;
+mod invoice;
+mod journal;
+mod mqtt;
+mod output;
+mod pomodoro;
+mod redact;
+mod report;
+mod status;
+
+use api::Api;
+use args::{
+    parse_date, parse_hours, parse_meta, parse_month, parse_week, AmountArg, CompletionKind,
+    DurationArg, EntrySelector, FieldValue, MetaFilter, SleepPhase, SortKey, SummaryTemplate,
+};
+use changes::{entry_changes, print_changes};
+use completion::completion_script;
+use config::{env_locale, Config};
+use daemon::{overdue, watch_running};
+use events::{announce, notify, run_hook, Event};
+use file::{
+    backup_log, check_lock, get_entry_iter, get_file_reader, get_file_writer, get_index, is_stdio,
+    last_entry, report_broken, save_entries, update_last_entry, WriteOptions,
+};
+#[cfg(feature = "idle")]
+use idle::watch_sleep;
+use idle::{idle_since, resume_entry, suspend_entry};
+use input::{choose_theirs, confirm_change, Input};
+use invoice::write_invoice;
+use output::{ignore_broken_pipe, watch, ColorChoice, Output};
+use pomodoro::pomodoro;
+use redact::{anonymize_entry, redact_entry, redact_sidecars};
+use report::{
+    billable_split, billed_entries, budget_percent, budget_use, format_period, read_person_log,
+    team_logs, ClientReport, TeamReport,
+};
+use status::{entry_json, print_status, print_xbar, running_minutes, shorten, StatusFormat};
+#[cfg(unix)]
+use {daemon::Daemon, discord::watch_discord};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "timelog",
+    setting = AppSettings::DeriveDisplayOrder
+)]
+struct Opt {
+    #[structopt(
+        short = "l",
+        long = "log-file",
+        env = "TIMELOG_FILE",
+        help = "The log file to use, or - to read it from stdin and write it to stdout [default: log.json]",
+    )]
+    log_file: Option<String>,
+    #[structopt(
+        long = "lenient",
+        help = "Skip entries that can't be parsed instead of failing",
+    )]
+    lenient: bool,
+    #[structopt(
+        short = "n",
+        long = "dry-run",
+        help = "Show what would change without writing anything",
+    )]
+    dry_run: bool,
+    #[structopt(long = "no-pager", help = "Don't send long output through $PAGER")]
+    no_pager: bool,
+    #[structopt(
+        short = "v",
+        long = "verbose",
+        parse(from_occurrences),
+        help = "Explain what timelog does on stderr, -vv for more details",
+    )]
+    verbose: u8,
+    #[structopt(
+        long = "log-format",
+        default_value = "text",
+        help = "How to write the messages of --verbose: text or json",
+    )]
+    log_format: LogFormat,
+    #[structopt(
+        short = "y",
+        long = "yes",
+        help = "Go ahead with deleting or rewriting entries without asking",
+    )]
+    yes: bool,
+    #[structopt(
+        long = "no-prompt",
+        help = "Don't read goals, results or notes, leaving them empty",
+    )]
+    no_prompt: bool,
+    #[structopt(
+        long = "force-unlock",
+        help = "Change entries in the days locked with `timelog lock` anyway",
+    )]
+    force_unlock: bool,
+    #[structopt(
+        long = "clock",
+        help = "Whether to show times on a 12h or a 24h clock [default: 24h]",
+    )]
+    clock: Option<Clock>,
+    #[structopt(
+        long = "locale",
+        parse(try_from_str = parse_locale),
+        help = "The language of month and day names, and how money is written, like de_DE [default: from $LC_TIME, $LC_MONETARY or $LANG]",
+    )]
+    locale: Option<Locale>,
+    #[structopt(
+        long = "language",
+        help = "The language of the output: en, de or fr [default: from $LC_MESSAGES or $LANG]",
+    )]
+    language: Option<Language>,
+    #[structopt(
+        long = "duration-style",
+        help = "How to show durations: units (1h5m30s), clock (1:05:30) or hours (1.09) [default: units]",
+    )]
+    duration_style: Option<DurationStyle>,
+    #[structopt(
+        long = "color",
+        help = "When to color the output: auto, always or never [default: auto]",
+    )]
+    color: Option<ColorChoice>,
+    #[structopt(subcommand)]
+    sub_command: SubCommand,
+}
+
+/// Which clock times are shown on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clock {
+    TwelveHour,
+    TwentyFourHour,
+}
+
+impl FromStr for Clock {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "12h" | "12" => Ok(Clock::TwelveHour),
+            "24h" | "24" => Ok(Clock::TwentyFourHour),
+            _ => Err(format!("`{}` is not a clock, expected 12h or 24h", s)),
+        }
+    }
+}
+
+/// How diagnostics are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Text,
+    /// One JSON object per line, with the time, level, module and message.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "`{}` is not a log format, expected text or json",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes the diagnostics of timelog and its library to stderr.
+struct StderrLogger {
+    level: LevelFilter,
+    format: LogFormat,
+}
+
+impl StderrLogger {
+    /// Installs the logger, showing warnings and, with each `-v`, more details.
+    fn init(verbose: u8, format: LogFormat) {
+        let level = match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+        let logger = Box::leak(Box::new(StderrLogger { level, format }));
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(level);
+        }
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = record.level().as_str().to_lowercase();
+        match self.format {
+            LogFormat::Text => eprintln!("{}: {}", level, record.args()),
+            LogFormat::Json => eprintln!(
+                "{}",
+                serde_json::json!({
+                    "time": Local::now().to_rfc3339(),
+                    "level": level,
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                })
+            ),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Stops the running entry of `log` at `time`, splitting it at midnight if the config says so.
+///
+/// Returns the stopped entry as a whole.
+fn stop_entry(
+    log: &mut Timelog,
+    config: &Config,
+    result: String,
+    time: DateTime<FixedOffset>,
+) -> Result<Entry> {
+    let stopped = log.stop_at(result, time)?.clone();
+    if config.split_at_midnight {
+        log.split_last_at_midnight(Timezone::Local);
+    }
+    Ok(stopped)
+}
+
+/// The first line of `text`, trimmed.
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("").trim()
+}
+
+fn time_arg_group() -> ArgGroup<'static> {
+    ArgGroup::with_name("time").required(true).multiple(true)
+}
+
+#[derive(Debug, StructOpt)]
+enum SubCommand {
+    #[structopt(name = "start", about = "Create a new log entry")]
+    Start {
+        #[structopt(
+            long = "meta",
+            number_of_values = 1,
+            parse(try_from_str = parse_meta),
+            help = "Attach data to the entry, like --meta issue=123",
+        )]
+        meta: Vec<(String, String)>,
+        #[structopt(
+            long = "estimate",
+            help = "How long the entry is expected to take, like 2h or 1h30m",
+        )]
+        estimate: Option<DurationArg>,
+        #[structopt(
+            long = "client",
+            help = "Who the work is for [default: from the project meta and the config]",
+        )]
+        client: Option<String>,
+        #[structopt(
+            long = "billable",
+            help = "Mark the entry as billable [default: from the project or client in the config]",
+        )]
+        billable: bool,
+        #[structopt(
+            long = "non-billable",
+            conflicts_with = "billable",
+            help = "Mark the entry as not billable",
+        )]
+        non_billable: bool,
+        #[structopt(
+            long = "rate",
+            help = "What an hour of the work is worth, like 120 EUR [default: from the project or client in the config]",
+        )]
+        rate: Option<Rate>,
+        #[structopt(
+            short = "e",
+            long = "edit",
+            help = "Write the goal in $EDITOR instead of typing it at a prompt",
+        )]
+        edit: bool,
+    },
+    #[structopt(name = "stop", about = "Complete the latest log entry")]
+    Stop {
+        #[structopt(
+            short = "e",
+            long = "edit",
+            help = "Write the result in $EDITOR instead of typing it at a prompt",
+        )]
+        edit: bool,
+    },
+    #[structopt(
+        name = "note",
+        about = "Add a note to the latest log entry"
+    )]
+    Note {
+        #[structopt(
+            short = "e",
+            long = "edit",
+            help = "Write the note in $EDITOR instead of typing it at a prompt",
+        )]
+        edit: bool,
+    },
+    #[structopt(
+        name = "expense",
+        about = "Record money spent, like a train ticket, so it can be billed with the time"
+    )]
+    Expense {
+        #[structopt(
+            long = "amount",
+            help = "How much was spent, like 30 EUR, or 30 in the currency of the rate of the client",
+        )]
+        amount: AmountArg,
+        #[structopt(
+            long = "client",
+            help = "Who the money was spent for [default: from the project meta and the config]",
+        )]
+        client: Option<String>,
+        #[structopt(
+            long = "date",
+            parse(try_from_str = parse_date),
+            help = "The day the money was spent, like 2024-03-01 [default: today]",
+        )]
+        date: Option<NaiveDate>,
+        #[structopt(
+            long = "meta",
+            number_of_values = 1,
+            parse(try_from_str = parse_meta),
+            help = "Attach data to the expense, like --meta project=website",
+        )]
+        meta: Vec<(String, String)>,
+        #[structopt(long = "non-billable", help = "Don't bill the expense to the client")]
+        non_billable: bool,
+        #[structopt(help = "What the money was spent on")]
+        description: String,
+    },
+    #[structopt(
+        name = "off",
+        about = "Record time off, like vacation, which doesn't count as time worked"
+    )]
+    Off {
+        #[structopt(subcommand)]
+        command: Option<OffCommand>,
+        #[structopt(
+            long = "date",
+            parse(try_from_str = parse_date),
+            help = "The day taken off, like 2024-07-01 [default: today]",
+        )]
+        date: Option<NaiveDate>,
+        #[structopt(
+            long = "kind",
+            help = "What kind of time off it is: vacation, sick, holiday or unpaid",
+        )]
+        kind: Option<AbsenceKind>,
+        #[structopt(
+            long = "hours",
+            parse(try_from_str = parse_hours),
+            help = "How many hours were taken off, like 4.5 [default: 8]",
+        )]
+        hours: Option<Duration>,
+        #[structopt(help = "What the time off is for")]
+        description: Option<String>,
+    },
+    #[structopt(name = "print", about = "Print all log entries")]
+    Print {
+        #[structopt(
+            long = "layout",
+            help = "How to lay out entries: compact, detailed or verbose [default: detailed]",
+        )]
+        layout: Option<Layout>,
+        #[structopt(
+            long = "meta",
+            number_of_values = 1,
+            help = "Only print entries with this data, either key=value or just a key",
+        )]
+        meta: Vec<MetaFilter>,
+        #[structopt(long = "client", help = "Only print entries for this client")]
+        client: Option<String>,
+        #[structopt(
+            long = "format",
+            conflicts_with = "layout",
+            help = "Print each entry as a line like \"{start:%H:%M} {duration} {goal}\" [default: $TIMELOG_FORMAT]",
+        )]
+        format: Option<EntryTemplate>,
+        #[structopt(short = "r", long = "reverse", help = "Print the newest entries first")]
+        reverse: bool,
+        #[structopt(
+            long = "sort",
+            help = "What to sort entries by: start, stop, duration or goal",
+        )]
+        sort: Option<SortKey>,
+        #[structopt(
+            long = "last",
+            value_name = "N",
+            help = "Only print the N most recent entries",
+        )]
+        last: Option<usize>,
+        #[structopt(
+            short = "f",
+            long = "follow",
+            help = "Print the entries again every second, like watch(1)",
+        )]
+        follow: bool,
+        #[structopt(long = "render", help = "Render results and notes as markdown")]
+        render: bool,
+        #[structopt(
+            long = "json",
+            conflicts_with_all = &["layout", "format", "render"],
+            help = "Print the entries as a JSON array, with their durations in seconds",
+        )]
+        json: bool,
+        #[structopt(
+            short = "0",
+            long = "print0",
+            conflicts_with = "json",
+            help = "End each entry with a NUL instead of a newline, for xargs -0",
+        )]
+        print0: bool,
+    },
+    #[structopt(
+        name = "status",
+        about = "Show the running entry and how long it has run"
+    )]
+    Status {
+        #[structopt(
+            short = "w",
+            long = "watch",
+            help = "Update the time every second, like watch(1)",
+        )]
+        watch: bool,
+        #[structopt(
+            long = "json",
+            help = "Print the running entry as JSON on one line, or null if there is none",
+        )]
+        json: bool,
+        #[structopt(
+            long = "format",
+            conflicts_with_all = &["watch", "json"],
+            help = "How to print the running entry: text, json, tmux, waybar or xbar [default: text]",
+        )]
+        format: Option<StatusFormat>,
+        #[structopt(
+            long = "max-width",
+            value_name = "N",
+            default_value = "30",
+            help = "Shorten the goal to N characters for status bars",
+        )]
+        max_width: usize,
+        #[structopt(
+            short = "q",
+            long = "quiet",
+            conflicts_with_all = &["watch", "json", "format"],
+            help = "Print nothing, but exit with 0 if an entry is running, 1 if not and 2 on errors",
+        )]
+        quiet: bool,
+    },
+    #[structopt(
+        name = "prompt",
+        about = "Print the running entry compactly for a shell prompt, or nothing if there is none"
+    )]
+    Prompt {},
+    #[structopt(
+        name = "remind",
+        about = "Remind you of an entry that has been running for too long, e.g. from cron"
+    )]
+    Remind {
+        #[structopt(
+            long = "after",
+            help = "How long an entry may run, like 4h [default: remind-after from the config]",
+        )]
+        after: Option<DurationArg>,
+    },
+    #[structopt(
+        name = "on-suspend",
+        about = "Stop the running entry for a suspend and resume it after, as a systemd-sleep hook"
+    )]
+    OnSuspend {
+        #[structopt(help = "pre before going to sleep, or post after waking up")]
+        phase: SleepPhase,
+        #[structopt(help = "The kind of sleep systemd-sleep passes, like suspend")]
+        kind: Option<String>,
+        #[structopt(
+            long = "resume",
+            help = "Start the entry again after waking up [default: resume from the config]",
+        )]
+        resume: bool,
+    },
+    #[structopt(
+        name = "pomodoro",
+        about = "Work on a goal in pomodoros, logging each work interval and break as an entry"
+    )]
+    Pomodoro {
+        #[structopt(
+            long = "work",
+            default_value = "25m",
+            help = "How long each work interval is",
+        )]
+        work: DurationArg,
+        #[structopt(
+            long = "break",
+            default_value = "5m",
+            help = "How long the breaks between work intervals are",
+        )]
+        pause: DurationArg,
+        #[structopt(
+            long = "cycles",
+            default_value = "4",
+            help = "How many work intervals to do",
+        )]
+        cycles: u32,
+        #[structopt(
+            short = "e",
+            long = "edit",
+            help = "Write the goal in $EDITOR instead of typing it at a prompt",
+        )]
+        edit: bool,
+    },
+    #[structopt(name = "sync", about = "Push the running entry to other services")]
+    Sync {
+        #[structopt(subcommand)]
+        target: SyncCommand,
+    },
+    #[structopt(name = "show", about = "Print one entry with all of its fields")]
+    Show {
+        #[structopt(help = "The entry to show, either an ID or a position like #3")]
+        entry: EntrySelector,
+    },
+    #[structopt(name = "export", about = "Write the whole log in another format")]
+    Export {
+        #[structopt(
+            long = "format",
+            default_value = "json",
+            help = "The format to write: json, or toml, yaml, cbor or bincode when they are enabled",
+        )]
+        format: Format,
+        #[structopt(long = "out", help = "Where to write the log [default: stdout]")]
+        out: Option<PathBuf>,
+        #[structopt(
+            long = "anonymize",
+            help = "Replace goals, results, notes and meta other than the project with hashes, keeping times, projects and tags",
+        )]
+        anonymize: bool,
+        #[structopt(
+            long = "salt",
+            requires = "anonymize",
+            help = "Hash with this secret, so that exports can be compared [default: a new one for every export]",
+        )]
+        salt: Option<String>,
+    },
+    #[structopt(name = "check", about = "Check the log for inconsistent entries")]
+    Check {},
+    #[structopt(
+        name = "repair",
+        about = "Fix inconsistent entries, saving a backup of the log first"
+    )]
+    Repair {},
+    #[structopt(name = "dedupe", about = "Merge duplicate entries")]
+    Dedupe {
+        #[structopt(
+            short = "t",
+            long = "tolerance",
+            default_value = "0",
+            help = "How far apart start and stop times of duplicates may be, like 30s or 2m",
+        )]
+        tolerance: DurationArg,
+    },
+    #[structopt(
+        name = "merge-file",
+        about = "Merge the entries of another log file into the log"
+    )]
+    MergeFile {
+        #[structopt(help = "The log file to merge in")]
+        file: String,
+        #[structopt(
+            long = "ours",
+            conflicts_with = "theirs",
+            help = "Resolve conflicts by keeping our version of the entry",
+        )]
+        ours: bool,
+        #[structopt(
+            long = "theirs",
+            help = "Resolve conflicts by taking their version of the entry",
+        )]
+        theirs: bool,
+    },
+    #[structopt(name = "diff", about = "Show the differences between two log files")]
+    Diff {
+        #[structopt(help = "The old log file")]
+        old: String,
+        #[structopt(help = "The new log file")]
+        new: String,
+    },
+    #[structopt(name = "delete", about = "Move an entry to the trash")]
+    Delete {
+        #[structopt(help = "The entry to delete, either an ID or a position like #3")]
+        entry: EntrySelector,
+    },
+    #[structopt(name = "set", about = "Change fields of an entry")]
+    Set {
+        #[structopt(help = "The entry to change, either an ID or a position like #3")]
+        entry: EntrySelector,
+        #[structopt(
+            required = true,
+            help = "The fields to set, like billable=false, client=acme, estimate=2h, rate=\"120 EUR\", user=alice or meta.issue=123, with an empty value to clear one",
+        )]
+        fields: Vec<FieldValue>,
+    },
+    #[structopt(
+        name = "redact",
+        about = "Replace text matching a pattern in the goals, results and notes of every entry"
+    )]
+    Redact {
+        #[structopt(
+            long = "pattern",
+            help = "A regular expression for the text to replace, like 'password=\\S+'",
+        )]
+        pattern: String,
+        #[structopt(
+            long = "replace",
+            default_value = "[REDACTED]",
+            help = "What to replace the text with",
+        )]
+        replace: String,
+    },
+    #[structopt(
+        name = "lock",
+        about = "Lock the entries up to a day, like once they were invoiced, so that they can't change"
+    )]
+    Lock {
+        #[structopt(
+            long = "through",
+            parse(try_from_str = parse_date),
+            help = "The last day to lock, like 2024-03-31 [default: show the lock]",
+        )]
+        through: Option<NaiveDate>,
+    },
+    #[structopt(name = "trash", about = "Manage deleted entries")]
+    Trash {
+        #[structopt(subcommand)]
+        command: TrashCommand,
+    },
+    #[structopt(name = "hook", about = "Record things from the hooks of other tools")]
+    Hook {
+        #[structopt(subcommand)]
+        command: HookCommand,
+    },
+    #[structopt(name = "history", about = "Show the changes made to the log")]
+    History {
+        #[structopt(help = "Only show changes to the entry with this ID")]
+        id: Option<u64>,
+    },
+    #[structopt(name = "reindex", about = "Rebuild the summary index from the log")]
+    Reindex {},
+    #[structopt(
+        name = "recover",
+        about = "Remove broken entries from the log, saving them to a quarantine file"
+    )]
+    Recover {
+        #[structopt(
+            short = "q",
+            long = "quarantine",
+            help = "The file to save broken entries to [default: <log file>.quarantine]",
+        )]
+        quarantine: Option<String>,
+    },
+    #[structopt(
+        name = "summary",
+        about = "Summarize time over certain time periods",
+        group = time_arg_group(),
+        setting = AppSettings::DeriveDisplayOrder,
+    )]
+    Summary {
+        #[structopt(
+            short = "y",
+            long = "yearly",
+            group = "time",
+            help = "Prints yearly summaries",
+        )]
+        yearly: bool,
+        #[structopt(
+            short = "m",
+            long = "monthly",
+            group = "time",
+            help = "Prints monthly summaries",
+        )]
+        monthly: bool,
+        #[structopt(
+            short = "q",
+            long = "quarterly",
+            group = "time",
+            help = "Prints quarterly summaries",
+        )]
+        quarterly: bool,
+        #[structopt(
+            short = "w",
+            long = "weekly",
+            group = "time",
+            help = "Prints weekly summaries",
+        )]
+        weekly: bool,
+        #[structopt(
+            short = "d",
+            long = "daily",
+            group = "time",
+            help = "Prints daily summaries",
+        )]
+        daily: bool,
+        #[structopt(
+            short = "z",
+            long = "timezone",
+            default_value = "local",
+            help = "The timezone to group by: local, utc, original or an offset like +02:00",
+        )]
+        timezone: Timezone,
+        #[structopt(long = "week-start", help = "The day weeks start on [default: monday]")]
+        week_start: Option<Weekday>,
+        #[structopt(
+            long = "estimates",
+            group = "time",
+            help = "Prints how estimates compared to the time spent, per goal",
+        )]
+        estimates: bool,
+        #[structopt(
+            long = "by-client",
+            group = "time",
+            help = "Prints the time spent per client",
+        )]
+        by_client: bool,
+        #[structopt(
+            long = "by-project",
+            group = "time",
+            help = "Prints the time spent per project meta, and how much of its budget is used",
+        )]
+        by_project: bool,
+        #[structopt(
+            long = "billable",
+            help = "Splits the time per period or client into billable and non-billable time",
+        )]
+        billable: bool,
+        #[structopt(
+            long = "earnings",
+            help = "Shows what the billable time earned per period or client, and in total, at the rate of each entry or else the one in the config",
+        )]
+        earnings: bool,
+        #[structopt(
+            long = "format",
+            help = "Print each period as a line like \"{period}\\t{hours:.2}\"",
+        )]
+        format: Option<SummaryTemplate>,
+    },
+    #[structopt(name = "report", about = "Write a report about the log as a document")]
+    Report {
+        #[structopt(subcommand)]
+        report: ReportCommand,
+    },
+    #[structopt(name = "team", about = "Work with the logs of several people")]
+    Team {
+        #[structopt(subcommand)]
+        command: TeamCommand,
+    },
+    #[structopt(
+        name = "invoice",
+        about = "Write an invoice for the billable time and expenses of a client in one month"
+    )]
+    Invoice {
+        #[structopt(long = "client", help = "The client to bill")]
+        client: String,
+        #[structopt(
+            long = "month",
+            parse(try_from_str = parse_month),
+            help = "The month to bill, like 2024-03 [default: last month]",
+        )]
+        month: Option<NaiveDate>,
+        #[structopt(long = "out", help = "Where to write the invoice [default: stdout]")]
+        out: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "budget",
+        about = "Show how much of their budgets projects have used, exiting with 1 if too much"
+    )]
+    Budget {
+        #[structopt(
+            long = "threshold",
+            help = "How much of a budget in percent may be used [default: budget-threshold from the config, or 100]",
+        )]
+        threshold: Option<f64>,
+    },
+    #[structopt(
+        name = "completions",
+        about = "Print a script that completes timelog commands in your shell"
+    )]
+    Completions {
+        #[structopt(
+            possible_values = &Shell::variants(),
+            case_insensitive = true,
+            help = "The shell to complete for",
+        )]
+        shell: Shell,
+    },
+    /// Lists values for the completion scripts to offer.
+    #[structopt(name = "complete", setting = AppSettings::Hidden)]
+    Complete { what: CompletionKind },
+    #[cfg(unix)]
+    #[structopt(
+        name = "daemon",
+        about = "Keep the log loaded and let other timelog commands use it through a socket"
+    )]
+    Daemon {},
+    #[structopt(name = "serve", about = "Serve the log as JSON over HTTP")]
+    Serve {
+        #[structopt(
+            long = "addr",
+            default_value = "127.0.0.1:8080",
+            help = "The address to listen on",
+        )]
+        addr: String,
+    },
+    #[cfg(feature = "rhai")]
+    #[structopt(
+        name = "script",
+        about = "Run a rhai script on the log, for custom reports"
+    )]
+    Script {
+        #[structopt(help = "The script to run")]
+        path: PathBuf,
+        #[structopt(help = "Arguments for the script, which it gets as `args`")]
+        args: Vec<String>,
+    },
+    /// Runs `timelog-<name>` from `$PATH` for any other subcommand, like git and cargo do.
+    #[structopt(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Debug, StructOpt)]
+enum SyncCommand {
+    #[structopt(
+        name = "hass",
+        about = "Set a Home Assistant entity to the running entry, as set up in the config"
+    )]
+    Hass {
+        #[structopt(
+            short = "w",
+            long = "watch",
+            help = "Keep the entity up to date, pushing whenever the running entry changes",
+        )]
+        watch: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum ReportCommand {
+    #[structopt(
+        name = "client",
+        about = "Report the entries of one client, the time per project, and what it earned"
+    )]
+    Client {
+        #[structopt(help = "The client to report on")]
+        client: String,
+        #[structopt(
+            long = "from",
+            parse(try_from_str = parse_date),
+            help = "The first day to report on, like 2024-03-01",
+        )]
+        from: Option<NaiveDate>,
+        #[structopt(
+            long = "to",
+            parse(try_from_str = parse_date),
+            help = "The last day to report on, like 2024-03-31",
+        )]
+        to: Option<NaiveDate>,
+        #[structopt(long = "markdown", help = "Write the report as markdown")]
+        markdown: bool,
+        #[structopt(
+            long = "format",
+            help = "Write each entry as a line like \"{start:%H:%M} {duration} {goal}\" [default: \"{start} {duration} {meta.project} {goal}\"]",
+        )]
+        format: Option<EntryTemplate>,
+        #[structopt(long = "out", help = "Where to write the report [default: stdout]")]
+        out: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "team",
+        about = "Write a timesheet of one week with the time of every person on every project"
+    )]
+    Team {
+        #[structopt(
+            long = "logs",
+            required = true,
+            min_values = 1,
+            help = "The log of each person, like alice.json, which names the person for the entries without a user",
+        )]
+        logs: Vec<PathBuf>,
+        #[structopt(
+            long = "week",
+            parse(try_from_str = parse_week),
+            help = "The week to report on, like 2024-W12 [default: this week]",
+        )]
+        week: Option<NaiveDate>,
+        #[structopt(long = "markdown", help = "Write the report as markdown")]
+        markdown: bool,
+        #[structopt(long = "out", help = "Where to write the report [default: stdout]")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum OffCommand {
+    #[structopt(
+        name = "report",
+        about = "Show the time taken off in a year, and how much of the allowance is left"
+    )]
+    Report {
+        #[structopt(
+            long = "year",
+            help = "The year to report on, like 2024 [default: this year]",
+        )]
+        year: Option<i32>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum TeamCommand {
+    #[structopt(
+        name = "summary",
+        about = "Total the time in a directory of logs per person and per project"
+    )]
+    Summary {
+        #[structopt(
+            help = "The directory with one log per person, like alice.json, for the entries without a user"
+        )]
+        dir: PathBuf,
+        #[structopt(
+            long = "from",
+            parse(try_from_str = parse_date),
+            help = "The first day to include, like 2024-03-01",
+        )]
+        from: Option<NaiveDate>,
+        #[structopt(
+            long = "to",
+            parse(try_from_str = parse_date),
+            help = "The last day to include, like 2024-03-31",
+        )]
+        to: Option<NaiveDate>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum HookCommand {
+    #[structopt(
+        name = "git-commit",
+        about = "Add the commit that was just made as a note on the running entry, from a git post-commit hook"
+    )]
+    GitCommit {},
+}
+
+#[derive(Debug, StructOpt)]
+enum TrashCommand {
+    #[structopt(name = "list", about = "List deleted entries")]
+    List {},
+    #[structopt(name = "restore", about = "Move deleted entries back into the log")]
+    Restore {
+        #[structopt(required = true, help = "The positions of the entries in the trash")]
+        positions: Vec<usize>,
+    },
+    #[structopt(name = "purge", about = "Permanently remove all deleted entries")]
+    Purge {},
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    StderrLogger::init(opt.verbose, opt.log_format);
+    // `status --quiet`, `remind` and `budget` exit with 1 to say something, so their errors need
+    // another code.
+    let error_code = match opt.sub_command {
+        SubCommand::Status { quiet: true, .. }
+        | SubCommand::Remind { .. }
+        | SubCommand::Budget { .. } => 2,
+        _ => 1,
+    };
+    if let Err(e) = run(opt) {
+        eprintln!("error: {}", e);
+        if let Some(timelog::Error::Parse { .. }) = e.downcast_ref() {
+            eprintln!(
+                "hint: use --lenient to skip broken entries, or `timelog recover` to fix them"
+            );
+        }
+        std::process::exit(error_code);
+    }
+}
+
+fn run(opt: Opt) -> Result<()> {
+    let config = Config::load()?;
+    let log_file = opt
+        .log_file
+        .clone()
+        .or_else(|| config.log_file.clone())
+        .unwrap_or_else(|| "log.json".to_string());
+    // Only the size and modification time, since hashing the whole log on every command would
+    // make them all as slow as the log is long. `check` and `repair` compare the checksum.
+    let verify = !matches!(opt.sub_command, SubCommand::Prompt {}) && !is_stdio(&log_file);
+    if verify && Checksum::is_fresh(&log_file).unwrap_or(None) == Some(false) {
+        eprintln!(
+            "warning: {} was modified outside of timelog (run `timelog check` to compare its checksum)",
+            log_file
+        );
+    }
+
+    let color = opt
+        .color
+        .or(config.color)
+        .unwrap_or(ColorChoice::Auto)
+        .enabled();
+    let locale = match opt.locale {
+        Some(locale) => Some(locale),
+        None => config.locale.as_deref().map(parse_locale).transpose()?,
+    };
+    let time_format = TimeFormat::new()
+        .twelve_hour(opt.clock.or(config.clock) == Some(Clock::TwelveHour))
+        .locale(locale.unwrap_or_else(|| {
+            env_locale(&["LC_ALL", "LC_TIME", "LANG"])
+                .and_then(|l| parse_locale(&l).ok())
+                .unwrap_or(Locale::POSIX)
+        }));
+    let money_format = MoneyFormat::new().locale(locale.unwrap_or_else(|| {
+        env_locale(&["LC_ALL", "LC_MONETARY", "LANG"])
+            .and_then(|l| parse_locale(&l).ok())
+            .unwrap_or(Locale::POSIX)
+    }));
+    let language = opt.language.or(config.language).unwrap_or_else(|| {
+        env_locale(&["LC_ALL", "LC_MESSAGES", "LANG"])
+            .and_then(|l| Language::from_locale(&l))
+            .unwrap_or_default()
+    });
+    let yes = opt.yes || config.yes;
+    let write = WriteOptions {
+        dry_run: opt.dry_run,
+        force_unlock: opt.force_unlock,
+    };
+    let duration_style = opt.duration_style.or(config.duration_style);
+    let duration_format = DurationFormat::new().style(duration_style.unwrap_or_default());
+    match opt.sub_command {
+        SubCommand::Print {
+            layout,
+            meta,
+            client,
+            format,
+            reverse,
+            sort,
+            last,
+            follow,
+            render,
+            json,
+            print0,
+        } => {
+            // A layout given on the command line wins over the format from the environment.
+            let format = match (format, layout) {
+                (None, None) if !json => env::var("TIMELOG_FORMAT")
+                    .ok()
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.parse::<EntryTemplate>())
+                    .transpose()
+                    .map_err(|e| format!("TIMELOG_FORMAT: {}", e))?,
+                (format, _) => format,
+            };
+            let layout = layout.unwrap_or(Layout::Detailed);
+            let width = terminal_size().map(|(Width(w), _)| usize::from(w));
+            let formatter = EntryFormatter::new(layout)
+                .color(color)
+                .width(width)
+                .time_format(time_format)
+                .duration_format(duration_format)
+                .language(language)
+                .markdown(render);
+            let format = format.map(|f| f.duration_format(duration_format));
+            let (log_file, lenient) = (&log_file, opt.lenient);
+            let mut out = Output::paged(!opt.no_pager && !follow);
+            let mut print = || -> Result<()> {
+                let entries = get_entry_iter(log_file, lenient)?.filter(|e| {
+                    e.as_ref().map_or(true, |e| {
+                        meta.iter().all(|m| m.matches(e))
+                            && client.as_ref().is_none_or(|c| e.client.as_ref() == Some(c))
+                    })
+                });
+                let entries = if sort.is_some() || reverse || last.is_some() {
+                    let mut entries = entries.collect::<timelog::Result<Vec<_>>>()?;
+                    if let Some(last) = last {
+                        entries.drain(..entries.len().saturating_sub(last));
+                    }
+                    if let Some(sort) = sort {
+                        sort.sort(&mut entries);
+                    }
+                    if reverse {
+                        entries.reverse();
+                    }
+                    Either::Left(entries.into_iter().map(Ok))
+                } else {
+                    Either::Right(entries)
+                };
+                if json {
+                    let now = Local::now();
+                    let entries = entries
+                        .map(|e| e.map(|e| entry_json(&e, now)))
+                        .collect::<timelog::Result<Vec<_>>>()?;
+                    serde_json::to_writer_pretty(&mut out, &entries)?;
+                    writeln!(out)?;
+                    return Ok(());
+                }
+                let end = if print0 { "\0" } else { "\n" };
+                let mut first = true;
+                for e in entries {
+                    let e = e?;
+                    if let Some(format) = &format {
+                        write!(out, "{}{}", format.render(&e), end)?;
+                        continue;
+                    }
+                    if !first && layout != Layout::Compact && !print0 {
+                        writeln!(out)?;
+                    }
+                    first = false;
+                    write!(out, "{}{}", formatter.display(&e), end)?;
+                }
+                Ok(())
+            };
+            if follow {
+                watch(|| ignore_broken_pipe(print()))?;
+            } else {
+                ignore_broken_pipe(print())?;
+            }
+        }
+        SubCommand::Status {
+            watch: watching,
+            json,
+            format,
+            max_width,
+            quiet,
+        } => {
+            if quiet {
+                let running = last_entry(&log_file, opt.lenient)?.is_some_and(|e| e.is_active());
+                std::process::exit(if running { 0 } else { 1 });
+            }
+            let format = if json {
+                StatusFormat::Json
+            } else {
+                format.unwrap_or(StatusFormat::Text)
+            };
+            let status = || {
+                let last = last_entry(&log_file, opt.lenient)?;
+                let active = last.as_ref().filter(|e| e.is_active());
+                match format {
+                    StatusFormat::Text => {
+                        print_status(last, color, time_format, duration_format, language)?
+                    }
+                    StatusFormat::Json => {
+                        let value = active.map(|e| entry_json(e, Local::now()));
+                        println!("{}", serde_json::to_string(&value)?);
+                    }
+                    StatusFormat::Tmux => {
+                        if let Some(entry) = active {
+                            let running = running_minutes(entry)?;
+                            let goal = shorten(first_line(&entry.goal), max_width);
+                            println!(
+                                "#[fg=green]▶#[default] #[bold]{}#[nobold] {}",
+                                duration_format.format(running),
+                                goal.replace('#', "##")
+                            );
+                        }
+                    }
+                    StatusFormat::Waybar => {
+                        let value = match active {
+                            Some(entry) => {
+                                let running = duration_format.format(running_minutes(entry)?);
+                                let goal = shorten(first_line(&entry.goal), max_width);
+                                let label = language.label(Label::RunningFor);
+                                let tooltip =
+                                    format!("{}\n{} {}", entry.goal.trim(), label, running);
+                                serde_json::json!({
+                                    "text": format!("▶ {} {}", running, goal),
+                                    "tooltip": tooltip,
+                                    "class": "running",
+                                })
+                            }
+                            None => serde_json::json!({
+                                "text": "",
+                                "tooltip": language.label(Label::NothingRunning),
+                                "class": "idle",
+                            }),
+                        };
+                        println!("{}", value);
+                    }
+                    StatusFormat::Xbar => print_xbar(
+                        &log_file,
+                        opt.lenient,
+                        max_width,
+                        time_format,
+                        duration_format,
+                    )?,
+                }
+                Ok(())
+            };
+            if watching {
+                watch(status)?;
+            } else {
+                status()?;
+            }
+        }
+        SubCommand::Prompt {} => {
+            // This runs for every prompt, so it only reads the end of the log or asks the daemon.
+            if let Some(entry) = last_entry(&log_file, opt.lenient)?.filter(|e| e.is_active()) {
+                let running = duration_format.format(running_minutes(&entry)?);
+                println!("▶ {} {}", running, first_line(&entry.goal));
+            }
+        }
+        SubCommand::Remind { after } => {
+            let limit = after
+                .or(config.notifications.remind_after)
+                .map(|DurationArg(d)| d)
+                .ok_or("remind needs --after, or remind-after in the [notifications] config")?;
+            let entry = last_entry(&log_file, opt.lenient)?.filter(|e| e.is_active());
+            let running = entry
+                .as_ref()
+                .and_then(|e| Some(Local::now().fixed_offset() - e.start?));
+            if let (Some(entry), Some(running)) = (entry, running) {
+                if running > limit {
+                    let message = overdue(&entry, running, limit);
+                    println!("{}", message);
+                    notify("Forgot to stop?", &message);
+                    std::process::exit(1);
+                }
+            }
+        }
+        SubCommand::OnSuspend {
+            phase,
+            kind,
+            resume,
+        } => {
+            debug!("{:?} {}", phase, kind.as_deref().unwrap_or("sleep"));
+            match phase {
+                SleepPhase::Pre => {
+                    suspend_entry(&log_file, &config, write)?;
+                }
+                SleepPhase::Post => {
+                    let resume = resume || config.suspend.resume;
+                    resume_entry(&log_file, &config, write, resume)?
+                }
+            }
+        }
+        SubCommand::Pomodoro {
+            work: DurationArg(work),
+            pause: DurationArg(pause),
+            cycles,
+            edit,
+        } => {
+            if opt.dry_run {
+                Err("a pomodoro can't be a dry run, since it stops the entries it starts")?;
+            }
+            if work <= Duration::zero() || pause < Duration::zero() {
+                Err("pomodoros need a positive length")?;
+            }
+            if last_entry(&log_file, opt.lenient)?.is_some_and(|e| e.is_active()) {
+                Err("an entry is already running, stop it first")?;
+            }
+            let goal = Input::detect(opt.no_prompt, edit).read("goal", None)?;
+            pomodoro(&log_file, &config, &goal, work, pause, cycles)?;
+        }
+        SubCommand::Sync {
+            target: SyncCommand::Hass { watch },
+        } => {
+            let hass = config
+                .home_assistant
+                .as_ref()
+                .ok_or("sync hass needs a [home-assistant] config")?;
+            let mut pushed = None;
+            loop {
+                let entry = last_entry(&log_file, opt.lenient)?.filter(|e| e.is_active());
+                // The duration in the attributes is refreshed every minute.
+                let key = (entry.as_ref().map(|e| e.id), Local::now().timestamp() / 60);
+                if pushed != Some(key) {
+                    match hass.push(entry.as_ref()) {
+                        Err(e) if watch => warn!("Home Assistant couldn't be updated: {}", e),
+                        result => result?,
+                    }
+                    pushed = Some(key);
+                }
+                if !watch {
+                    break;
+                }
+                thread::sleep(std::time::Duration::from_secs(5));
+            }
+        }
+        SubCommand::Show { entry } => {
+            let entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            let entry = &entries[entry.find(&entries)?];
+            let width = terminal_size().map(|(Width(w), _)| usize::from(w));
+            let formatter = EntryFormatter::new(Layout::Verbose)
+                .color(color)
+                .width(width)
+                .time_format(time_format)
+                .duration_format(duration_format)
+                .language(language);
+            println!("{}", formatter.display(entry));
+        }
+        SubCommand::Summary {
+            yearly,
+            quarterly,
+            monthly,
+            weekly,
+            daily,
+            timezone,
+            week_start,
+            estimates,
+            by_client,
+            by_project,
+            billable,
+            earnings,
+            format,
+        } => {
+            let week_start = week_start.or(config.week_start).unwrap_or(Weekday::Mon);
+            let periods = [
+                (yearly, Period::Year),
+                (quarterly, Period::Quarter),
+                (monthly, Period::Month),
+                (weekly, Period::Week { start: week_start }),
+                (daily, Period::Day),
+            ];
+            let summary_format = duration_format.seconds(false);
+            let selected: Vec<_> = periods
+                .iter()
+                .filter(|(on, _)| *on)
+                .map(|(_, p)| *p)
+                .collect();
+            if !selected.is_empty() {
+                // The index only keeps the total of each day, so the billable part and the
+                // earnings are summed up from the entries, as they are billed. The totals then
+                // come from those as well, so that they include the rounding of billable time.
+                let (index, billed) = if billable || earnings {
+                    (None, billed_entries(&log_file, opt.lenient, &config)?)
+                } else {
+                    let index = get_index(&log_file, opt.lenient, timezone)?;
+                    (Some(index), Vec::new())
+                };
+                let (billable_entries, other_entries): (Vec<&Entry>, Vec<_>) =
+                    billed.iter().partition(|e| e.billable);
+                for (i, &period) in selected.iter().enumerate() {
+                    if i != 0 {
+                        println!();
+                    }
+                    let totals = |entries: &[&Entry]| -> BTreeMap<_, _> {
+                        let entries = entries.iter().copied();
+                        let summary = summarize(entries, period, Range::all(), timezone);
+                        summary
+                            .buckets
+                            .into_iter()
+                            .map(|b| (b.start, b.total))
+                            .collect()
+                    };
+                    let billable_totals = totals(&billable_entries);
+                    let other_totals = totals(&other_entries);
+                    let earned = summarize_earnings(
+                        billable_entries.iter().copied(),
+                        period,
+                        Range::all(),
+                        timezone,
+                    );
+                    let summary = match &index {
+                        Some(index) => Summary::from_days(index.days(), period, Range::all()),
+                        None => summarize(&billed, period, Range::all(), timezone),
+                    };
+                    let mut total_earned = Earnings::new();
+                    for b in summary.buckets.iter().copied() {
+                        if let Some(format) = &format {
+                            println!(
+                                "{}",
+                                format.render(period, b, summary_format, time_format, language)
+                            );
+                            continue;
+                        }
+                        let name =
+                            format!("{}:", format_period(period, b.start, time_format, language));
+                        let split = if billable {
+                            let part = |totals: &BTreeMap<_, _>| {
+                                totals.get(&b.start).copied().unwrap_or_else(Duration::zero)
+                            };
+                            let (billed, other) = (part(&billable_totals), part(&other_totals));
+                            billable_split(billed, other, summary_format, language)
+                        } else {
+                            String::new()
+                        };
+                        let earned = if earnings {
+                            let earned = earned.get(&b.start).cloned().unwrap_or_default();
+                            total_earned.extend(&earned);
+                            let earned = money_format.format_earnings(&earned);
+                            format!(", {} {}", language.label(Label::Earned), earned)
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "{} {}{}{}",
+                            Style::Title.paint(name, color),
+                            Style::Duration.paint(summary_format.format(b.total), color),
+                            split,
+                            earned
+                        );
+                    }
+                    if earnings && format.is_none() {
+                        println!(
+                            "{} {}, {} {}",
+                            Style::Title.paint(language.label(Label::Total), color),
+                            Style::Duration.paint(summary_format.format(summary.total()), color),
+                            language.label(Label::Earned),
+                            money_format.format_earnings(&total_earned)
+                        );
+                    }
+                }
+            }
+            if estimates {
+                if !selected.is_empty() {
+                    println!();
+                }
+                let entries = get_entry_iter(&log_file, opt.lenient)?
+                    .collect::<timelog::Result<Vec<_>>>()?;
+                for g in summarize_estimates(&entries) {
+                    let variance = g.variance();
+                    let off = if variance > Duration::zero() {
+                        let over = language.label(Label::Over);
+                        format!("{} {}", summary_format.format(variance), over)
+                    } else if variance < Duration::zero() {
+                        let under = language.label(Label::Under);
+                        format!("{} {}", summary_format.format(-variance), under)
+                    } else {
+                        language.label(Label::OnTime).to_string()
+                    };
+                    println!(
+                        "{} estimated {}, took {}, {} ({} entries)",
+                        Style::Title.paint(format!("{}:", g.goal), color),
+                        Style::Duration.paint(summary_format.format(g.estimated), color),
+                        Style::Duration.paint(summary_format.format(g.actual), color),
+                        off,
+                        g.entries
+                    );
+                }
+            }
+            if by_client {
+                if !selected.is_empty() || estimates {
+                    println!();
+                }
+                let entries = billed_entries(&log_file, opt.lenient, &config)?;
+                let clients = summarize_clients(&entries);
+                for c in &clients {
+                    let client = c.client.as_deref().unwrap_or("(no client)");
+                    let split = if billable {
+                        let other = c.total - c.billable;
+                        billable_split(c.billable, other, summary_format, language)
+                    } else {
+                        String::new()
+                    };
+                    let earned = if earnings {
+                        let earned = money_format.format_earnings(&c.earnings);
+                        format!(", {} {}", language.label(Label::Earned), earned)
+                    } else {
+                        String::new()
+                    };
+                    println!(
+                        "{} {}{}{} ({} entries)",
+                        Style::Title.paint(format!("{}:", client), color),
+                        Style::Duration.paint(summary_format.format(c.total), color),
+                        split,
+                        earned,
+                        c.entries
+                    );
+                }
+                if earnings {
+                    let mut total = Duration::zero();
+                    let mut total_earned = Earnings::new();
+                    for c in &clients {
+                        total += c.total;
+                        total_earned.extend(&c.earnings);
+                    }
+                    println!(
+                        "{} {}, {} {}",
+                        Style::Title.paint(language.label(Label::Total), color),
+                        Style::Duration.paint(summary_format.format(total), color),
+                        language.label(Label::Earned),
+                        money_format.format_earnings(&total_earned)
+                    );
+                }
+            }
+            if by_project {
+                if !selected.is_empty() || estimates || by_client {
+                    println!();
+                }
+                let entries = get_entry_iter(&log_file, opt.lenient)?
+                    .collect::<timelog::Result<Vec<_>>>()?;
+                for p in summarize_projects(&entries) {
+                    let budget = p.project.as_ref().and_then(|p| config.budgets.get(p));
+                    let used = match budget {
+                        Some(&DurationArg(budget)) => {
+                            format!(", {}", budget_use(p.total, budget, summary_format))
+                        }
+                        None => String::new(),
+                    };
+                    let project = p.project.as_deref().unwrap_or("(no project)");
+                    println!(
+                        "{} {}{} ({} entries)",
+                        Style::Title.paint(format!("{}:", project), color),
+                        Style::Duration.paint(summary_format.format(p.total), color),
+                        used,
+                        p.entries
+                    );
+                }
+            }
+        }
+        SubCommand::Budget { threshold } => {
+            if config.budgets.is_empty() {
+                Err("there are no budgets, set them up in [budgets] in the config")?;
+            }
+            let threshold = threshold.or(config.budget_threshold).unwrap_or(100.0);
+            let summary_format = duration_format.seconds(false);
+            let entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            let used: HashMap<_, _> = summarize_projects(&entries)
+                .into_iter()
+                .filter_map(|p| Some((p.project?, p.total)))
+                .collect();
+            let mut crossed = Vec::new();
+            for (project, &DurationArg(budget)) in &config.budgets {
+                let used = used.get(project).copied().unwrap_or_else(Duration::zero);
+                println!(
+                    "{} {}",
+                    Style::Title.paint(format!("{}:", project), color),
+                    budget_use(used, budget, summary_format)
+                );
+                if budget_percent(used, budget) >= threshold {
+                    crossed.push(project.as_str());
+                }
+            }
+            if !crossed.is_empty() {
+                eprintln!(
+                    "warning: {} used {}% of the budget or more",
+                    crossed.join(", "),
+                    threshold
+                );
+                std::process::exit(1);
+            }
+        }
+        SubCommand::Report {
+            report:
+                ReportCommand::Client {
+                    client,
+                    from,
+                    to,
+                    markdown,
+                    format,
+                    out,
+                },
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => "{start} {duration} {meta.project} {goal}".parse()?,
+            };
+            let format = format.duration_format(duration_format.seconds(false));
+            let range = Range::new(from, to.and_then(|to| to.succ_opt()));
+            let entries = billed_entries(&log_file, opt.lenient, &config)?;
+            let report = ClientReport::new(&entries, &client, range)?;
+            let report = report.write(markdown, &format, duration_format, money_format, language);
+            match out {
+                Some(out) => fs::write(out, report)?,
+                None => print!("{}", report),
+            }
+        }
+        SubCommand::Report {
+            report:
+                ReportCommand::Team {
+                    logs,
+                    week,
+                    markdown,
+                    out,
+                },
+        } => {
+            let week = week.unwrap_or_else(|| {
+                let today = Local::now().date_naive();
+                today.week(Weekday::Mon).first_day()
+            });
+            let mut people = Vec::new();
+            for path in &logs {
+                people.push((path.as_path(), read_person_log(path, opt.lenient)?));
+            }
+            let report = TeamReport::new(&people, week)?;
+            let report = report.write(markdown, duration_format);
+            match out {
+                Some(out) => fs::write(out, report)?,
+                None => print!("{}", report),
+            }
+        }
+        SubCommand::Team {
+            command: TeamCommand::Summary { dir, from, to },
+        } => {
+            let summary_format = duration_format.seconds(false);
+            let range = Range::new(from, to.and_then(|to| to.succ_opt()));
+            let mut entries = Vec::new();
+            for path in team_logs(&dir)? {
+                entries.extend(read_person_log(&path, opt.lenient)?);
+            }
+            entries.retain(|e| {
+                let day = e.start.map(|s| Timezone::Local.date(&s));
+                day.is_some_and(|day| range.contains(day))
+            });
+            let line = |name: &str, total: Duration, count: usize| {
+                println!(
+                    "  {} {} ({} entries)",
+                    Style::Title.paint(format!("{}:", name), color),
+                    Style::Duration.paint(summary_format.format(total), color),
+                    count
+                );
+            };
+            println!("{}", Style::Title.paint("People:", color));
+            for u in summarize_users(&entries) {
+                line(u.user.as_deref().unwrap_or("(no user)"), u.total, u.entries);
+            }
+            println!("{}", Style::Title.paint("Projects:", color));
+            let projects = summarize_projects(&entries);
+            for p in &projects {
+                let project = p.project.as_deref().unwrap_or("(no project)");
+                line(project, p.total, p.entries);
+            }
+            println!(
+                "{} {} ({} entries)",
+                Style::Title.paint(language.label(Label::Total), color),
+                Style::Duration.paint(
+                    summary_format.format(projects.iter().map(|p| p.total).sum()),
+                    color
+                ),
+                projects.iter().map(|p| p.entries).sum::<usize>()
+            );
+        }
+        SubCommand::Invoice { client, month, out } => {
+            let month = month.unwrap_or_else(|| {
+                let today = Local::now().date_naive();
+                let this_month = today.with_day(1).expect("every month has a first day");
+                this_month - Months::new(1)
+            });
+            let entries = billed_entries(&log_file, opt.lenient, &config)?;
+            let invoice = write_invoice(
+                &entries,
+                &client,
+                month,
+                &config.invoice,
+                duration_format,
+                money_format,
+            )?;
+            match out {
+                Some(out) => fs::write(out, invoice)?,
+                None => print!("{}", invoice),
+            }
+        }
+        SubCommand::Completions { shell } => {
+            print!("{}", completion_script(shell));
+        }
+        SubCommand::Complete { what } => {
+            let mut values = Vec::new();
+            for e in get_entry_iter(&log_file, true)? {
+                let e = e?;
+                match what {
+                    CompletionKind::Ids => values.extend(e.id.map(|id| id.to_string())),
+                    CompletionKind::MetaKeys => values.extend(e.meta.into_keys()),
+                    CompletionKind::Projects => values.extend(e.meta.get("project").cloned()),
+                }
+            }
+            for value in values.into_iter().unique() {
+                println!("{}", value);
+            }
+        }
+        #[cfg(unix)]
+        SubCommand::Daemon {} => {
+            if is_stdio(&log_file) {
+                Err("the daemon needs a log file")?;
+            }
+            let path = Client::path_for(&log_file);
+            if Client::connect(&path).is_ok() {
+                Err(format!(
+                    "a daemon is already listening on {}",
+                    path.display()
+                ))?;
+            }
+            // The socket of a daemon that was killed is still there, but nobody listens on it.
+            let _ = fs::remove_file(&path);
+            let mut daemon = Daemon::load(&log_file, opt.lenient)?;
+            let listener = UnixListener::bind(&path)?;
+            let notifications = &config.notifications;
+            if let Some(DurationArg(every)) = notifications.every {
+                if every.num_seconds() <= 0 {
+                    Err("notifications can't be every 0 seconds")?;
+                }
+            }
+            if config.idle.after.is_some() {
+                // Fail early when idle detection is unavailable.
+                idle_since()?;
+            }
+            if notifications.every.is_some()
+                || notifications.remind_after.is_some()
+                || config.idle.after.is_some()
+            {
+                let (log_file, lenient, config) = (log_file.clone(), opt.lenient, config.clone());
+                thread::spawn(move || watch_running(&log_file, lenient, &config));
+            }
+            if let Some(discord) = &config.discord {
+                let (log_file, lenient, discord) = (log_file.clone(), opt.lenient, discord.clone());
+                thread::spawn(move || watch_discord(&log_file, lenient, &discord));
+            }
+            if config.suspend.daemon {
+                #[cfg(not(feature = "idle"))]
+                Err("listening to logind needs timelog to be built with the idle feature")?;
+                #[cfg(feature = "idle")]
+                {
+                    let (log_file, config) = (log_file.clone(), config.clone());
+                    thread::spawn(move || {
+                        if let Err(e) = watch_sleep(&log_file, &config) {
+                            warn!("can't listen to logind for suspends: {}", e);
+                        }
+                    });
+                }
+            }
+            println!("Listening on {}", path.display());
+            for stream in listener.incoming() {
+                if let Err(e) = stream.map_err(Into::into).and_then(|s| daemon.serve(s)) {
+                    warn!("{}", e);
+                }
+            }
+        }
+        SubCommand::Serve { addr } => {
+            if is_stdio(&log_file) {
+                Err("the server needs a log file")?;
+            }
+            let server = tiny_http::Server::http(&addr)
+                .map_err(|e| format!("can't listen on {}: {}", addr, e))?;
+            let week_start = config.week_start.unwrap_or(Weekday::Mon);
+            let api = Api {
+                log_file,
+                lenient: opt.lenient,
+                write,
+                week_start,
+                config,
+                #[cfg(feature = "graphql")]
+                schema: timelog::graphql_schema(week_start),
+            };
+            println!("Listening on http://{}", addr);
+            for request in server.incoming_requests() {
+                api.respond(request);
+            }
+        }
+        #[cfg(feature = "rhai")]
+        SubCommand::Script { path, args } => {
+            let source =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let week_start = config.week_start.unwrap_or(Weekday::Mon);
+            let script = timelog::Script::compile(&source, week_start)?;
+            let entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            script.run(entries, args)?;
+        }
+        SubCommand::External(args) => {
+            let (name, args) = args.split_first().expect("clap gives the subcommand name");
+            let program = format!("timelog-{}", name);
+            let flag = |on: bool| if on { "1" } else { "0" };
+            debug!("running {}", program);
+            let status = Command::new(&program)
+                .args(args)
+                .env("TIMELOG_FILE", &log_file)
+                .env("TIMELOG_COLOR", if color { "always" } else { "never" })
+                .env("TIMELOG_LENIENT", flag(opt.lenient))
+                .env("TIMELOG_DRY_RUN", flag(opt.dry_run))
+                .env("TIMELOG_YES", flag(yes))
+                .env("TIMELOG_NO_PROMPT", flag(opt.no_prompt))
+                .env("TIMELOG_VERBOSE", opt.verbose.to_string())
+                .status()
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::NotFound => {
+                        format!(
+                            "`{}` is not a timelog command, and {} isn't on the PATH",
+                            name, program
+                        )
+                    }
+                    _ => format!("{} can't be run: {}", program, e),
+                })?;
+            if !status.success() {
+                std::process::exit(status.code().unwrap_or(1));
+            }
+        }
+        SubCommand::Export {
+            format,
+            out,
+            anonymize,
+            salt,
+        } => {
+            let mut entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            if anonymize {
+                let salt = salt.unwrap_or_else(|| {
+                    let now = std::time::SystemTime::now();
+                    format!("{:?}{}", now, std::process::id())
+                });
+                for e in &mut entries {
+                    anonymize_entry(e, &salt)?;
+                }
+            }
+            let writer: Box<dyn Write> = match out {
+                Some(out) => Box::new(BufWriter::new(File::create(out)?)),
+                None => Box::new(io::stdout().lock()),
+            };
+            write_entries_to(writer, &entries, format)?;
+        }
+        SubCommand::Check {} => {
+            let entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            let issues = validate(&entries);
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            let mut problems = issues.len();
+            if !is_stdio(&log_file) && Checksum::verify(&log_file)? == Some(false) {
+                println!(
+                    "{} was modified outside of timelog (its checksum doesn't match)",
+                    log_file
+                );
+                problems += 1;
+            }
+            if problems > 0 {
+                Err(format!("found {} problems in the log", problems))?;
+            }
+        }
+        SubCommand::Repair {} => {
+            let mut entries = get_entry_iter(&log_file, opt.lenient)?
+                .enumerate()
+                .map(|(i, e)| e.map(|e| (EntryRef::new(i, &e), e)))
+                .collect::<timelog::Result<Vec<_>>>()?;
+            entries.sort_by(|a, b| a.1.cmp(&b.1));
+            let old: Vec<_> = entries.iter().map(|(_, e)| e.clone()).collect();
+
+            if is_stdio(&log_file) && !yes && !opt.dry_run {
+                Err("--yes is required when the log is read from stdin")?;
+            }
+            let fix = |description: String| -> Result<bool> {
+                status!(&log_file, "{}", description);
+                confirm_change("Apply this fix?", yes, opt.dry_run)
+            };
+            let mut changed = false;
+
+            let accept = !is_stdio(&log_file)
+                && Checksum::verify(&log_file)? == Some(false)
+                && fix(format!(
+                    "Accept the changes made to {} outside of timelog",
+                    log_file
+                ))?;
+
+            let mut kept: Vec<(EntryRef, Entry)> = Vec::new();
+            for (r, e) in entries {
+                let duplicate = kept
+                    .iter()
+                    .rev()
+                    .take_while(|(_, k)| k.start == e.start)
+                    .find(|(_, k)| *k == e)
+                    .map(|(other, _)| *other);
+                if let Some(other) = duplicate {
+                    if fix(format!("Drop entry {}, a duplicate of {}", r, other))? {
+                        changed = true;
+                        continue;
+                    }
+                }
+                kept.push((r, e));
+            }
+            let mut entries = kept;
+
+            for i in 1..entries.len() {
+                let (next, next_start) = match entries[i].1.start {
+                    Some(start) => (entries[i].0, start),
+                    None => continue,
+                };
+                let (r, e) = &mut entries[i - 1];
+                let description = match e.stop {
+                    None => format!(
+                        "Stop running entry {} at {}, when {} starts",
+                        r,
+                        next_start.with_timezone(&Local),
+                        next
+                    ),
+                    Some(stop) if stop > next_start => format!(
+                        "Move the stop time of entry {} from {} to {}, when {} starts",
+                        r,
+                        stop.with_timezone(&Local),
+                        next_start.with_timezone(&Local),
+                        next
+                    ),
+                    Some(_) => continue,
+                };
+                if fix(description)? {
+                    e.stop = Some(next_start);
+                    changed = true;
+                }
+            }
+
+            if config.split_at_midnight {
+                let mut next_id = entries.iter().filter_map(|(_, e)| e.id).max().unwrap_or(0) + 1;
+                let mut split = Vec::new();
+                for (r, e) in entries {
+                    let pieces = e.split_at_midnight(Timezone::Local);
+                    let description =
+                        format!("Split entry {} at midnight into {}", r, pieces.len());
+                    if pieces.len() == 1 || !fix(description)? {
+                        split.push((r, e));
+                        continue;
+                    }
+                    changed = true;
+                    for mut piece in pieces {
+                        if piece.id.is_none() && e.id.is_some() {
+                            piece.id = Some(next_id);
+                            next_id += 1;
+                        }
+                        split.push((r, piece));
+                    }
+                }
+                entries = split;
+            }
+
+            if changed {
+                backup_log(&log_file, opt.dry_run)?;
+                let entries = entries.into_iter().map(|(_, e)| e).collect();
+                save_entries(&log_file, &old, entries, write)?;
+            } else if accept {
+                if !opt.dry_run {
+                    Checksum::update(&log_file)?;
+                }
+            } else {
+                status!(&log_file, "Nothing to repair.");
+            }
+        }
+        SubCommand::Dedupe { tolerance } => {
+            let tolerance = tolerance.0;
+            let mut entries = get_entry_iter(&log_file, opt.lenient)?
+                .enumerate()
+                .map(|(i, e)| e.map(|e| (EntryRef::new(i, &e), e)))
+                .collect::<timelog::Result<Vec<_>>>()?;
+            entries.sort_by(|a, b| a.1.cmp(&b.1));
+            let old: Vec<_> = entries.iter().map(|(_, e)| e.clone()).collect();
+
+            type Time = Option<DateTime<FixedOffset>>;
+            let close = |a: Time, b: Time| match (a, b) {
+                (Some(a), Some(b)) => (a - b).abs() <= tolerance,
+                (None, None) => true,
+                _ => false,
+            };
+
+            let mut removed = 0;
+            let mut kept: Vec<(EntryRef, Entry)> = Vec::new();
+            for (r, e) in entries {
+                let duplicate = kept
+                    .iter_mut()
+                    .rev()
+                    .take_while(|(_, k)| close(k.start, e.start))
+                    .find(|(_, k)| {
+                        close(k.start, e.start) && close(k.stop, e.stop) && k.goal == e.goal
+                    });
+                if let Some((other, k)) = duplicate {
+                    status!(&log_file, "Merged entry {} into {}", r, other);
+                    if k.result.is_empty() {
+                        k.result = e.result;
+                    }
+                    for note in e.notes {
+                        if !k.notes.contains(&note) {
+                            k.notes.push(note);
+                        }
+                    }
+                    for tag in e.tags {
+                        if !k.tags.contains(&tag) {
+                            k.tags.push(tag);
+                        }
+                    }
+                    for (key, value) in e.meta {
+                        k.meta.entry(key).or_insert(value);
+                    }
+                    k.estimate = k.estimate.or(e.estimate);
+                    removed += 1;
+                } else {
+                    kept.push((r, e));
+                }
+            }
+
+            if removed == 0 {
+                status!(&log_file, "No duplicates found.");
+            } else {
+                let prompt = format!("Remove {} duplicate entries?", removed);
+                if !confirm_change(&prompt, yes, opt.dry_run)? {
+                    return Ok(());
+                }
+                status!(&log_file, "Removed {} duplicate entries", removed);
+                backup_log(&log_file, opt.dry_run)?;
+                let kept = kept.into_iter().map(|(_, e)| e).collect();
+                save_entries(&log_file, &old, kept, write)?;
+            }
+        }
+        SubCommand::MergeFile { file, ours, theirs } => {
+            if is_stdio(&log_file) && !ours && !theirs {
+                Err("--ours or --theirs is required when the log is read from stdin")?;
+            }
+            let old = read_entries(get_file_reader(&log_file)?)?.into_vec();
+            let mut entries = old.clone();
+            let others = read_entries_iter(File::open(&file)?);
+
+            // Entries are matched by their start time, since IDs are only unique per log.
+            let mut by_start: HashMap<_, _> = entries
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| e.start.map(|start| (start, i)))
+                .collect();
+            let mut ids: HashSet<_> = entries.iter().filter_map(|e| e.id).collect();
+            let mut next_id = ids.iter().max().map_or(1, |id| id + 1);
+
+            let (mut added, mut conflicts) = (0, 0);
+            for other in others {
+                let mut other = other?;
+                match other.start.and_then(|start| by_start.get(&start)) {
+                    Some(&i) if entries[i] == other => {}
+                    Some(&i) => {
+                        conflicts += 1;
+                        let take_theirs = if ours || theirs {
+                            theirs
+                        } else {
+                            println!("Ours:\n{}\n", entries[i]);
+                            println!("Theirs:\n{}\n", other);
+                            choose_theirs()?
+                        };
+                        if take_theirs {
+                            other.id = entries[i].id;
+                            entries[i] = other;
+                        }
+                    }
+                    None if entries.contains(&other) => {}
+                    None => {
+                        if let Some(id) = other.id {
+                            if !ids.insert(id) {
+                                other.id = Some(next_id);
+                                ids.insert(next_id);
+                            }
+                        }
+                        next_id = next_id.max(other.id.map_or(0, |id| id + 1));
+                        if let Some(start) = other.start {
+                            by_start.insert(start, entries.len());
+                        }
+                        entries.push(other);
+                        added += 1;
+                    }
+                }
+            }
+
+            status!(
+                &log_file,
+                "Added {} entries and resolved {} conflicts from {}",
+                added,
+                conflicts,
+                file
+            );
+            save_entries(&log_file, &old, entries.into_iter().collect(), write)?;
+        }
+        SubCommand::Diff { old, new } => {
+            let read = |file: &str| -> Result<Vec<Entry>> {
+                let mut entries = read_entries_iter(File::open(file)?)
+                    .collect::<timelog::Result<Vec<_>>>()?;
+                entries.sort();
+                Ok(entries)
+            };
+            print_changes(&read(&old)?, &read(&new)?);
+        }
+        SubCommand::Hook {
+            command: HookCommand::GitCommit {},
+        } => {
+            if last_entry(&log_file, opt.lenient)?.is_some_and(|e| e.is_active()) {
+                let note = last_commit()?;
+                let mut noted = None;
+                update_last_entry(&log_file, write, |log| {
+                    if log.active().is_none() {
+                        return Ok(());
+                    }
+                    let entry = log.note(note)?;
+                    if !opt.dry_run {
+                        run_hook(&log_file, "pre", Event::Note, entry)?;
+                    }
+                    noted = Some(entry.clone());
+                    Ok(())
+                })?;
+                if let (Some(noted), false) = (noted, opt.dry_run) {
+                    announce(&log_file, &config, Event::Note, &noted);
+                }
+            }
+        }
+        SubCommand::History { id } => {
+            for record in AuditRecord::read_all(AuditRecord::path_for(&log_file))? {
+                if id.is_some() && record.id != id {
+                    continue;
+                }
+                println!("{}  {}", record.time.with_timezone(&Local), record.command);
+                match (&record.before, &record.after) {
+                    (Some(before), Some(after)) => {
+                        println!("  changed {:#}", after);
+                        for change in entry_changes(before, after) {
+                            println!("    {}", change);
+                        }
+                    }
+                    (Some(before), None) => println!("  deleted {:#}", before),
+                    (None, Some(after)) => println!("  created {:#}", after),
+                    (None, None) => {}
+                }
+            }
+        }
+        SubCommand::Delete { entry } => {
+            let old = read_entries(get_file_reader(&log_file)?)?;
+            let mut entries = old.clone();
+            let i = entry.find(&entries)?;
+            let entry = entries.remove(i);
+            status!(&log_file, "{}", entry);
+            // Before the trash is touched, so that a locked entry doesn't end up in both.
+            let deleted = std::slice::from_ref(&entry);
+            check_lock(&log_file, deleted, &[], write.force_unlock)?;
+            if !confirm_change("Move this entry to the trash?", yes, opt.dry_run)? {
+                return Ok(());
+            }
+
+            if !is_stdio(&log_file) && !opt.dry_run {
+                let trash_path = Trash::path_for(&log_file);
+                let mut trash = Trash::read(&trash_path)?;
+                trash.push(entry);
+                trash.write(&trash_path)?;
+            }
+            save_entries(&log_file, &old, entries, write)?;
+        }
+        SubCommand::Set { entry, fields } => {
+            let old = read_entries(get_file_reader(&log_file)?)?;
+            let mut entries = old.clone();
+            let i = entry.find(&entries)?;
+            let mut entry = entries.remove(i);
+            for field in fields {
+                field.apply(&mut entry);
+            }
+            status!(&log_file, "{}", entry);
+            entries.insert_sorted(entry);
+            save_entries(&log_file, &old, entries, write)?;
+        }
+        SubCommand::Expense {
+            amount,
+            client,
+            date,
+            meta,
+            non_billable,
+            description,
+        } => {
+            if description.trim().is_empty() {
+                Err("the expense needs a description")?;
+            }
+            let mut entry = Entry {
+                goal: description,
+                meta: meta.into_iter().collect(),
+                billable: !non_billable,
+                user: config.user(),
+                ..Entry::default()
+            };
+            entry.client = client.or_else(|| config.client_of(&entry.meta));
+            let currency = match amount.currency {
+                Some(currency) => currency,
+                None => match config.rate_for(&entry) {
+                    Some(rate) => rate.currency,
+                    None => Err("there is no rate to take the currency from, give one like --amount \"30 EUR\"")?,
+                },
+            };
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            entry.expense = Some(Expense::new(date, Money::new(amount.cents, currency)));
+            let old = read_entries(get_file_reader(&log_file)?)?;
+            let mut entries = old.clone();
+            let highest = entries.iter().filter_map(|e| e.id).max();
+            entry.id = Some(highest.map_or(1, |id| id + 1));
+            status!(&log_file, "{}", entry);
+            entries.insert_sorted(entry);
+            save_entries(&log_file, &old, entries, write)?;
+        }
+        SubCommand::Redact { pattern, replace } => {
+            let pattern = Regex::new(&pattern)
+                .map_err(|e| format!("`{}` is not a valid pattern: {}", pattern, e))?;
+            let old = read_entries(get_file_reader(&log_file)?)?.into_vec();
+            let mut entries = old.clone();
+            let mut redacted = 0;
+            for e in &mut entries {
+                if redact_entry(e, &pattern, &replace) {
+                    redacted += 1;
+                }
+            }
+            if redacted == 0 {
+                status!(&log_file, "No entries match {}.", pattern);
+                return Ok(());
+            }
+            let prompt = format!("Redact {} entries?", redacted);
+            if !confirm_change(&prompt, yes, opt.dry_run)? {
+                return Ok(());
+            }
+            save_entries(&log_file, &old, entries.into_iter().collect(), write)?;
+            if !is_stdio(&log_file) && !opt.dry_run {
+                redact_sidecars(&log_file, &pattern, &replace)?;
+                status!(&log_file, "Redacted {} entries", redacted);
+            }
+        }
+        SubCommand::Lock { through } => {
+            if is_stdio(&log_file) {
+                Err("a log read from stdin can't be locked")?;
+            }
+            let path = Lock::path_for(&log_file);
+            let lock = Lock::read(&path)?;
+            let through = match through {
+                Some(through) => through,
+                None => {
+                    match lock {
+                        Some(lock) => println!("{} is locked through {}", log_file, lock.through),
+                        None => println!("{} isn't locked", log_file),
+                    }
+                    return Ok(());
+                }
+            };
+            if let Some(lock) = lock.filter(|l| through < l.through) {
+                if !opt.force_unlock {
+                    Err(format!(
+                        "{} is locked through {}, use --force-unlock to move the lock back",
+                        log_file, lock.through
+                    ))?;
+                }
+            }
+            if opt.dry_run {
+                println!("Would lock {} through {}", log_file, through);
+            } else {
+                Lock::new(through).write(&path)?;
+                println!("Locked {} through {}", log_file, through);
+            }
+        }
+        SubCommand::Off {
+            command: Some(OffCommand::Report { year }),
+            ..
+        } => {
+            let year = year.unwrap_or_else(|| Local::now().year());
+            // Days off are working days, so counting them in days of 24 hours would be confusing.
+            let summary_format = duration_format.seconds(false).always_hours(true);
+            let entries = get_entry_iter(&log_file, opt.lenient)?
+                .collect::<timelog::Result<Vec<_>>>()?;
+            let mut taken: BTreeMap<_, _> = config
+                .time_off
+                .keys()
+                .map(|&kind| (kind, Duration::zero()))
+                .collect();
+            for absence in entries.iter().filter_map(|e| e.absence.as_ref()) {
+                if absence.date.year() == year {
+                    *taken.entry(absence.kind).or_insert_with(Duration::zero) += absence.duration;
+                }
+            }
+            if taken.is_empty() {
+                status!(&log_file, "No time off in {}", year);
+            }
+            for (kind, used) in taken {
+                let used = match config.time_off.get(&kind) {
+                    Some(&DurationArg(allowance)) => budget_use(used, allowance, summary_format),
+                    None => summary_format.format(used),
+                };
+                let kind = Style::Title.paint(format!("{}:", kind), color);
+                println!("{} {}", kind, used);
+            }
+        }
+        SubCommand::Off {
+            command: None,
+            date,
+            kind,
+            hours,
+            description,
+        } => {
+            let kind = match kind {
+                Some(kind) => kind,
+                None => Err("give the kind of time off, like --kind vacation")?,
+            };
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let hours = hours.unwrap_or_else(|| Duration::hours(8));
+            let old = read_entries(get_file_reader(&log_file)?)?;
+            let mut entries = old.clone();
+            let highest = entries.iter().filter_map(|e| e.id).max();
+            let entry = Entry {
+                id: Some(highest.map_or(1, |id| id + 1)),
+                goal: description.unwrap_or_default(),
+                absence: Some(Absence::new(date, kind, hours)),
+                user: config.user(),
+                ..Entry::default()
+            };
+            status!(&log_file, "{}", entry);
+            entries.insert_sorted(entry);
+            save_entries(&log_file, &old, entries, write)?;
+        }
+        SubCommand::Trash { command } => {
+            let trash_path = Trash::path_for(&log_file);
+            let mut trash = Trash::read(&trash_path)?;
+            match command {
+                TrashCommand::List {} => {
+                    for (i, t) in trash.entries.iter().enumerate() {
+                        if i != 0 {
+                            println!();
+                        }
+                        println!("{}. Deleted {}", i + 1, t.deleted.with_timezone(&Local));
+                        println!("{}", t.entry);
+                    }
+                }
+                TrashCommand::Restore { mut positions } => {
+                    positions.sort_unstable();
+                    positions.dedup();
+                    if positions.iter().any(|&p| p == 0 || p > trash.entries.len()) {
+                        Err("no such entry in the trash")?;
+                    }
+
+                    let old = read_entries(get_file_reader(&log_file)?)?;
+                    let mut entries = old.clone();
+                    for &p in positions.iter().rev() {
+                        entries.insert_sorted(trash.entries.remove(p - 1).entry);
+                    }
+                    save_entries(&log_file, &old, entries, write)?;
+                    if !opt.dry_run {
+                        trash.write(&trash_path)?;
+                        println!("Restored {} entries", positions.len());
+                    }
+                }
+                TrashCommand::Purge {} => {
+                    let prompt = format!(
+                        "Permanently remove the {} entries in the trash?",
+                        trash.entries.len()
+                    );
+                    if opt.dry_run {
+                        println!("Would permanently remove {} entries", trash.entries.len());
+                    } else if trash.entries.is_empty() || confirm_change(&prompt, yes, false)? {
+                        println!("Permanently removed {} entries", trash.entries.len());
+                        trash.entries.clear();
+                        trash.write(&trash_path)?;
+                    }
+                }
+            }
+        }
+        SubCommand::Reindex {} => {
+            let timezone = Index::read(Index::path_for(&log_file))
+                .ok()
+                .flatten()
+                .map_or(Timezone::Local, |index| index.timezone());
+            let mut index = Index::new(timezone);
+            for e in get_entry_iter(&log_file, opt.lenient)? {
+                index.add(&e?);
+            }
+            if !opt.dry_run {
+                index.write(Index::path_for(&log_file), &log_file)?;
+            }
+        }
+        SubCommand::Recover { quarantine } => {
+            let recovered = match get_file_reader(&log_file)? {
+                Some(reader) => read_entries_lenient(reader)?,
+                None => Recovered::default(),
+            };
+            if recovered.broken.is_empty() {
+                status!(&log_file, "No broken entries found.");
+            } else {
+                report_broken(&recovered.broken);
+                let quarantine = match quarantine {
+                    Some(quarantine) => quarantine,
+                    None if is_stdio(&log_file) => {
+                        Err("--quarantine is required when the log is read from stdin")?
+                    }
+                    None => format!("{}.quarantine", log_file),
+                };
+                let prompt = format!(
+                    "Move {} broken entries to {}?",
+                    recovered.broken.len(),
+                    quarantine
+                );
+                if !confirm_change(&prompt, yes, opt.dry_run)? {
+                    return Ok(());
+                }
+                if !opt.dry_run {
+                    let writer = get_file_writer(&quarantine)?;
+                    serde_json::to_writer_pretty(writer, &recovered.broken)?;
+                }
+                status!(
+                    &log_file,
+                    "Kept {} entries, moved {} broken entries to {}",
+                    recovered.entries.len(),
+                    recovered.broken.len(),
+                    quarantine
+                );
+
+                let entries = recovered.entries.iter().cloned().collect();
+                save_entries(&log_file, &recovered.entries, entries, write)?;
+            }
+        }
+        SubCommand::Start {
+            meta,
+            estimate,
+            client,
+            billable,
+            non_billable,
+            rate,
+            edit,
+        } => {
+            let estimate = estimate.map(|DurationArg(d)| d);
+            if estimate.is_some_and(|d| d < Duration::zero()) {
+                Err("the estimate can't be negative")?;
+            }
+            let tags = config.tags.clone();
+            let dry_run = opt.dry_run;
+            let input = Input::detect(opt.no_prompt, edit);
+            let mut started = None;
+            let mut entry = Entry {
+                meta: meta.into_iter().collect(),
+                estimate,
+                tags,
+                user: config.user(),
+                ..Entry::default()
+            };
+            config.git.apply(&mut entry)?;
+            entry.client = client.or_else(|| config.client_of(&entry.meta));
+            entry.billable = billable || (!non_billable && config.billable_for(&entry));
+            entry.rate = rate.or_else(|| config.rate_for(&entry));
+            update_last_entry(&log_file, write, |log| {
+                entry.goal = input.read("goal", None)?;
+                let entry = log.start_with(entry);
+                if !dry_run {
+                    run_hook(&log_file, "pre", Event::Start, entry)?;
+                }
+                started = Some(entry.clone());
+                Ok(())
+            })?;
+            let started = started.expect("an entry was started");
+            if !dry_run {
+                announce(&log_file, &config, Event::Start, &started);
+            }
+        }
+        SubCommand::Stop { edit } => {
+            let input = Input::detect(opt.no_prompt, edit);
+            let mut stopped = None;
+            update_last_entry(&log_file, write, |log| {
+                let result = input.read("result", Some(log.entries().try_active()?))?;
+                let entry = stop_entry(log, &config, result, Local::now().fixed_offset())?;
+                if !opt.dry_run {
+                    run_hook(&log_file, "pre", Event::Stop, &entry)?;
+                }
+                stopped = Some(entry);
+                Ok(())
+            })?;
+            let stopped = stopped.expect("an entry was stopped");
+            if !opt.dry_run {
+                announce(&log_file, &config, Event::Stop, &stopped);
+            }
+        }
+        SubCommand::Note { edit } => {
+            let input = Input::detect(opt.no_prompt, edit);
+            let mut noted = None;
+            update_last_entry(&log_file, write, |log| {
+                let last = log.last().ok_or(timelog::Error::NoEntries)?;
+                let note = input.read("note", Some(last))?;
+                let entry = log.note(note)?;
+                if !opt.dry_run {
+                    run_hook(&log_file, "pre", Event::Note, entry)?;
+                }
+                noted = Some(entry.clone());
+                Ok(())
+            })?;
+            let noted = noted.expect("a note was added");
+            if !opt.dry_run {
+                announce(&log_file, &config, Event::Note, &noted);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The commit that was just made in the repository of the working directory, as a note like
+/// `commit 1a2b3c4: Fix the parser`.
+fn last_commit() -> Result<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=commit %h: %s"])
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("git can't be run: {}", e))?;
+    if !output.status.success() {
+        Err(format!("git log failed with {}", output.status))?;
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
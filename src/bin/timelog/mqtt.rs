@@ -0,0 +1,101 @@
+use crate::{events::Webhook, Result};
+use serde_derive::Deserialize;
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+/// An MQTT broker that events are published to like they are sent to webhooks, for home
+/// automation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Mqtt {
+    /// The host and port of the broker, like `localhost:1883`.
+    pub broker: String,
+    topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Whether the broker keeps the latest event for clients that subscribe later.
+    #[serde(default)]
+    retain: bool,
+}
+
+impl Mqtt {
+    const CONNECT: u8 = 0x10;
+    const CONNACK: u8 = 0x20;
+    const PUBLISH: u8 = 0x30;
+    const DISCONNECT: u8 = 0xe0;
+
+    /// Publishes `payload` with MQTT 3.1.1, once and without waiting for it to arrive, like QoS
+    /// 0 does.
+    pub fn publish(&self, payload: &str) -> Result<()> {
+        let address = self
+            .broker
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format!("{} has no address", self.broker))?;
+        let mut stream = TcpStream::connect_timeout(&address, Webhook::TIMEOUT)?;
+        stream.set_read_timeout(Some(Webhook::TIMEOUT))?;
+        stream.set_write_timeout(Some(Webhook::TIMEOUT))?;
+
+        // A clean session that is kept alive for a minute.
+        let mut flags = 0x02;
+        let mut connect = mqtt_string("MQTT")?;
+        let mut credentials = mqtt_string(&format!("timelog-{}", std::process::id()))?;
+        if let Some(username) = &self.username {
+            flags |= 0x80;
+            credentials.extend(mqtt_string(username)?);
+        }
+        if let Some(password) = &self.password {
+            flags |= 0x40;
+            credentials.extend(mqtt_string(password)?);
+        }
+        connect.extend([4, flags, 0, 60]);
+        connect.extend(credentials);
+        stream.write_all(&mqtt_packet(Mqtt::CONNECT, &connect))?;
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != Mqtt::CONNACK {
+            Err("the broker didn't answer like an MQTT broker")?;
+        }
+        if connack[3] != 0 {
+            Err(format!(
+                "the broker refused the connection with {}",
+                connack[3]
+            ))?;
+        }
+
+        let mut publish = mqtt_string(&self.topic)?;
+        publish.extend(payload.as_bytes());
+        let kind = Mqtt::PUBLISH | u8::from(self.retain);
+        stream.write_all(&mqtt_packet(kind, &publish))?;
+        stream.write_all(&mqtt_packet(Mqtt::DISCONNECT, &[]))?;
+        Ok(())
+    }
+}
+
+/// An MQTT packet of `kind` around `body`, whose length is encoded seven bits at a time.
+fn mqtt_packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![kind];
+    let mut length = body.len();
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        if length == 0 {
+            packet.push(byte);
+            break;
+        }
+        packet.push(byte | 0x80);
+    }
+    packet.extend(body);
+    packet
+}
+
+/// `s` with its length in front, like MQTT writes strings.
+fn mqtt_string(s: &str) -> Result<Vec<u8>> {
+    use std::convert::TryFrom;
+
+    let mut bytes = u16::try_from(s.len())?.to_be_bytes().to_vec();
+    bytes.extend(s.as_bytes());
+    Ok(bytes)
+}
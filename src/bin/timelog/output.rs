@@ -0,0 +1,120 @@
+use crate::Result;
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    thread,
+};
+
+/// When to color the output. Automatic coloring follows the `NO_COLOR` convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("`{}` is not auto, always or never", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether to color what is printed to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Where long output goes: through a pager when stdout is a terminal, like git does, and straight
+/// to stdout otherwise.
+pub enum Output {
+    Stdout(io::Stdout),
+    Pager(Child),
+}
+
+impl Output {
+    /// Starts `$PAGER`, or `less` if it isn't set, unless `enabled` is false or stdout isn't a
+    /// terminal. Falls back to stdout if the pager can't be started.
+    pub fn paged(enabled: bool) -> Output {
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        if !enabled || !io::stdout().is_terminal() || pager.trim().is_empty() || pager == "cat" {
+            return Output::Stdout(io::stdout());
+        }
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&pager).stdin(Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            // Quit when everything fits on one screen, show colors and don't clear the screen.
+            command.env("LESS", "FRX");
+        }
+        match command.spawn() {
+            Ok(child) => Output::Pager(child),
+            Err(_) => Output::Stdout(io::stdout()),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Output::Stdout(stdout) => stdout,
+            Output::Pager(child) => child.stdin.as_mut().expect("the pager's stdin is piped"),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for Output {
+    /// Waits for the pager to be closed.
+    fn drop(&mut self) {
+        if let Output::Pager(child) = self {
+            drop(child.stdin.take());
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Clears the screen and runs `f` every second, until interrupted.
+pub fn watch<F: FnMut() -> Result<()>>(mut f: F) -> Result<()> {
+    loop {
+        print!("\x1b[2J\x1b[H");
+        f()?;
+        io::stdout().flush()?;
+        thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+/// Treats output that stopped being read, like when a pager is closed early, as a success.
+pub fn ignore_broken_pipe(result: Result<()>) -> Result<()> {
+    match result {
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    config::Config,
+    events::{announce, notify, run_hook, Event},
+    file::{update_last_entry, WriteOptions},
+    first_line, stop_entry, Result,
+};
+use chrono::{Duration, Local};
+use std::{collections::BTreeMap, iter::once, thread};
+use timelog::{format_dur, Entry};
+
+/// Runs `cycles` pomodoros of `work` on `goal`, with a break of `pause` after each but the last.
+///
+/// Work intervals are tagged `pomodoro` and `work` besides the tags from the config, breaks
+/// `pomodoro` and `break`, and both are numbered in their `pomodoro` meta, like 2/4.
+pub fn pomodoro(
+    log_file: &str,
+    config: &Config,
+    goal: &str,
+    work: Duration,
+    pause: Duration,
+    cycles: u32,
+) -> Result<()> {
+    let mut work_tags = config.tags.clone();
+    work_tags.extend(["pomodoro".to_string(), "work".to_string()]);
+    for cycle in 1..=cycles {
+        let count = format!("{}/{}", cycle, cycles);
+        let meta: BTreeMap<_, _> = once(("pomodoro".to_string(), count.clone())).collect();
+        let entry = Entry {
+            goal: goal.to_string(),
+            tags: work_tags.clone(),
+            meta: meta.clone(),
+            estimate: Some(work),
+            user: config.user(),
+            ..Entry::default()
+        };
+        println!("Pomodoro {}: working for {}", count, format_dur(work));
+        run_interval(log_file, config, entry, work)?;
+        if cycle == cycles {
+            let summary = format!("Pomodoros done: {}", first_line(goal));
+            notify(&summary, &format!("{} of them", cycles));
+            break;
+        }
+        notify(
+            &format!("Break {}", count),
+            &format!("Take {} off", format_dur(pause)),
+        );
+        let entry = Entry {
+            goal: "Break".to_string(),
+            tags: vec!["pomodoro".to_string(), "break".to_string()],
+            meta,
+            estimate: Some(pause),
+            user: config.user(),
+            ..Entry::default()
+        };
+        println!("Pomodoro {}: break for {}", count, format_dur(pause));
+        run_interval(log_file, config, entry, pause)?;
+        notify(&format!("Back to work: {}", first_line(goal)), "");
+    }
+    Ok(())
+}
+
+/// Starts `entry`, waits for `length` and stops it again, running the same hooks as `start` and
+/// `stop`.
+fn run_interval(log_file: &str, config: &Config, entry: Entry, length: Duration) -> Result<()> {
+    let mut started = None;
+    update_last_entry(log_file, WriteOptions::default(), |log| {
+        if log.active().is_some() {
+            Err("an entry is already running, stop it first")?;
+        }
+        let entry = log.start_with(entry);
+        run_hook(log_file, "pre", Event::Start, entry)?;
+        started = Some(entry.clone());
+        Ok(())
+    })?;
+    let started = started.expect("an entry was started");
+    announce(log_file, config, Event::Start, &started);
+    thread::sleep(length.to_std()?);
+    let mut stopped = None;
+    update_last_entry(log_file, WriteOptions::default(), |log| {
+        if log.active().map(|e| e.id) != Some(started.id) {
+            Err("the pomodoro was interrupted, its entry isn't running anymore")?;
+        }
+        let entry = stop_entry(log, config, String::new(), Local::now().fixed_offset())?;
+        run_hook(log_file, "pre", Event::Stop, &entry)?;
+        stopped = Some(entry);
+        Ok(())
+    })?;
+    let stopped = stopped.expect("an entry was stopped");
+    announce(log_file, config, Event::Stop, &stopped);
+    Ok(())
+}
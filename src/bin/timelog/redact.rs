@@ -0,0 +1,96 @@
+use crate::{file::get_file_reader, Result};
+use hmac::{Hmac, KeyInit, Mac};
+use regex::{NoExpand, Regex};
+use sha2::Sha256;
+use std::{borrow::Cow, fs::File, io::BufWriter};
+use timelog::{read_entries_lenient, write_entries, AuditRecord, Entry, Trash};
+
+/// Replaces the goal, result, notes and the meta other than `project` of `entry` with hashes
+/// keyed with `salt`.
+///
+/// Equal texts get equal hashes, so entries can still be grouped by them, but the texts can't be
+/// guessed without the salt. Empty texts stay empty.
+pub fn anonymize_entry(entry: &mut Entry, salt: &str) -> Result<()> {
+    let hash = |text: &mut String| -> Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes())?;
+        mac.update(text.as_bytes());
+        let bytes = mac.finalize().into_bytes();
+        *text = bytes[..6].iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(())
+    };
+    hash(&mut entry.goal)?;
+    hash(&mut entry.result)?;
+    for note in &mut entry.notes {
+        hash(note)?;
+    }
+    for (key, value) in &mut entry.meta {
+        if key != "project" {
+            hash(value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the text matching `pattern` in the goal, result and notes of `entry` with `replace`,
+/// returning whether anything matched.
+pub fn redact_entry(entry: &mut Entry, pattern: &Regex, replace: &str) -> bool {
+    let mut redacted = redact_text(&mut entry.goal, pattern, replace);
+    redacted |= redact_text(&mut entry.result, pattern, replace);
+    for note in &mut entry.notes {
+        redacted |= redact_text(note, pattern, replace);
+    }
+    redacted
+}
+
+fn redact_text(text: &mut String, pattern: &Regex, replace: &str) -> bool {
+    match pattern.replace_all(text, NoExpand(replace)) {
+        Cow::Owned(redacted) => {
+            *text = redacted;
+            true
+        }
+        Cow::Borrowed(_) => false,
+    }
+}
+
+/// Redacts the copies of entries that are kept next to the log: the trash, the audit trail,
+/// including the command lines in it, and the backup.
+pub fn redact_sidecars(log_file: &str, pattern: &Regex, replace: &str) -> Result<()> {
+    let trash_path = Trash::path_for(log_file);
+    let mut trash = Trash::read(&trash_path)?;
+    let mut redacted = false;
+    for t in &mut trash.entries {
+        redacted |= redact_entry(&mut t.entry, pattern, replace);
+    }
+    if redacted {
+        trash.write(&trash_path)?;
+    }
+
+    let audit_path = AuditRecord::path_for(log_file);
+    let mut records = AuditRecord::read_all(&audit_path)?;
+    let mut redacted = false;
+    for r in &mut records {
+        redacted |= redact_text(&mut r.command, pattern, replace);
+        for e in r.before.iter_mut().chain(r.after.iter_mut()) {
+            redacted |= redact_entry(e, pattern, replace);
+        }
+    }
+    if redacted {
+        AuditRecord::rewrite(&audit_path, &records)?;
+    }
+
+    let backup = format!("{}.bak", log_file);
+    if let Some(reader) = get_file_reader(&backup)? {
+        let mut entries = read_entries_lenient(reader)?.entries;
+        let mut redacted = false;
+        for e in &mut entries {
+            redacted |= redact_entry(e, pattern, replace);
+        }
+        if redacted {
+            write_entries(BufWriter::new(File::create(&backup)?), &entries)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use crate::{config::Config, file::get_entry_iter, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use timelog::{
+    validate, DurationFormat, Earnings, Entry, EntryTemplate, Label, Language, MoneyFormat, Period,
+    Range, TimeFormat, Timezone, ValidationIssue,
+};
+
+/// Every entry of the log as it is billed, with the rate from the config for the ones that have
+/// none of their own.
+///
+/// Billable entries are rounded the way their client is billed, by moving their stop time.
+pub fn billed_entries(log_file: &str, lenient: bool, config: &Config) -> Result<Vec<Entry>> {
+    let mut entries = get_entry_iter(log_file, lenient)?.collect::<timelog::Result<Vec<_>>>()?;
+    for e in &mut entries {
+        if e.rate.is_none() && e.expense.is_none() {
+            e.rate = config.rate_for(e);
+        }
+        if let (true, Some(start), Some(duration)) = (e.billable, e.start, e.duration()) {
+            e.stop = Some(start + config.billed(e, duration));
+        }
+    }
+    Ok(entries)
+}
+
+/// The logs in `dir`, which are the files ending in `.json`, in the order of their names.
+pub fn team_logs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut logs = Vec::new();
+    let files = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    for file in files {
+        let path = file?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "json") {
+            logs.push(path);
+        }
+    }
+    if logs.is_empty() {
+        Err(format!("there are no logs in {}", dir.display()))?;
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+/// The entries of the log of one person at `path`, with the name of the file, like `alice` for
+/// `alice.json`, as the user of the entries without one.
+pub fn read_person_log(path: &Path, lenient: bool) -> Result<Vec<Entry>> {
+    let name = path.to_string_lossy();
+    let person = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+    let mut entries = get_entry_iter(&name, lenient)?.collect::<timelog::Result<Vec<_>>>()?;
+    for e in &mut entries {
+        if e.user.is_none() {
+            e.user = person.clone();
+        }
+    }
+    Ok(entries)
+}
+
+/// Everything about the time spent for one client in a range of days, with the billable entries
+/// as they are billed.
+pub struct ClientReport<'a> {
+    client: &'a str,
+    range: Range,
+    entries: Vec<&'a Entry>,
+    /// The total, billable time and earnings per `project` meta, with `""` for the entries
+    /// without one.
+    projects: BTreeMap<&'a str, (Duration, Duration, Earnings)>,
+}
+
+impl<'a> ClientReport<'a> {
+    pub fn new(entries: &'a [Entry], client: &'a str, range: Range) -> Result<Self> {
+        let entries: Vec<_> = entries
+            .iter()
+            .filter(|e| e.client.as_deref() == Some(client))
+            .filter(|e| {
+                let day = e.start.map(|s| Timezone::Local.date(&s));
+                day.is_some_and(|day| range.contains(day))
+            })
+            .collect();
+        if entries.is_empty() {
+            Err(format!("there are no entries for {} to report on", client))?;
+        }
+        let mut projects = BTreeMap::new();
+        for e in &entries {
+            let duration = match e.duration() {
+                Some(duration) => duration,
+                None => continue,
+            };
+            let project = e.meta.get("project").map_or("", String::as_str);
+            let (total, billable, earned) = projects
+                .entry(project)
+                .or_insert_with(|| (Duration::zero(), Duration::zero(), Earnings::new()));
+            *total += duration;
+            if e.billable {
+                *billable += duration;
+                if let Some(rate) = &e.rate {
+                    earned.add(rate.earnings(duration));
+                }
+            }
+        }
+        Ok(ClientReport {
+            client,
+            range,
+            entries,
+            projects,
+        })
+    }
+
+    /// Writes the report as text, or as markdown, with every entry as a line of `format`.
+    pub fn write(
+        &self,
+        markdown: bool,
+        format: &EntryTemplate,
+        duration_format: DurationFormat,
+        money_format: MoneyFormat,
+        language: Language,
+    ) -> String {
+        let duration_format = duration_format.seconds(false);
+        let duration = |d: &Duration| duration_format.format(*d);
+        let heading = |level: usize, text: &str| {
+            if markdown {
+                format!("{} {}\n\n", "#".repeat(level), text)
+            } else {
+                format!("{}\n\n", text)
+            }
+        };
+        let days = match (self.range.from, self.range.to.and_then(|to| to.pred_opt())) {
+            (Some(from), Some(to)) => format!(", {} to {}", from, to),
+            (Some(from), None) => format!(", from {}", from),
+            (None, Some(to)) => format!(", up to {}", to),
+            (None, None) => String::new(),
+        };
+        let mut out = heading(1, &format!("Report for {}{}", self.client, days));
+
+        out += &heading(2, "Entries");
+        for e in &self.entries {
+            let line = format.render(e);
+            if markdown {
+                out += &format!("- {}\n", line.trim_end());
+            } else {
+                out += &format!("  {}\n", line.trim_end());
+            }
+        }
+        out += "\n";
+
+        out += &heading(2, "Projects");
+        let mut total = (Duration::zero(), Duration::zero(), Earnings::new());
+        if markdown {
+            out += "| Project | Time | Billable | Earned |\n|---|---:|---:|---:|\n";
+        }
+        for (project, (time, billable, earned)) in &self.projects {
+            let project = if project.is_empty() {
+                "(no project)"
+            } else {
+                project
+            };
+            let earned_text = money_format.format_earnings(earned);
+            if markdown {
+                out += &format!(
+                    "| {} | {} | {} | {} |\n",
+                    project.replace('|', "\\|"),
+                    duration(time),
+                    duration(billable),
+                    earned_text
+                );
+            } else {
+                out += &format!(
+                    "  {}: {} ({} {}), {} {}\n",
+                    project,
+                    duration(time),
+                    duration(billable),
+                    language.label(Label::BillableTime),
+                    language.label(Label::Earned),
+                    earned_text
+                );
+            }
+            total.0 += *time;
+            total.1 += *billable;
+            total.2.extend(earned);
+        }
+        let (time, billable, earned) = &total;
+        let earned = money_format.format_earnings(earned);
+        if markdown {
+            out += &format!(
+                "| **Total** | **{}** | **{}** | **{}** |\n",
+                duration(time),
+                duration(billable),
+                earned
+            );
+        } else {
+            out += &format!(
+                "\n{} {}{}, {} {}\n",
+                language.label(Label::Total),
+                duration(time),
+                billable_split(*billable, *time - *billable, duration_format, language),
+                language.label(Label::Earned),
+                earned
+            );
+        }
+        out
+    }
+}
+
+/// The time every person spent on every project in one week, going by the `project` meta.
+pub struct TeamReport {
+    /// The Monday of the week.
+    week: NaiveDate,
+    /// The time per person and project, with `""` for the entries without a project.
+    people: BTreeMap<String, BTreeMap<String, Duration>>,
+    projects: BTreeSet<String>,
+}
+
+impl TeamReport {
+    /// Totals the stopped entries of the `week` starting on the given Monday, from the log of
+    /// every person at its path.
+    ///
+    /// This fails if the entries of one person in that week overlap, since their time would be
+    /// counted twice.
+    pub fn new(logs: &[(&Path, Vec<Entry>)], week: NaiveDate) -> Result<Self> {
+        let range = Range::new(Some(week), Some(week + Duration::days(7)));
+        let in_week = |e: &Entry| {
+            let day = e.start.map(|s| Timezone::Local.date(&s));
+            day.is_some_and(|day| range.contains(day))
+        };
+        let mut overlaps = Vec::new();
+        for (path, entries) in logs {
+            for issue in validate(entries) {
+                if let ValidationIssue::Overlap(a, b) = &issue {
+                    let (a, b) = (&entries[a.index - 1], &entries[b.index - 1]);
+                    if a.user == b.user && (in_week(a) || in_week(b)) {
+                        overlaps.push(format!("{}: {}", path.display(), issue));
+                    }
+                }
+            }
+        }
+        if !overlaps.is_empty() {
+            Err(format!(
+                "some entries overlap, so their time would be counted twice:\n{}",
+                overlaps.join("\n")
+            ))?;
+        }
+
+        let mut people = BTreeMap::<_, BTreeMap<_, _>>::new();
+        let mut projects = BTreeSet::new();
+        for e in logs.iter().flat_map(|(_, entries)| entries) {
+            let duration = match e.duration() {
+                Some(duration) if in_week(e) => duration,
+                _ => continue,
+            };
+            let person = e.user.clone().unwrap_or_default();
+            let project = e.meta.get("project").cloned().unwrap_or_default();
+            let time = people.entry(person).or_default();
+            *time.entry(project.clone()).or_insert_with(Duration::zero) += duration;
+            projects.insert(project);
+        }
+        if people.is_empty() {
+            Err(format!(
+                "there is no time to report on in week {}",
+                week.format("%G-W%V")
+            ))?;
+        }
+        Ok(TeamReport {
+            week,
+            people,
+            projects,
+        })
+    }
+
+    /// Writes the report as a table of people by projects, with the totals of both, as text or
+    /// as markdown.
+    pub fn write(&self, markdown: bool, duration_format: DurationFormat) -> String {
+        let duration_format = duration_format.seconds(false);
+        let duration = |d: Duration| duration_format.format(d);
+        let last = self.week + Duration::days(6);
+        let title = format!(
+            "Timesheet for week {}, {} to {}",
+            self.week.format("%G-W%V"),
+            self.week,
+            last
+        );
+
+        let mut header = vec!["Person".to_string()];
+        header.extend(self.projects.iter().map(|p| match p.as_str() {
+            "" => "(no project)".to_string(),
+            p => p.to_string(),
+        }));
+        header.push("Total".to_string());
+        let mut rows = vec![header];
+        let mut totals = BTreeMap::<&str, Duration>::new();
+        for (person, time) in &self.people {
+            let mut row = vec![match person.as_str() {
+                "" => "(no user)".to_string(),
+                p => p.to_string(),
+            }];
+            for project in &self.projects {
+                let cell = time.get(project).copied();
+                row.push(cell.map_or_else(String::new, duration));
+                if let Some(cell) = cell {
+                    *totals.entry(project).or_insert_with(Duration::zero) += cell;
+                }
+            }
+            row.push(duration(time.values().copied().sum()));
+            rows.push(row);
+        }
+        let mut total_row = vec!["Total".to_string()];
+        total_row.extend(self.projects.iter().map(|p| duration(totals[p.as_str()])));
+        total_row.push(duration(totals.values().copied().sum()));
+        rows.push(total_row);
+
+        if markdown {
+            let cell = |text: &str| text.replace('|', "\\|");
+            let mut out = format!("# {}\n\n", title);
+            let line = |row: &[String]| {
+                let cells: Vec<_> = row.iter().map(|c| cell(c)).collect();
+                format!("| {} |\n", cells.join(" | "))
+            };
+            out += &line(&rows[0]);
+            out += &format!("|---|{}\n", "---:|".repeat(rows[0].len() - 1));
+            for row in &rows[1..rows.len() - 1] {
+                out += &line(row);
+            }
+            let total: Vec<_> = rows[rows.len() - 1]
+                .iter()
+                .map(|c| format!("**{}**", c))
+                .collect();
+            out += &line(&total);
+            out
+        } else {
+            let widths: Vec<_> = (0..rows[0].len())
+                .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+                .collect();
+            let mut out = format!("{}\n\n", title);
+            for row in &rows {
+                let mut line = format!("{:1$}", row[0], widths[0]);
+                for (cell, width) in row.iter().zip(&widths).skip(1) {
+                    let cell = if cell.is_empty() { "-" } else { cell };
+                    line += &format!("  {:>1$}", cell, width);
+                }
+                out += line.trim_end();
+                out += "\n";
+            }
+            out
+        }
+    }
+}
+
+/// How much of `budget` is used in percent.
+pub fn budget_percent(used: Duration, budget: Duration) -> f64 {
+    if budget <= Duration::zero() {
+        return f64::INFINITY;
+    }
+    used.num_milliseconds() as f64 / budget.num_milliseconds() as f64 * 100.0
+}
+
+/// How much of `budget` is used, like `12h of 80h (15%), 68h left`.
+pub fn budget_use(used: Duration, budget: Duration, format: DurationFormat) -> String {
+    let left = if used <= budget {
+        format!("{} left", format.format(budget - used))
+    } else {
+        format!("{} over", format.format(used - budget))
+    };
+    format!(
+        "{} of {} ({:.0}%), {}",
+        format.format(used),
+        format.format(budget),
+        budget_percent(used, budget),
+        left
+    )
+}
+
+/// The billable and the other time, to put after a total in a summary.
+pub fn billable_split(
+    billable: Duration,
+    other: Duration,
+    format: DurationFormat,
+    language: Language,
+) -> String {
+    format!(
+        " ({} {}, {} {})",
+        format.format(billable),
+        language.label(Label::BillableTime),
+        format.format(other),
+        language.label(Label::NonBillableTime)
+    )
+}
+
+/// The name of the period of the given length that starts on `start`.
+pub fn format_period(
+    period: Period,
+    start: NaiveDate,
+    time_format: TimeFormat,
+    language: Language,
+) -> String {
+    let label = |label| language.label(label);
+    match period {
+        Period::Day | Period::Custom(_) => time_format.format_date(start, "%v"),
+        Period::ISO_WEEK => {
+            let week = start.iso_week();
+            format!("{}, {} {}", week.year(), label(Label::Week), week.week())
+        }
+        Period::Week { .. } => {
+            let start = time_format.format_date(start, "%v");
+            format!("{} {}", label(Label::WeekOf), start)
+        }
+        Period::Month => time_format.format_date(start, "%B %Y"),
+        Period::Quarter => {
+            let quarter = start.month0() / 3 + 1;
+            format!("{}{} {}", label(Label::Quarter), quarter, start.year())
+        }
+        Period::Year => start.format("%Y").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use timelog::{Expense, Money, Rate};
+
+    fn entry(client: &str, billable: bool, start: &str, stop: &str) -> Entry {
+        Entry {
+            start: Some(DateTime::parse_from_rfc3339(start).unwrap()),
+            stop: Some(DateTime::parse_from_rfc3339(stop).unwrap()),
+            client: Some(client.to_string()),
+            billable,
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn billed_entries_are_rounded_and_rated() {
+        let config: Config = toml::from_str(
+            r#"
+            [clients]
+            acme = { round = "15m up" }
+
+            [rates]
+            default = "100 EUR"
+            site = "80 EUR"
+            "#,
+        )
+        .unwrap();
+        let mut site = entry("acme", true, "2024-03-04T10:00:00Z", "2024-03-04T10:10:00Z");
+        site.meta.insert("project".to_string(), "site".to_string());
+        let expense = Entry {
+            client: Some("acme".to_string()),
+            billable: true,
+            expense: Some(Expense::new(
+                NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+                "12 EUR".parse::<Money>().unwrap(),
+            )),
+            ..Entry::default()
+        };
+        let entries = vec![
+            entry("acme", true, "2024-03-04T10:00:00Z", "2024-03-04T10:50:00Z"),
+            entry(
+                "acme",
+                false,
+                "2024-03-04T11:00:00Z",
+                "2024-03-04T11:50:00Z",
+            ),
+            Entry {
+                rate: Some(Rate::new(5000, "EUR")),
+                ..entry(
+                    "globex",
+                    true,
+                    "2024-03-04T12:00:00Z",
+                    "2024-03-04T12:50:00Z",
+                )
+            },
+            site,
+            expense,
+        ];
+        let path = std::env::temp_dir().join(format!("timelog-billed-{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        let billed = billed_entries(&path.to_string_lossy(), false, &config);
+        fs::remove_file(&path).unwrap();
+        let billed = billed.unwrap();
+
+        let stops: Vec<_> = billed
+            .iter()
+            .map(|e| e.stop.map(|s| s.to_rfc3339()))
+            .collect();
+        assert_eq!(
+            stops,
+            [
+                Some("2024-03-04T11:00:00+00:00".to_string()),
+                Some("2024-03-04T11:50:00+00:00".to_string()),
+                Some("2024-03-04T12:50:00+00:00".to_string()),
+                Some("2024-03-04T10:15:00+00:00".to_string()),
+                None,
+            ]
+        );
+        let rates: Vec<_> = billed
+            .iter()
+            .map(|e| e.rate.as_ref().map(|r| r.cents))
+            .collect();
+        assert_eq!(
+            rates,
+            [Some(10000), Some(10000), Some(5000), Some(8000), None]
+        );
+    }
+}
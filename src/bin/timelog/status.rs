@@ -0,0 +1,166 @@
+use crate::{file::get_entry_iter, first_line, Result};
+use chrono::{DateTime, Duration, Local};
+use std::{env, fs, path::PathBuf, str::FromStr};
+use timelog::{DurationFormat, Entry, Label, Language, Style, TimeFormat};
+
+/// Prints the running entry, if there is one, with how long it has been running.
+pub fn print_status(
+    last: Option<Entry>,
+    color: bool,
+    time_format: TimeFormat,
+    duration_format: DurationFormat,
+    language: Language,
+) -> Result<()> {
+    let (entry, start) = match last {
+        Some(e) if e.is_active() => match e.start {
+            Some(start) => (e, start),
+            None => Err("the running entry has no start time")?,
+        },
+        _ => {
+            println!("{}", language.label(Label::NothingRunning));
+            return Ok(());
+        }
+    };
+    let now = Local::now();
+    let start = start.with_timezone(&Local);
+    let since = if start.date_naive() == now.date_naive() {
+        time_format.format(&start, time_format.time())
+    } else {
+        time_format.format(&start, time_format.date_time())
+    };
+    let goal = entry.goal.lines().next().unwrap_or("").trim();
+    println!("{}", Style::Active.paint(goal, color));
+    println!(
+        "{} {}, {} {}",
+        language.label(Label::RunningFor),
+        Style::Duration.paint(duration_format.format(now - start), color),
+        language.label(Label::Since),
+        since
+    );
+    Ok(())
+}
+
+/// How long the running `entry` has run, in whole minutes, since seconds would only make status
+/// bars and prompts wider.
+pub fn running_minutes(entry: &Entry) -> Result<Duration> {
+    let start = entry.start.ok_or("the running entry has no start time")?;
+    let running = Local::now().fixed_offset() - start;
+    Ok(Duration::minutes(running.num_minutes()))
+}
+
+/// Shortens `text` to `max` characters, ending it with `…` if anything was cut off.
+pub fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+/// Prints an xbar or SwiftBar plugin: the running entry as the title, and a menu of today's
+/// entries with actions to start or stop an entry.
+pub fn print_xbar(
+    log_file: &str,
+    lenient: bool,
+    max_width: usize,
+    time_format: TimeFormat,
+    duration_format: DurationFormat,
+) -> Result<()> {
+    // `|` starts the parameters of a line.
+    let line = |text: &str| text.replace('|', "¦");
+    let entries = get_entry_iter(log_file, lenient)?.collect::<timelog::Result<Vec<_>>>()?;
+    let active = entries.last().filter(|e| e.is_active());
+    match active {
+        Some(entry) => {
+            let running = duration_format.format(running_minutes(entry)?);
+            let goal = shorten(first_line(&entry.goal), max_width);
+            println!("▶ {} {}", running, line(&goal));
+        }
+        None => println!("■"),
+    }
+    println!("---");
+    let now = Local::now();
+    let today = now.date_naive();
+    let mut total = Duration::zero();
+    for entry in &entries {
+        let start = match entry.start {
+            Some(start) if start.with_timezone(&Local).date_naive() == today => start,
+            _ => continue,
+        };
+        let duration = entry.stop.unwrap_or_else(|| now.fixed_offset()) - start;
+        total += duration;
+        println!(
+            "{} {} {}",
+            time_format.format(&start.with_timezone(&Local), time_format.time()),
+            duration_format.format(duration),
+            line(first_line(&entry.goal))
+        );
+    }
+    println!("Today: {}", duration_format.format(total));
+    println!("---");
+    let exe = env::current_exe()?;
+    let log_file = fs::canonicalize(log_file).unwrap_or_else(|_| PathBuf::from(log_file));
+    let action = |name: &str, command: &str| {
+        println!(
+            "{} | bash=\"{}\" param1=-l param2=\"{}\" param3=--no-prompt param4={} terminal=false refresh=true",
+            name,
+            exe.display(),
+            log_file.display(),
+            command
+        );
+    };
+    if active.is_some() {
+        action("Stop", "stop");
+    } else {
+        action("Start", "start");
+    }
+    Ok(())
+}
+
+/// How `status` prints the running entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFormat {
+    Text,
+    /// The entry on one line, with whether it is running and its duration, or null.
+    Json,
+    /// A line for the status bar of tmux, with its style codes, or nothing.
+    Tmux,
+    /// The JSON of a custom waybar module, with the class `running` or `idle`.
+    Waybar,
+    /// A plugin for the menu bar of macOS through xbar or SwiftBar.
+    Xbar,
+}
+
+impl FromStr for StatusFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(StatusFormat::Text),
+            "json" => Ok(StatusFormat::Json),
+            "tmux" => Ok(StatusFormat::Tmux),
+            "waybar" => Ok(StatusFormat::Waybar),
+            "xbar" => Ok(StatusFormat::Xbar),
+            _ => Err(format!(
+                "`{}` is not a status format, expected text, json, tmux, waybar or xbar",
+                s
+            )),
+        }
+    }
+}
+
+/// `entry` as JSON for scripts, with whether it is running and its duration in seconds, which
+/// for a running entry is how long it has run until `now`.
+pub fn entry_json(entry: &Entry, now: DateTime<Local>) -> serde_json::Value {
+    let mut value = serde_json::to_value(entry).expect("entries can be written as JSON");
+    let duration = entry
+        .duration()
+        .or_else(|| Some(now.fixed_offset() - entry.start?).filter(|_| entry.is_active()));
+    if let serde_json::Value::Object(fields) = &mut value {
+        let seconds = duration.map(|d| d.num_seconds());
+        fields.insert("duration".to_string(), seconds.into());
+        fields.insert("active".to_string(), entry.is_active().into());
+    }
+    value
+}
@@ -0,0 +1,124 @@
+use crate::{Absence, Entry, Error, Expense, Rate, Result};
+use chrono::{DateTime, Duration, TimeZone};
+
+/// Builds an `Entry`, checking that it makes sense.
+#[derive(Default, Debug, Clone)]
+pub struct EntryBuilder {
+    entry: Entry,
+    require_goal: bool,
+}
+
+impl EntryBuilder {
+    pub fn id(mut self, id: u64) -> Self {
+        self.entry.id = Some(id);
+        self
+    }
+
+    pub fn start<Tz: TimeZone>(mut self, time: DateTime<Tz>) -> Self {
+        self.entry.start = Some(time.fixed_offset());
+        self
+    }
+
+    pub fn stop<Tz: TimeZone>(mut self, time: DateTime<Tz>) -> Self {
+        self.entry.stop = Some(time.fixed_offset());
+        self
+    }
+
+    pub fn goal<S: Into<String>>(mut self, goal: S) -> Self {
+        self.entry.goal = goal.into();
+        self
+    }
+
+    pub fn result<S: Into<String>>(mut self, result: S) -> Self {
+        self.entry.result = result.into();
+        self
+    }
+
+    /// Adds a note. Notes are kept in the order they were added.
+    pub fn note<S: Into<String>>(mut self, note: S) -> Self {
+        self.entry.notes.push(note.into());
+        self
+    }
+
+    /// Adds a tag, unless the entry already has it.
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        let tag = tag.into();
+        if !self.entry.tags.contains(&tag) {
+            self.entry.tags.push(tag);
+        }
+        self
+    }
+
+    /// Attaches `value` to the entry under `key`, replacing any earlier value.
+    pub fn meta<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.entry.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets how long the entry is expected to take.
+    pub fn estimate(mut self, estimate: Duration) -> Self {
+        self.entry.estimate = Some(estimate);
+        self
+    }
+
+    /// Sets who the work is done for.
+    pub fn client<S: Into<String>>(mut self, client: S) -> Self {
+        self.entry.client = Some(client.into());
+        self
+    }
+
+    /// Sets whether the time can be billed to the client.
+    pub fn billable(mut self, billable: bool) -> Self {
+        self.entry.billable = billable;
+        self
+    }
+
+    /// Sets what an hour of the work is worth.
+    pub fn rate(mut self, rate: Rate) -> Self {
+        self.entry.rate = Some(rate);
+        self
+    }
+
+    /// Sets who did the work.
+    pub fn user<S: Into<String>>(mut self, user: S) -> Self {
+        self.entry.user = Some(user.into());
+        self
+    }
+
+    /// Makes the entry an expense instead of time spent.
+    pub fn expense(mut self, expense: Expense) -> Self {
+        self.entry.expense = Some(expense);
+        self
+    }
+
+    /// Makes the entry an absence instead of time spent.
+    pub fn absence(mut self, absence: Absence) -> Self {
+        self.entry.absence = Some(absence);
+        self
+    }
+
+    /// Makes `build` fail if the entry has no goal.
+    pub fn require_goal(mut self) -> Self {
+        self.require_goal = true;
+        self
+    }
+
+    /// Builds the entry.
+    ///
+    /// This fails if the entry stops without having started, stops before it starts, or has no
+    /// goal when one is required.
+    pub fn build(self) -> Result<Entry> {
+        let entry = self.entry;
+        match (entry.start, entry.stop) {
+            (None, Some(_)) => return Err(Error::MissingStart),
+            (Some(start), Some(stop)) if stop < start => {
+                return Err(Error::InvalidRange { start, stop })
+            }
+            _ => {}
+        }
+        if self.require_goal && entry.goal.trim().is_empty() {
+            return Err(Error::MissingGoal);
+        }
+        Ok(entry)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::Result;
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The SHA-256 digest of a log, stored next to it to detect edits made outside of timelog.
+///
+/// The sidecar file uses the same format as `sha256sum`, so it can also be checked with
+/// `sha256sum -c`. Hashing the whole log is too slow to do on every command, so next to the digest
+/// timelog also remembers the size and modification time the log had when it last wrote it, the
+/// way an `Index` does. Commands only compare those, and the digest itself is checked when asked
+/// for (`check` and `repair`). Rewriting only the end of the log doesn't rehash it either, which
+/// leaves the digest behind until the next `verify` catches it up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum(String);
+
+/// The state of a log when timelog last wrote it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct Stamp {
+    log_len: u64,
+    log_modified: SystemTime,
+}
+
+impl Stamp {
+    fn of_file(log_path: &Path) -> Result<Stamp> {
+        let meta = fs::metadata(log_path)?;
+        Ok(Stamp {
+            log_len: meta.len(),
+            log_modified: meta.modified()?,
+        })
+    }
+
+    fn path_for(log_path: &Path) -> PathBuf {
+        let mut path = Checksum::path_for(log_path).into_os_string();
+        path.push(".stamp");
+        PathBuf::from(path)
+    }
+
+    fn read(log_path: &Path) -> Result<Option<Stamp>> {
+        match fs::read(Stamp::path_for(log_path)) {
+            Ok(s) => Ok(Some(serde_json::from_slice(&s)?)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, log_path: &Path) -> Result<()> {
+        fs::write(Stamp::path_for(log_path), serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+impl Checksum {
+    /// The path of the checksum belonging to the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".sha256");
+        PathBuf::from(path)
+    }
+
+    /// Computes the checksum of everything `reader` yields.
+    pub fn compute<R: Read>(mut reader: R) -> Result<Checksum> {
+        let mut hasher = Sha256::new();
+        let mut buf = [0; 8192];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let hex = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok(Checksum(hex))
+    }
+
+    /// Computes the checksum of the file at `path`.
+    pub fn of_file<P: AsRef<Path>>(path: P) -> Result<Checksum> {
+        Checksum::compute(File::open(path)?)
+    }
+
+    /// Reads the checksum stored at `path`, returning `None` if there is none.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Option<Checksum>> {
+        match fs::read_to_string(path) {
+            Ok(s) => Ok(s.split_whitespace().next().map(|s| Checksum(s.to_string()))),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stores the checksum of the log at `log_path` in its sidecar file.
+    pub fn update<P: AsRef<Path>>(log_path: P) -> Result<()> {
+        let log_path = log_path.as_ref();
+        Checksum::of_file(log_path)?.store(log_path)
+    }
+
+    fn store(&self, log_path: &Path) -> Result<()> {
+        debug!("the checksum of {} is now {}", log_path.display(), self);
+        let name = log_path.file_name().unwrap_or_default().to_string_lossy();
+        fs::write(
+            Checksum::path_for(log_path),
+            format!("{}  {}\n", self, name),
+        )?;
+        Checksum::touch(log_path)
+    }
+
+    /// Records that timelog just wrote the log at `log_path`, without rehashing it.
+    pub fn touch<P: AsRef<Path>>(log_path: P) -> Result<()> {
+        let log_path = log_path.as_ref();
+        Stamp::of_file(log_path)?.write(log_path)
+    }
+
+    /// Whether the log at `log_path` still has the size and modification time timelog left it
+    /// with, which is cheap enough to check on every command.
+    ///
+    /// Returns `None` when timelog hasn't recorded them yet.
+    pub fn is_fresh<P: AsRef<Path>>(log_path: P) -> Result<Option<bool>> {
+        let log_path = log_path.as_ref();
+        match Stamp::read(log_path)? {
+            Some(stamp) => Ok(Some(stamp == Stamp::of_file(log_path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks the log at `log_path` against its stored checksum, reading all of it.
+    ///
+    /// A digest that is only behind because timelog rewrote the end of the log is brought up to
+    /// date instead of being reported. Returns `None` when there is no stored checksum to compare
+    /// against.
+    pub fn verify<P: AsRef<Path>>(log_path: P) -> Result<Option<bool>> {
+        let log_path = log_path.as_ref();
+        let stored = match Checksum::read(Checksum::path_for(log_path))? {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        let actual = Checksum::of_file(log_path)?;
+        if actual != stored && Checksum::is_fresh(log_path)? != Some(true) {
+            return Ok(Some(false));
+        }
+        actual.store(log_path)?;
+        Ok(Some(true))
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A log in the temporary directory, removed again with its sidecar files when dropped.
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new(name: &str, contents: &str) -> TempLog {
+            let path = std::env::temp_dir().join(format!(
+                "timelog-checksum-{}-{}.json",
+                std::process::id(),
+                name
+            ));
+            fs::write(&path, contents).unwrap();
+            TempLog(path)
+        }
+
+        fn append(&self, s: &str) {
+            let mut file = fs::OpenOptions::new().append(true).open(&self.0).unwrap();
+            file.write_all(s.as_bytes()).unwrap();
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(Stamp::path_for(&self.0));
+            let _ = fs::remove_file(Checksum::path_for(&self.0));
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn compute() {
+        assert_eq!(
+            Checksum::compute(&b"abc"[..]).unwrap().to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn stored_like_sha256sum() {
+        let log = TempLog::new("sha256sum", "[]");
+        Checksum::update(&log.0).unwrap();
+        let stored = fs::read_to_string(Checksum::path_for(&log.0)).unwrap();
+        let name = log.0.file_name().unwrap().to_string_lossy();
+        let digest = Checksum::of_file(&log.0).unwrap();
+        assert_eq!(stored, format!("{}  {}\n", digest, name));
+        assert_eq!(
+            Checksum::read(Checksum::path_for(&log.0)).unwrap(),
+            Some(digest)
+        );
+    }
+
+    #[test]
+    fn without_a_checksum() {
+        let log = TempLog::new("none", "[]");
+        assert_eq!(Checksum::verify(&log.0).unwrap(), None);
+        assert_eq!(Checksum::is_fresh(&log.0).unwrap(), None);
+    }
+
+    #[test]
+    fn verify_unchanged() {
+        let log = TempLog::new("unchanged", "[]");
+        Checksum::update(&log.0).unwrap();
+        assert_eq!(Checksum::is_fresh(&log.0).unwrap(), Some(true));
+        assert_eq!(Checksum::verify(&log.0).unwrap(), Some(true));
+    }
+
+    #[test]
+    fn verify_edited() {
+        let log = TempLog::new("edited", "[]");
+        Checksum::update(&log.0).unwrap();
+        log.append("\n");
+        assert_eq!(Checksum::is_fresh(&log.0).unwrap(), Some(false));
+        assert_eq!(Checksum::verify(&log.0).unwrap(), Some(false));
+        // Nothing was brought up to date.
+        assert_eq!(Checksum::verify(&log.0).unwrap(), Some(false));
+    }
+
+    #[test]
+    fn verify_after_rewriting_the_tail() {
+        let log = TempLog::new("tail", "[]");
+        Checksum::update(&log.0).unwrap();
+        // Like rewriting the last entry does: the log changes, and only the stamp is updated.
+        log.append("\n");
+        Checksum::touch(&log.0).unwrap();
+        assert_eq!(Checksum::is_fresh(&log.0).unwrap(), Some(true));
+        let stored = Checksum::read(Checksum::path_for(&log.0)).unwrap();
+        assert_ne!(stored, Some(Checksum::of_file(&log.0).unwrap()));
+
+        assert_eq!(Checksum::verify(&log.0).unwrap(), Some(true));
+        let stored = Checksum::read(Checksum::path_for(&log.0)).unwrap();
+        assert_eq!(stored, Some(Checksum::of_file(&log.0).unwrap()));
+
+        // Edits after that are caught again.
+        log.append("\n");
+        assert_eq!(Checksum::verify(&log.0).unwrap(), Some(false));
+    }
+}
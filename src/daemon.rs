@@ -0,0 +1,113 @@
+use crate::{Entry, Error, Result};
+use log::debug;
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+/// A request to a daemon that keeps a log loaded, sent as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "request", rename_all = "kebab-case")]
+pub enum Request {
+    /// The latest entry.
+    Last,
+    /// Every entry, in order.
+    Entries,
+    /// Replaces the latest entry with `entries`, as long as it still is `last`.
+    ///
+    /// This is how `start`, `stop` and `note` change the log, the same way they change only the
+    /// end of a log file.
+    ReplaceLast {
+        last: Option<Box<Entry>>,
+        entries: Vec<Entry>,
+    },
+}
+
+/// The answer to a `Request`, sent as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "response", rename_all = "kebab-case")]
+pub enum Response {
+    Last { entry: Option<Box<Entry>> },
+    Entries { entries: Vec<Entry> },
+    Done,
+    Error { message: String },
+}
+
+/// A connection to the daemon keeping a log loaded.
+#[derive(Debug)]
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    /// The path of the socket of the daemon for the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".sock");
+        PathBuf::from(path)
+    }
+
+    /// Connects to the daemon listening at `path`.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Client> {
+        let path = path.as_ref();
+        let writer = UnixStream::connect(path)?;
+        debug!("connected to the daemon at {}", path.display());
+        Ok(Client {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Sends `request` and waits for the response.
+    ///
+    /// An error response is returned as `Error::Daemon`.
+    pub fn send(&mut self, request: &Request) -> Result<Response> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(Error::Daemon(
+                "the daemon closed the connection".to_string(),
+            ));
+        }
+        match serde_json::from_str(&line)? {
+            Response::Error { message } => Err(Error::Daemon(message)),
+            response => Ok(response),
+        }
+    }
+
+    /// The latest entry of the log.
+    pub fn last(&mut self) -> Result<Option<Entry>> {
+        match self.send(&Request::Last)? {
+            Response::Last { entry } => Ok(entry.map(|e| *e)),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Every entry of the log.
+    pub fn entries(&mut self) -> Result<Vec<Entry>> {
+        match self.send(&Request::Entries)? {
+            Response::Entries { entries } => Ok(entries),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Replaces the latest entry, which should be `last`, with `entries`.
+    pub fn replace_last(&mut self, last: Option<Entry>, entries: Vec<Entry>) -> Result<()> {
+        match self.send(&Request::ReplaceLast {
+            last: last.map(Box::new),
+            entries,
+        })? {
+            Response::Done => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+fn unexpected(response: Response) -> Error {
+    Error::Daemon(format!("unexpected response {:?}", response))
+}
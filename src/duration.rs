@@ -0,0 +1,491 @@
+use chrono::Duration;
+use std::str::FromStr;
+
+/// How a duration is laid out by `DurationFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DurationStyle {
+    /// Amounts with units, like `1d2h30m`.
+    #[default]
+    Units,
+    /// Hours and minutes like on a clock, like `26:30`.
+    Clock,
+    /// A fractional number of hours, like `26.50`.
+    Decimal,
+}
+
+impl FromStr for DurationStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "units" => Ok(DurationStyle::Units),
+            "clock" => Ok(DurationStyle::Clock),
+            "decimal" | "hours" => Ok(DurationStyle::Decimal),
+            _ => Err(format!(
+                "`{}` is not a duration style, expected units, clock or hours",
+                s
+            )),
+        }
+    }
+}
+
+/// Options for printing durations.
+///
+/// The default prints durations the way `parse_dur` reads them, with days, hours, minutes and
+/// seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationFormat {
+    pub style: DurationStyle,
+    /// Whether to show seconds. When they are hidden, durations are rounded to whole minutes.
+    pub seconds: bool,
+    /// Whether to show hours even when there are none, and count days in hours.
+    pub always_hours: bool,
+    /// How many decimals to show in the decimal style.
+    pub decimals: usize,
+}
+
+impl Default for DurationFormat {
+    fn default() -> Self {
+        DurationFormat {
+            style: DurationStyle::Units,
+            seconds: true,
+            always_hours: false,
+            decimals: 2,
+        }
+    }
+}
+
+impl DurationFormat {
+    pub fn new() -> Self {
+        DurationFormat::default()
+    }
+
+    pub fn style(mut self, style: DurationStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn seconds(mut self, seconds: bool) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    pub fn always_hours(mut self, always_hours: bool) -> Self {
+        self.always_hours = always_hours;
+        self
+    }
+
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Formats `dur`, starting with a `-` if it is negative.
+    pub fn format(&self, dur: Duration) -> String {
+        let sign = if dur < Duration::zero() { "-" } else { "" };
+        let dur = dur.abs();
+
+        if self.style == DurationStyle::Decimal {
+            let hours = dur.num_milliseconds() as f64 / 3_600_000.0;
+            let hours = format!("{:.*}", self.decimals, hours);
+            // What rounds to zero isn't negative anymore.
+            let sign = if hours.bytes().all(|b| b == b'0' || b == b'.') {
+                ""
+            } else {
+                sign
+            };
+            return format!("{}{}", sign, hours);
+        }
+
+        let mut secs = dur.num_seconds();
+        if !self.seconds {
+            secs = (secs + 30) / 60 * 60;
+        }
+        let sign = if secs == 0 { "" } else { sign };
+        let (m, s) = (secs / 60 % 60, secs % 60);
+        let (d, h) = match (self.style, self.always_hours) {
+            (DurationStyle::Units, false) => (secs / 86_400, secs / 3_600 % 24),
+            _ => (0, secs / 3_600),
+        };
+
+        let mut out = sign.to_string();
+        if self.style == DurationStyle::Clock {
+            out += &format!("{}:{:02}", h, m);
+            if self.seconds {
+                out += &format!(":{:02}", s);
+            }
+            return out;
+        }
+
+        if d != 0 {
+            out += &format!("{}d", d);
+        }
+        if h != 0 || self.always_hours {
+            out += &format!("{}h", h);
+        }
+        if m != 0 {
+            out += &format!("{}m", m);
+        }
+        if s != 0 && self.seconds {
+            out += &format!("{}s", s);
+        }
+        if out.len() == sign.len() {
+            out += if self.seconds { "0s" } else { "0m" };
+        }
+        out
+    }
+}
+
+/// Formats `dur` with the default `DurationFormat`, like `1d2h30m`.
+pub fn format_dur(dur: Duration) -> String {
+    DurationFormat::default().format(dur)
+}
+
+/// Parses a duration like the ones `format_dur` prints.
+///
+/// Accepted are amounts of days, hours, minutes and seconds (`1d2h30m`, `90m`, `1h 30m`),
+/// fractional amounts (`1.5h`) and clock notation (`1:30`, `1:30:15`). A leading `-` makes the
+/// duration negative.
+pub fn parse_dur(s: &str) -> Result<Duration, String> {
+    let trimmed = s.trim();
+    let (negative, rest) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, trimmed),
+    };
+    if rest.is_empty() {
+        return Err("the duration is empty".to_string());
+    }
+
+    let dur = if rest.contains(':') {
+        parse_clock(rest)
+    } else {
+        parse_units(rest)
+    }
+    .map_err(|e| format!("invalid duration `{}`: {}", s, e))?;
+
+    Ok(if negative { -dur } else { dur })
+}
+
+/// Parses `H:MM` or `H:MM:SS`.
+fn parse_clock(s: &str) -> Result<Duration, String> {
+    let parts: Vec<_> = s.split(':').collect();
+    if parts.len() > 3 {
+        return Err("expected hours:minutes or hours:minutes:seconds".to_string());
+    }
+
+    let mut dur = Duration::zero();
+    for (i, part) in parts.iter().enumerate() {
+        let n: i64 = part
+            .parse()
+            .map_err(|_| format!("`{}` is not a whole number", part))?;
+        if i != 0 && !(0..60).contains(&n) {
+            return Err(format!("`{}` is not between 0 and 59", part));
+        }
+        let part = match i {
+            0 => Duration::try_hours(n),
+            1 => Duration::try_minutes(n),
+            _ => Duration::try_seconds(n),
+        };
+        dur = part
+            .and_then(|part| dur.checked_add(&part))
+            .ok_or("the duration is too long")?;
+    }
+    Ok(dur)
+}
+
+/// Parses a sequence of amounts with units, like `1d 2.5h`.
+fn parse_units(s: &str) -> Result<Duration, String> {
+    let mut dur = Duration::zero();
+    let mut seen = String::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(len);
+        if number.is_empty() {
+            return Err(format!("expected a number before `{}`", after));
+        }
+        let amount: f64 = number
+            .parse()
+            .map_err(|_| format!("`{}` is not a number", number))?;
+
+        let unit = after
+            .chars()
+            .next()
+            .ok_or_else(|| format!("`{}` is missing a unit (d, h, m or s)", number))?;
+        let seconds = match unit {
+            'd' => 86_400.0,
+            'h' => 3_600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return Err(format!("`{}` is not a unit, expected d, h, m or s", unit)),
+        };
+        if seen.contains(unit) {
+            return Err(format!("`{}` is given more than once", unit));
+        }
+        seen.push(unit);
+
+        let nanos = (amount * seconds * 1e9).round();
+        if !nanos.is_finite() || nanos.abs() > i64::MAX as f64 {
+            return Err(format!("`{}{}` is too long", number, unit));
+        }
+        dur = dur
+            .checked_add(&Duration::nanoseconds(nanos as i64))
+            .ok_or("the duration is too long")?;
+        rest = after[unit.len_utf8()..].trim_start();
+    }
+    Ok(dur)
+}
+
+/// Which way `Rounding` rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundDirection {
+    Up,
+    Down,
+    #[default]
+    Nearest,
+}
+
+/// Rounding of durations to a multiple of a step, like `15m up`.
+///
+/// Written as a duration like `parse_dur` reads it, followed by `up`, `down` or `nearest`, which
+/// is the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounding {
+    pub step: Duration,
+    pub direction: RoundDirection,
+}
+
+impl Rounding {
+    /// Rounds `dur` to a multiple of the step, to the nearest millisecond.
+    pub fn round(&self, dur: Duration) -> Duration {
+        let step = self.step.num_milliseconds();
+        let millis = dur.num_milliseconds();
+        let down = millis.div_euclid(step) * step;
+        let rest = millis - down;
+        let rounded = match self.direction {
+            RoundDirection::Down => down,
+            RoundDirection::Up if rest == 0 => down,
+            RoundDirection::Up => down + step,
+            RoundDirection::Nearest if rest * 2 < step => down,
+            RoundDirection::Nearest => down + step,
+        };
+        Duration::milliseconds(rounded)
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (step, direction) = match s.rsplit_once(' ') {
+            Some((step, "up")) => (step, RoundDirection::Up),
+            Some((step, "down")) => (step, RoundDirection::Down),
+            Some((step, "nearest")) => (step, RoundDirection::Nearest),
+            _ => (s, RoundDirection::Nearest),
+        };
+        let step = parse_dur(step.trim())?;
+        if step.num_milliseconds() <= 0 {
+            return Err(format!(
+                "`{}` can't be rounded to, expected a positive step",
+                s
+            ));
+        }
+        Ok(Rounding { step, direction })
+    }
+}
+
+/// Reads an optional duration stored as a whole number of seconds.
+pub(crate) fn deserialize_seconds<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::{de::Error, Deserialize};
+
+    match Option::<i64>::deserialize(deserializer)? {
+        Some(seconds) => Duration::try_seconds(seconds)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("{} seconds is too long", seconds))),
+        None => Ok(None),
+    }
+}
+
+/// Stores a duration as a whole number of seconds, for `#[serde(with)]`.
+pub(crate) mod seconds {
+    use chrono::Duration;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dur: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(dur.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let seconds = i64::deserialize(deserializer)?;
+        Duration::try_seconds(seconds)
+            .ok_or_else(|| D::Error::custom(format!("{} seconds is too long", seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dur(s: &str) -> Duration {
+        parse_dur(s).unwrap()
+    }
+
+    #[test]
+    fn parse_units() {
+        assert_eq!(dur("90m"), Duration::minutes(90));
+        assert_eq!(dur("1d2h30m"), Duration::minutes((24 + 2) * 60 + 30));
+        assert_eq!(dur("1h 30m 15s"), Duration::seconds(5415));
+        assert_eq!(dur(" 2h "), Duration::hours(2));
+        assert_eq!(dur("0s"), Duration::zero());
+    }
+
+    #[test]
+    fn parse_fractions() {
+        assert_eq!(dur("1.5h"), Duration::minutes(90));
+        assert_eq!(dur(".5m"), Duration::seconds(30));
+        assert_eq!(dur("0.001s"), Duration::milliseconds(1));
+    }
+
+    #[test]
+    fn parse_clock() {
+        assert_eq!(dur("1:30"), Duration::minutes(90));
+        assert_eq!(dur("0:59:59"), Duration::seconds(3599));
+        assert_eq!(dur("26:00"), Duration::hours(26));
+        assert!(parse_dur("1:60").is_err());
+        assert!(parse_dur("1:00:60").is_err());
+        assert!(parse_dur("1:-1").is_err());
+        assert!(parse_dur("1:2:3:4").is_err());
+        assert!(parse_dur("1:").is_err());
+    }
+
+    #[test]
+    fn parse_negative() {
+        assert_eq!(dur("-1h"), Duration::hours(-1));
+        assert_eq!(dur("- 1:30"), Duration::minutes(-90));
+        assert!(parse_dur("-").is_err());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_dur("").is_err());
+        assert!(parse_dur("   ").is_err());
+        assert!(parse_dur("1").is_err());
+        assert!(parse_dur("h").is_err());
+        assert!(parse_dur("1x").is_err());
+        assert!(parse_dur("1h1h").is_err());
+        assert!(parse_dur("1..5h").is_err());
+        assert!(parse_dur("999999999999d").is_err());
+    }
+
+    #[test]
+    fn format_units() {
+        let format = DurationFormat::new();
+        assert_eq!(format.format(Duration::zero()), "0s");
+        assert_eq!(format.format(Duration::seconds(93_784)), "1d2h3m4s");
+        assert_eq!(format.format(Duration::hours(24)), "1d");
+        assert_eq!(format.format(Duration::minutes(-90)), "-1h30m");
+        assert_eq!(
+            format.always_hours(true).format(Duration::minutes(30)),
+            "0h30m"
+        );
+        assert_eq!(format.always_hours(true).format(Duration::hours(26)), "26h");
+    }
+
+    #[test]
+    fn format_without_seconds_rounds_to_minutes() {
+        let format = DurationFormat::new().seconds(false);
+        assert_eq!(format.format(Duration::zero()), "0m");
+        assert_eq!(format.format(Duration::seconds(29)), "0m");
+        assert_eq!(format.format(Duration::seconds(30)), "1m");
+        assert_eq!(format.format(Duration::seconds(3599)), "1h");
+        assert_eq!(format.format(Duration::seconds(-90)), "-2m");
+        assert_eq!(format.format(Duration::seconds(-20)), "0m");
+    }
+
+    #[test]
+    fn format_clock() {
+        let format = DurationFormat::new().style(DurationStyle::Clock);
+        assert_eq!(format.format(Duration::zero()), "0:00:00");
+        assert_eq!(format.format(Duration::seconds(95_415)), "26:30:15");
+        let format = format.seconds(false);
+        assert_eq!(format.format(Duration::seconds(95_415)), "26:30");
+        assert_eq!(format.format(Duration::minutes(-5)), "-0:05");
+        assert_eq!(format.format(Duration::seconds(-1)), "0:00");
+    }
+
+    #[test]
+    fn format_decimal() {
+        let format = DurationFormat::new().style(DurationStyle::Decimal);
+        assert_eq!(format.format(Duration::minutes(90)), "1.50");
+        assert_eq!(format.format(Duration::minutes(-90)), "-1.50");
+        assert_eq!(format.format(Duration::seconds(-1)), "0.00");
+        assert_eq!(format.decimals(0).format(Duration::minutes(150)), "2");
+    }
+
+    fn rounding(s: &str) -> Rounding {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_rounding() {
+        let up = Rounding {
+            step: Duration::minutes(15),
+            direction: RoundDirection::Up,
+        };
+        assert_eq!(rounding("15m up"), up);
+        assert_eq!(rounding(" 15m  up "), up);
+        assert_eq!(rounding("1h").direction, RoundDirection::Nearest);
+        assert_eq!(rounding("1h 30m down").step, Duration::minutes(90));
+        assert!("0m".parse::<Rounding>().is_err());
+        assert!("-15m".parse::<Rounding>().is_err());
+        assert!("15m sideways".parse::<Rounding>().is_err());
+        assert!("up".parse::<Rounding>().is_err());
+    }
+
+    #[test]
+    fn round_at_the_boundaries() {
+        let m = Duration::minutes;
+        let s = Duration::seconds;
+
+        let nearest = rounding("15m");
+        assert_eq!(nearest.round(Duration::zero()), Duration::zero());
+        assert_eq!(nearest.round(m(7) + s(29)), Duration::zero());
+        assert_eq!(nearest.round(m(7) + s(30)), m(15));
+        assert_eq!(nearest.round(m(15)), m(15));
+
+        let up = rounding("15m up");
+        assert_eq!(up.round(Duration::zero()), Duration::zero());
+        assert_eq!(up.round(m(15)), m(15));
+        assert_eq!(up.round(m(15) + Duration::milliseconds(1)), m(30));
+
+        let down = rounding("15m down");
+        assert_eq!(down.round(m(30) - Duration::milliseconds(1)), m(15));
+        assert_eq!(down.round(m(30)), m(30));
+    }
+
+    #[test]
+    fn round_negative_durations() {
+        let m = Duration::minutes;
+        assert_eq!(rounding("15m down").round(m(-1)), m(-15));
+        assert_eq!(rounding("15m up").round(m(-1)), Duration::zero());
+        assert_eq!(rounding("15m").round(m(-8)), m(-15));
+        assert_eq!(
+            rounding("15m").round(-m(7) - Duration::seconds(30)),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn parse_format_round_trip() {
+        for s in &["1d2h30m", "45m", "1h0m5s"] {
+            let d = dur(s);
+            assert_eq!(dur(&format_dur(d)), d);
+        }
+    }
+}
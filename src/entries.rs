@@ -0,0 +1,256 @@
+use crate::{Entry, Error, Result};
+use chrono::{DateTime, FixedOffset};
+use std::{
+    iter::FromIterator,
+    ops::{Deref, DerefMut},
+    slice, vec,
+};
+
+/// The entries of a log, always sorted by start time.
+///
+/// Entries can be read through slice methods. Changes go through methods that keep the entries
+/// sorted, so the first entry is always the earliest and the last one the latest.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "Vec<Entry>")]
+pub struct Entries(Vec<Entry>);
+
+impl Entries {
+    pub fn new() -> Self {
+        Entries::default()
+    }
+
+    /// Adds `entry` in its place in the order, after any equal entries, returning its index.
+    pub fn insert_sorted(&mut self, entry: Entry) -> usize {
+        let i = self.0.partition_point(|e| *e <= entry);
+        self.0.insert(i, entry);
+        i
+    }
+
+    /// Gives mutable access to the latest entry.
+    ///
+    /// If its start time is changed, it is moved back into place once the returned guard is
+    /// dropped.
+    pub fn last_mut(&mut self) -> Option<LastMut<'_>> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(LastMut { entries: self })
+        }
+    }
+
+    /// The latest entry, if it is still running.
+    pub fn active(&self) -> Option<&Entry> {
+        self.last().filter(|e| e.is_active())
+    }
+
+    /// Like `active`, but says why there is no running entry.
+    pub fn try_active(&self) -> Result<&Entry> {
+        match self.last() {
+            Some(e) if e.is_active() => Ok(e),
+            Some(_) => Err(Error::AlreadyStopped),
+            None => Err(Error::NoActiveEntry),
+        }
+    }
+
+    /// Gives mutable access to the latest entry, if it is still running.
+    pub fn active_mut(&mut self) -> Option<LastMut<'_>> {
+        if self.active().is_some() {
+            self.last_mut()
+        } else {
+            None
+        }
+    }
+
+    /// Stops the running entry at `stop`, recording `result`.
+    pub fn close_active(&mut self, stop: DateTime<FixedOffset>, result: String) -> Result<&Entry> {
+        let start = self.try_active()?.start;
+        if let Some(start) = start.filter(|&start| stop < start) {
+            return Err(Error::InvalidRange { start, stop });
+        }
+        {
+            let mut entry = self.last_mut().expect("there is a running entry");
+            entry.stop = Some(stop);
+            entry.result = result;
+        }
+        Ok(self.last().expect("the entry was just stopped"))
+    }
+
+    /// Removes and returns the latest entry.
+    pub fn pop(&mut self) -> Option<Entry> {
+        self.0.pop()
+    }
+
+    /// Removes and returns the entry at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Entry {
+        self.0.remove(index)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    pub fn retain<F: FnMut(&Entry) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    pub fn as_slice(&self) -> &[Entry] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<Entry> {
+        self.0
+    }
+}
+
+/// Mutable access to the latest entry of `Entries`, which keeps them sorted when dropped.
+pub struct LastMut<'a> {
+    entries: &'a mut Entries,
+}
+
+impl Deref for LastMut<'_> {
+    type Target = Entry;
+
+    fn deref(&self) -> &Entry {
+        self.entries
+            .0
+            .last()
+            .expect("LastMut is only created for non-empty entries")
+    }
+}
+
+impl DerefMut for LastMut<'_> {
+    fn deref_mut(&mut self) -> &mut Entry {
+        self.entries
+            .0
+            .last_mut()
+            .expect("LastMut is only created for non-empty entries")
+    }
+}
+
+impl Drop for LastMut<'_> {
+    fn drop(&mut self) {
+        let v = &self.entries.0;
+        if v.len() > 1 && v[v.len() - 1] < v[v.len() - 2] {
+            let last = self.entries.0.pop().expect("checked above");
+            self.entries.insert_sorted(last);
+        }
+    }
+}
+
+impl Deref for Entries {
+    type Target = [Entry];
+
+    fn deref(&self) -> &[Entry] {
+        &self.0
+    }
+}
+
+impl From<Vec<Entry>> for Entries {
+    fn from(mut entries: Vec<Entry>) -> Self {
+        entries.sort();
+        Entries(entries)
+    }
+}
+
+impl From<Entries> for Vec<Entry> {
+    fn from(entries: Entries) -> Self {
+        entries.0
+    }
+}
+
+impl FromIterator<Entry> for Entries {
+    fn from_iter<I: IntoIterator<Item = Entry>>(iter: I) -> Self {
+        Entries::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl Extend<Entry> for Entries {
+    fn extend<I: IntoIterator<Item = Entry>>(&mut self, iter: I) {
+        self.0.extend(iter);
+        self.0.sort();
+    }
+}
+
+impl IntoIterator for Entries {
+    type Item = Entry;
+    type IntoIter = vec::IntoIter<Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Entries {
+    type Item = &'a Entry;
+    type IntoIter = slice::Iter<'a, Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    fn entry(start: &str, stop: Option<&str>) -> Entry {
+        Entry {
+            start: Some(time(start)),
+            stop: stop.map(time),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn close_active() {
+        let mut entries: Entries = vec![
+            entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z")),
+            entry("2020-01-01T10:00:00Z", None),
+        ]
+        .into();
+        let stop = time("2020-01-01T11:00:00Z");
+        let closed = entries.close_active(stop, "done".to_string()).unwrap();
+        assert_eq!(closed.stop, Some(stop));
+        assert_eq!(closed.result, "done");
+        assert!(entries.active().is_none());
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn close_active_at_its_start() {
+        let mut entries: Entries = vec![entry("2020-01-01T10:00:00Z", None)].into();
+        let start = time("2020-01-01T10:00:00Z");
+        let closed = entries.close_active(start, String::new()).unwrap();
+        assert_eq!(closed.duration(), Some(chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn close_active_before_its_start() {
+        let running = entry("2020-01-01T10:00:00Z", None);
+        let mut entries: Entries = vec![running.clone()].into();
+        let stop = time("2020-01-01T09:59:59Z");
+        let err = entries.close_active(stop, String::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidRange { .. }));
+        assert_eq!(entries.as_slice(), [running]);
+    }
+
+    #[test]
+    fn close_active_without_a_running_entry() {
+        let stop = time("2020-01-01T11:00:00Z");
+        let err = Entries::new()
+            .close_active(stop, String::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::NoActiveEntry));
+
+        let stopped = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        let mut entries: Entries = vec![stopped.clone()].into();
+        let err = entries.close_active(stop, String::new()).unwrap_err();
+        assert!(matches!(err, Error::AlreadyStopped));
+        assert_eq!(entries.as_slice(), [stopped]);
+    }
+}
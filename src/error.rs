@@ -0,0 +1,122 @@
+use chrono::{DateTime, FixedOffset};
+use std::{error, fmt, io};
+
+/// Everything that can go wrong when working with a log.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The log, or one of the files stored next to it, isn't valid.
+    ///
+    /// `line` and `column` are 1-based. `line` is 0 when the problem has no position, and
+    /// `column` is 0 when it is just past the end of the previous line.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The log has no entries to operate on.
+    NoEntries,
+    /// There is no running entry.
+    NoActiveEntry,
+    /// The latest entry has already been stopped.
+    AlreadyStopped,
+    /// An entry has a stop time but no start time.
+    MissingStart,
+    /// An entry has no goal, but one is required.
+    MissingGoal,
+    /// The log couldn't be written in the requested format.
+    Serialize(String),
+    /// A time range ends before it starts.
+    InvalidRange {
+        start: DateTime<FixedOffset>,
+        stop: DateTime<FixedOffset>,
+    },
+    /// The daemon keeping the log loaded couldn't handle a request.
+    Daemon(String),
+    /// A script failed to compile or run.
+    Script(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// A parse error at the given position.
+    pub(crate) fn parse<M: Into<String>>(line: usize, column: usize, message: M) -> Self {
+        Error::Parse {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse {
+                line: 0, message, ..
+            } => write!(f, "{}", message),
+            Error::Parse {
+                line,
+                column: 0,
+                message,
+            } => write!(f, "{} at the end of line {}", message, line - 1),
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{} at line {}, column {}", message, line, column),
+            Error::NoEntries => write!(f, "the log has no entries"),
+            Error::NoActiveEntry => write!(f, "there is no running entry"),
+            Error::AlreadyStopped => write!(f, "the latest entry was already stopped"),
+            Error::MissingStart => write!(f, "the entry has a stop time but no start time"),
+            Error::MissingGoal => write!(f, "the entry has no goal"),
+            Error::Serialize(message) => write!(f, "couldn't write the log: {}", message),
+            Error::InvalidRange { start, stop } => {
+                write!(
+                    f,
+                    "the range stops at {} before it starts at {}",
+                    stop, start
+                )
+            }
+            Error::Daemon(message) => write!(f, "daemon: {}", message),
+            Error::Script(message) => write!(f, "script: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return Error::Io(e.into());
+        }
+        let (line, column) = (e.line(), e.column());
+        Error::parse(line, column, json_message(&e))
+    }
+}
+
+/// The message of a `serde_json` error, without the position it appends.
+pub(crate) fn json_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let position = format!(" at line {} column {}", e.line(), e.column());
+    match message.strip_suffix(&position) {
+        Some(message) => message.to_string(),
+        None => message,
+    }
+}
@@ -0,0 +1,16 @@
+use crate::Money;
+use chrono::NaiveDate;
+
+/// Money spent on a day, like a train ticket, that can be billed to a client along with the time.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Expense {
+    /// The day the money was spent.
+    pub date: NaiveDate,
+    pub amount: Money,
+}
+
+impl Expense {
+    pub fn new(date: NaiveDate, amount: Money) -> Self {
+        Expense { date, amount }
+    }
+}
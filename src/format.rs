@@ -0,0 +1,166 @@
+use crate::{read_entries, write_entries, Entries, Entry, Error, Result};
+use std::{
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// A serialization format for logs.
+///
+/// JSON is always available. The other formats are enabled with the cargo features of the same
+/// name: `toml`, `yaml`, `cbor` and `bincode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    /// A TOML document with an array of tables named `entries`.
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+impl Format {
+    /// Every format that is enabled.
+    pub fn all() -> &'static [Format] {
+        &[
+            Format::Json,
+            #[cfg(feature = "toml")]
+            Format::Toml,
+            #[cfg(feature = "yaml")]
+            Format::Yaml,
+            #[cfg(feature = "cbor")]
+            Format::Cbor,
+            #[cfg(feature = "bincode")]
+            Format::Bincode,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            #[cfg(feature = "toml")]
+            Format::Toml => "toml",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "yaml",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "cbor",
+            #[cfg(feature = "bincode")]
+            Format::Bincode => "bincode",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        let s = if s == "yml" { "yaml" } else { &s };
+        Format::all()
+            .iter()
+            .find(|f| f.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Format::all().iter().map(|f| f.name()).collect();
+                format!(
+                    "`{}` is not a supported format, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The layout of a TOML log, which can't have an array at the top level.
+#[cfg(feature = "toml")]
+#[derive(Deserialize, Serialize)]
+struct TomlLog<E> {
+    #[serde(default)]
+    entries: Vec<E>,
+}
+
+/// Reads a log written in `format`.
+pub fn read_entries_from<R: Read>(reader: R, format: Format) -> Result<Entries> {
+    match format {
+        Format::Json => read_entries(Some(reader)),
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let text = std::io::read_to_string(reader)?;
+            let log: TomlLog<Entry> = toml::from_str(&text).map_err(parse_error)?;
+            Ok(log.entries.into())
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            let entries: Vec<Entry> = serde_yaml::from_reader(reader).map_err(parse_error)?;
+            Ok(entries.into())
+        }
+        #[cfg(feature = "cbor")]
+        Format::Cbor => {
+            let entries: Vec<Entry> = ciborium::from_reader(reader).map_err(|e| match e {
+                ciborium::de::Error::Io(e) => Error::Io(e),
+                e => parse_error(e),
+            })?;
+            Ok(entries.into())
+        }
+        #[cfg(feature = "bincode")]
+        Format::Bincode => {
+            let entries: Vec<Entry> = bincode::deserialize_from(reader).map_err(|e| match *e {
+                bincode::ErrorKind::Io(e) => Error::Io(e),
+                e => parse_error(e),
+            })?;
+            Ok(entries.into())
+        }
+    }
+}
+
+/// Writes `entries` as a log in `format`, sorted by start time.
+pub fn write_entries_to<'a, W, I>(writer: W, entries: I, format: Format) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort();
+    match format {
+        Format::Json => write_entries(writer, entries),
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let text = toml::to_string(&TomlLog { entries }).map_err(write_error)?;
+            let mut writer = writer;
+            Ok(writer.write_all(text.as_bytes())?)
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::to_writer(writer, &entries).map_err(write_error),
+        #[cfg(feature = "cbor")]
+        Format::Cbor => ciborium::into_writer(&entries, writer).map_err(|e| match e {
+            ciborium::ser::Error::Io(e) => Error::Io(e),
+            e => write_error(e),
+        }),
+        #[cfg(feature = "bincode")]
+        Format::Bincode => bincode::serialize_into(writer, &entries).map_err(|e| match *e {
+            bincode::ErrorKind::Io(e) => Error::Io(e),
+            e => write_error(e),
+        }),
+    }
+}
+
+#[allow(dead_code)]
+fn parse_error<E: fmt::Display>(e: E) -> Error {
+    Error::parse(0, 0, e.to_string())
+}
+
+#[allow(dead_code)]
+fn write_error<E: fmt::Display>(e: E) -> Error {
+    Error::Serialize(e.to_string())
+}
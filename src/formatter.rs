@@ -0,0 +1,444 @@
+use crate::{
+    markdown, DurationFormat, Entry, Label, Language, Painted, Style, TimeFormat, Timezone,
+};
+use chrono::{DateTime, Duration, FixedOffset};
+use itertools::{EitherOrBoth, Itertools};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Write},
+    iter::once,
+    str::FromStr,
+};
+
+/// How much of an entry `EntryFormatter` shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// A single line with the ID, times, duration and the first line of the goal.
+    Compact,
+    /// One padded, titled line per field, with times in the timezone of the formatter.
+    #[default]
+    Detailed,
+    /// Like `Detailed`, but with every field and the times in the offset they were recorded in.
+    Verbose,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(Layout::Compact),
+            "detailed" => Ok(Layout::Detailed),
+            "verbose" => Ok(Layout::Verbose),
+            _ => Err(format!(
+                "`{}` is not a layout, expected compact, detailed or verbose",
+                s
+            )),
+        }
+    }
+}
+
+/// Formats entries in one of several layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EntryFormatter {
+    pub layout: Layout,
+    pub duration: DurationFormat,
+    /// The timezone times are shown in, except in the verbose layout.
+    pub timezone: Timezone,
+    /// Whether to color the output for a terminal.
+    pub color: bool,
+    /// The width to wrap text fields at, if any.
+    pub width: Option<usize>,
+    pub time: TimeFormat,
+    /// The language of titles and labels.
+    pub language: Language,
+    /// Whether to render results and notes as markdown.
+    pub markdown: bool,
+}
+
+impl EntryFormatter {
+    pub fn new(layout: Layout) -> Self {
+        EntryFormatter {
+            layout,
+            ..EntryFormatter::default()
+        }
+    }
+
+    pub fn duration_format(mut self, duration: DurationFormat) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn timezone(mut self, timezone: Timezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn time_format(mut self, time: TimeFormat) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    /// Formats `entry` into a string.
+    pub fn format(&self, entry: &Entry) -> String {
+        self.display(entry).to_string()
+    }
+
+    /// Something that formats `entry` when displayed.
+    pub fn display<'a>(&'a self, entry: &'a Entry) -> impl Display + 'a {
+        Formatted {
+            formatter: self,
+            entry,
+        }
+    }
+
+    pub fn write<W: Write>(&self, entry: &Entry, w: &mut W) -> fmt::Result {
+        match self.layout {
+            Layout::Compact => self.write_compact(entry, w),
+            Layout::Detailed | Layout::Verbose => self.write_titled(entry, w),
+        }
+    }
+
+    fn write_compact<W: Write>(&self, entry: &Entry, w: &mut W) -> fmt::Result {
+        if let Some(id) = entry.id {
+            write!(w, "{:>4} ", id)?;
+        }
+        if let Some(expense) = &entry.expense {
+            let date = self.time.format_date(expense.date, "%Y-%m-%d");
+            let width = if self.time.twelve_hour { 19 } else { 16 };
+            write!(w, "{:1$} ({2})", date, width, expense.amount)?;
+            if let Some(goal) = entry.goal.lines().next() {
+                write!(w, "  {}", self.paint(self.goal_style(entry), goal))?;
+            }
+            return Ok(());
+        }
+        if let Some(absence) = &entry.absence {
+            let date = self.time.format_date(absence.date, "%Y-%m-%d");
+            let width = if self.time.twelve_hour { 19 } else { 16 };
+            let duration = self.duration.format(absence.duration);
+            write!(w, "{:1$} ({2}, {3})", date, width, absence.kind, duration)?;
+            if let Some(goal) = entry.goal.lines().next() {
+                write!(w, "  {}", self.paint(self.goal_style(entry), goal))?;
+            }
+            return Ok(());
+        }
+        let start = entry.start.map(|t| self.timezone.convert(&t));
+        let stop = entry.stop.map(|t| self.timezone.convert(&t));
+        match start {
+            Some(start) => write!(w, "{}", self.time.format(&start, self.time.date_time()))?,
+            None => {
+                let width = if self.time.twelve_hour { 19 } else { 16 };
+                write!(w, "{:1$}", "--", width)?
+            }
+        }
+        match (start, stop) {
+            (Some(start), Some(stop)) if start.date_naive() == stop.date_naive() => {
+                write!(w, " - {}", self.time.format(&stop, self.time.time()))?
+            }
+            (_, Some(stop)) => write!(w, " - {}", self.time.format(&stop, self.time.date_time()))?,
+            (_, None) => {
+                let running = self.language.label(Label::Running);
+                write!(w, " - {}", Style::Active.paint(running, self.color))?
+            }
+        }
+        if let Some(duration) = entry.duration() {
+            let duration = self.duration.format(duration);
+            write!(w, " ({})", Style::Duration.paint(duration, self.color))?;
+        }
+        if let Some(goal) = entry.goal.lines().next() {
+            write!(w, "  {}", self.paint(self.goal_style(entry), goal))?;
+        }
+        Ok(())
+    }
+
+    fn write_titled<'a, W: Write>(&self, entry: &'a Entry, w: &mut W) -> fmt::Result {
+        let verbose = self.layout == Layout::Verbose;
+        let time = |t: Option<DateTime<FixedOffset>>| {
+            t.map(|t| {
+                let t = if verbose {
+                    t
+                } else {
+                    self.timezone.convert(&t)
+                };
+                self.time.format(&t, self.time.timestamp())
+            })
+        };
+
+        let mut fields: Vec<(Label, Field<'a>)> = Vec::new();
+        if entry.id.is_some() || verbose {
+            fields.push((
+                Label::Id,
+                Field::Optional(entry.id.map(|id| id.to_string())),
+            ));
+        }
+        if let Some(expense) = &entry.expense {
+            let date = self.time.format_date(expense.date, "%Y-%m-%d");
+            fields.extend(vec![
+                (Label::Date, Field::Text(date.into())),
+                (
+                    Label::Expense,
+                    Field::Text(expense.amount.to_string().into()),
+                ),
+            ]);
+        } else if let Some(absence) = &entry.absence {
+            let date = self.time.format_date(absence.date, "%Y-%m-%d");
+            let duration = self.duration.format(absence.duration);
+            fields.extend(vec![
+                (Label::Date, Field::Text(date.into())),
+                (
+                    Label::TimeOff,
+                    Field::Text(format!("{} ({})", absence.kind, duration).into()),
+                ),
+            ]);
+        } else {
+            fields.extend(vec![
+                (Label::StartTime, Field::Optional(time(entry.start))),
+                (Label::StopTime, Field::Optional(time(entry.stop))),
+                (
+                    Label::Duration,
+                    Field::Optional(entry.duration().map(|d| self.duration.format(d))),
+                ),
+            ]);
+        }
+        let worked = entry.expense.is_none() && entry.absence.is_none();
+        if entry.absence.is_none() || !entry.goal.is_empty() || verbose {
+            fields.push((Label::Goal, Field::Text(entry.goal.as_str().into())));
+        }
+        if entry.estimate.is_some() || verbose {
+            fields.push((Label::Estimate, Field::Optional(self.estimate(entry))));
+        }
+        if entry.client.is_some() || verbose {
+            fields.push((Label::Client, Field::Optional(entry.client.clone())));
+        }
+        if entry.billable || verbose {
+            let billable = if entry.billable {
+                Label::Yes
+            } else {
+                Label::No
+            };
+            let billable = self.language.label(billable);
+            fields.push((Label::Billable, Field::Text(billable.into())));
+        }
+        if entry.rate.is_some() || verbose {
+            let rate = entry.rate.as_ref().map(|r| r.to_string());
+            fields.push((Label::Rate, Field::Optional(rate)));
+        }
+        if entry.user.is_some() || verbose {
+            fields.push((Label::User, Field::Optional(entry.user.clone())));
+        }
+        let text = |text: &'a str| {
+            if self.markdown {
+                Field::Markdown(text)
+            } else {
+                Field::Text(text.into())
+            }
+        };
+        if worked || !entry.result.is_empty() || verbose {
+            fields.push((Label::Result, text(&entry.result)));
+        }
+        for note in &entry.notes {
+            fields.push((Label::Note, text(note)));
+        }
+        if !entry.tags.is_empty() || verbose {
+            fields.push((Label::Tags, Field::Text(entry.tags.join(", ").into())));
+        }
+        for (key, value) in &entry.meta {
+            fields.push((
+                Label::Meta,
+                Field::Text(format!("{}={}", key, value).into()),
+            ));
+        }
+
+        let title = |label| self.language.label(label);
+        let pad = fields
+            .iter()
+            .map(|x| title(x.0).chars().count())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for (i, (label, field)) in fields.iter().enumerate() {
+            if i != 0 {
+                writeln!(w)?;
+            }
+            let style = match label {
+                Label::Duration => Some(Style::Duration),
+                Label::Goal => self.goal_style(entry),
+                _ => None,
+            };
+            match field {
+                Field::Optional(x) => {
+                    self.write_option_title_pad(w, x, title(*label), pad, style)?
+                }
+                Field::Text(x) => self.write_str_title_pad(w, x, title(*label), pad, style)?,
+                Field::Markdown(x) => {
+                    let lines = markdown::render(x, self.color, |line, indent| {
+                        self.wrap(line, pad + indent)
+                    });
+                    self.write_lines_title_pad(w, lines, title(*label), pad)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_str_title_pad<W: Write>(
+        &self,
+        w: &mut W,
+        text: &str,
+        title: &str,
+        pad: usize,
+        style: Option<Style>,
+    ) -> fmt::Result {
+        let lines = text
+            .lines()
+            .flat_map(|line| self.wrap(line, pad))
+            .map(|line| self.paint(style, line));
+        self.write_lines_title_pad(w, lines, title, pad)
+    }
+
+    /// Writes `lines` next to `title`, which is padded to `pad`.
+    fn write_lines_title_pad<W, I>(
+        &self,
+        w: &mut W,
+        lines: I,
+        title: &str,
+        pad: usize,
+    ) -> fmt::Result
+    where
+        W: Write,
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let it = once(title).zip_longest(lines).enumerate();
+        for (i, either_or_both) in it {
+            if i != 0 {
+                writeln!(w)?;
+            }
+
+            let (left, right) = match either_or_both {
+                EitherOrBoth::Both(left, right) => (left, Some(right)),
+                EitherOrBoth::Left(left) => (left, None),
+                EitherOrBoth::Right(right) => ("", Some(right)),
+            };
+            let left = format!("{l:<width$}", l = left, width = pad);
+            write!(w, "{}", Style::Title.paint(left, self.color))?;
+            if let Some(right) = right {
+                write!(w, "{}", right)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_option_title_pad<W: Write, T: Display>(
+        &self,
+        w: &mut W,
+        text: &Option<T>,
+        title: &str,
+        pad: usize,
+        style: Option<Style>,
+    ) -> fmt::Result {
+        let title = format!("{:<width$}", title, width = pad);
+        write!(w, "{}", Style::Title.paint(title, self.color))?;
+        if let Some(right) = text {
+            write!(w, "{}", self.paint(style, right))
+        } else {
+            write!(w, "--")
+        }
+    }
+
+    /// Splits `line` into lines that fit next to a title padded to `pad`, breaking between words
+    /// where possible.
+    fn wrap<'a>(&self, line: &'a str, pad: usize) -> Vec<&'a str> {
+        let width = match self.width {
+            Some(width) if width > pad => width - pad,
+            _ => return vec![line],
+        };
+        let mut lines = Vec::new();
+        let mut rest = line;
+        while let Some((limit, _)) = rest.char_indices().nth(width) {
+            let end = if rest[limit..].starts_with(char::is_whitespace) {
+                Some(limit)
+            } else {
+                rest[..limit].rfind(char::is_whitespace)
+            };
+            match end.filter(|&end| !rest[..end].trim().is_empty()) {
+                Some(end) => {
+                    lines.push(rest[..end].trim_end());
+                    rest = rest[end..].trim_start();
+                }
+                None => {
+                    lines.push(&rest[..limit]);
+                    rest = &rest[limit..];
+                }
+            }
+        }
+        if !rest.is_empty() || lines.is_empty() {
+            lines.push(rest);
+        }
+        lines
+    }
+
+    /// The style of the goal, which marks the running entry.
+    fn goal_style(&self, entry: &Entry) -> Option<Style> {
+        Some(Style::Active).filter(|_| entry.is_active())
+    }
+
+    fn paint<T: Display>(&self, style: Option<Style>, value: T) -> Painted<T> {
+        Painted::new(style.filter(|_| self.color), value)
+    }
+
+    /// The estimate of `entry`, and how far off it was once the entry is stopped.
+    fn estimate(&self, entry: &Entry) -> Option<String> {
+        let estimate = self.duration.format(entry.estimate?);
+        let label = |label| self.language.label(label);
+        Some(match entry.variance() {
+            Some(v) if v > Duration::zero() => {
+                let v = self.duration.format(v);
+                format!("{} ({} {})", estimate, v, label(Label::Over))
+            }
+            Some(v) if v < Duration::zero() => {
+                let v = self.duration.format(-v);
+                format!("{} ({} {})", estimate, v, label(Label::Under))
+            }
+            Some(_) => format!("{} ({})", estimate, label(Label::OnTime)),
+            None => estimate,
+        })
+    }
+}
+
+enum Field<'a> {
+    Optional(Option<String>),
+    Text(Cow<'a, str>),
+    Markdown(&'a str),
+}
+
+struct Formatted<'a> {
+    formatter: &'a EntryFormatter,
+    entry: &'a Entry,
+}
+
+impl Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.formatter.write(self.entry, f)
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{summarize, Entry, Period, Range, Timelog, Timezone};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Weekday};
+
+/// The schema of the GraphQL API of a log.
+///
+/// Queries read the `Timelog` that is passed along with each request as data.
+pub type GraphQlSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Builds the schema, with weeks in summaries beginning on `week_start`.
+pub fn graphql_schema(week_start: Weekday) -> GraphQlSchema {
+    Schema::build(Query { week_start }, EmptyMutation, EmptySubscription).finish()
+}
+
+/// The root of all queries.
+pub struct Query {
+    week_start: Weekday,
+}
+
+#[Object]
+impl Query {
+    /// The entries matching every filter that is given, in order.
+    ///
+    /// `from` and `to` keep the entries that were running at some point between them, and `last`
+    /// only the latest few of the rest.
+    #[allow(clippy::too_many_arguments)]
+    async fn entries(
+        &self,
+        ctx: &Context<'_>,
+        goal: Option<String>,
+        tag: Option<String>,
+        meta_key: Option<String>,
+        active: Option<bool>,
+        from: Option<DateTime<FixedOffset>>,
+        to: Option<DateTime<FixedOffset>>,
+        last: Option<usize>,
+    ) -> Result<Vec<GraphQlEntry>> {
+        let log = ctx.data::<Timelog>()?;
+        let goal = goal.map(|g| g.to_lowercase());
+        let now = Local::now().fixed_offset();
+        let entries: Vec<_> = log
+            .iter()
+            .filter(|e| {
+                goal.as_ref()
+                    .is_none_or(|g| e.goal.to_lowercase().contains(g))
+            })
+            .filter(|e| tag.as_ref().is_none_or(|t| e.tags.contains(t)))
+            .filter(|e| meta_key.as_ref().is_none_or(|k| e.meta.contains_key(k)))
+            .filter(|e| active.is_none_or(|a| e.is_active() == a))
+            .filter(|e| from.is_none_or(|from| e.stop.unwrap_or(now) > from))
+            .filter(|e| to.is_none_or(|to| e.start.is_some_and(|start| start < to)))
+            .collect();
+        let skip = last.map_or(0, |last| entries.len().saturating_sub(last));
+        Ok(entries
+            .into_iter()
+            .skip(skip)
+            .map(|e| GraphQlEntry(e.clone()))
+            .collect())
+    }
+
+    /// The entry with `id`, if there is one.
+    async fn entry(&self, ctx: &Context<'_>, id: u64) -> Result<Option<GraphQlEntry>> {
+        let log = ctx.data::<Timelog>()?;
+        let entry = log.iter().find(|e| e.id == Some(id));
+        Ok(entry.cloned().map(GraphQlEntry))
+    }
+
+    /// The time tracked per period, like `timelog summary`.
+    ///
+    /// `timezone` is one of local, utc, original or an offset like +02:00, and `from` and `to`
+    /// limit the days that are included.
+    async fn summary(
+        &self,
+        ctx: &Context<'_>,
+        period: PeriodName,
+        timezone: Option<String>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<GraphQlSummary> {
+        let log = ctx.data::<Timelog>()?;
+        let timezone = match timezone {
+            Some(timezone) => timezone.parse::<Timezone>()?,
+            None => Timezone::Local,
+        };
+        let period = match period {
+            PeriodName::Daily => Period::Day,
+            PeriodName::Weekly => Period::Week {
+                start: self.week_start,
+            },
+            PeriodName::Monthly => Period::Month,
+            PeriodName::Quarterly => Period::Quarter,
+            PeriodName::Yearly => Period::Year,
+        };
+        let summary = summarize(log, period, Range::new(from, to), timezone);
+        Ok(GraphQlSummary {
+            duration: summary.total().num_seconds(),
+            buckets: summary
+                .buckets
+                .iter()
+                .map(|b| GraphQlBucket {
+                    start: b.start,
+                    duration: b.total.num_seconds(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The periods a summary can be split into.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodName {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+/// An entry, with durations in seconds.
+pub struct GraphQlEntry(Entry);
+
+#[Object(name = "Entry")]
+impl GraphQlEntry {
+    async fn id(&self) -> Option<u64> {
+        self.0.id
+    }
+
+    async fn start(&self) -> Option<DateTime<FixedOffset>> {
+        self.0.start
+    }
+
+    async fn stop(&self) -> Option<DateTime<FixedOffset>> {
+        self.0.stop
+    }
+
+    async fn goal(&self) -> &str {
+        &self.0.goal
+    }
+
+    async fn result(&self) -> &str {
+        &self.0.result
+    }
+
+    async fn notes(&self) -> &[String] {
+        &self.0.notes
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn meta(&self) -> Vec<MetaPair> {
+        self.0
+            .meta
+            .iter()
+            .map(|(key, value)| MetaPair {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    async fn estimate(&self) -> Option<i64> {
+        self.0.estimate.map(|d| d.num_seconds())
+    }
+
+    /// How long the entry took, or has been running so far.
+    async fn duration(&self) -> Option<i64> {
+        let e = &self.0;
+        let duration = e
+            .duration()
+            .or_else(|| Some(Local::now().fixed_offset() - e.start?).filter(|_| e.is_active()));
+        duration.map(|d| d.num_seconds())
+    }
+
+    async fn client(&self) -> Option<&str> {
+        self.0.client.as_deref()
+    }
+
+    async fn billable(&self) -> bool {
+        self.0.billable
+    }
+
+    /// What an hour of the work is worth, like `120.00 EUR/h`.
+    async fn rate(&self) -> Option<String> {
+        self.0.rate.as_ref().map(|r| r.to_string())
+    }
+
+    /// The money spent, if the entry is an expense instead of time.
+    async fn expense(&self) -> Option<GraphQlExpense> {
+        self.0.expense.as_ref().map(|e| GraphQlExpense {
+            date: e.date,
+            amount: e.amount.to_string(),
+        })
+    }
+
+    /// The time taken off, if the entry is an absence instead of time worked.
+    async fn absence(&self) -> Option<GraphQlAbsence> {
+        self.0.absence.as_ref().map(|a| GraphQlAbsence {
+            date: a.date,
+            kind: a.kind.to_string(),
+            duration: a.duration.num_seconds(),
+        })
+    }
+
+    async fn user(&self) -> Option<&str> {
+        self.0.user.as_deref()
+    }
+
+    async fn active(&self) -> bool {
+        self.0.is_active()
+    }
+}
+
+/// A piece of data attached to an entry.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+#[graphql(name = "Meta")]
+pub struct MetaPair {
+    key: String,
+    value: String,
+}
+
+/// Money spent on a day, with the amount like `30.00 EUR`.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+#[graphql(name = "Expense")]
+pub struct GraphQlExpense {
+    date: NaiveDate,
+    amount: String,
+}
+
+/// Time taken off on a day, like `vacation`, with the duration in seconds.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+#[graphql(name = "Absence")]
+pub struct GraphQlAbsence {
+    date: NaiveDate,
+    kind: String,
+    duration: i64,
+}
+
+/// The time tracked per period, with durations in seconds.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+#[graphql(name = "Summary")]
+pub struct GraphQlSummary {
+    buckets: Vec<GraphQlBucket>,
+    /// The time tracked over the whole summary.
+    duration: i64,
+}
+
+/// The time tracked in one period, starting on `start`.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Eq)]
+#[graphql(name = "Bucket")]
+pub struct GraphQlBucket {
+    start: NaiveDate,
+    duration: i64,
+}
@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+/// A language that output is translated into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+}
+
+/// A piece of text in the output that is translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Id,
+    StartTime,
+    StopTime,
+    Duration,
+    Goal,
+    Estimate,
+    Client,
+    Billable,
+    Rate,
+    /// The day of an expense or an absence.
+    Date,
+    Expense,
+    /// The kind and length of an absence.
+    TimeOff,
+    User,
+    Yes,
+    No,
+    Result,
+    Note,
+    Tags,
+    Meta,
+    /// A running entry in place of its stop time.
+    Running,
+    /// How much longer than estimated something took, after the duration.
+    Over,
+    /// How much shorter than estimated something took, after the duration.
+    Under,
+    OnTime,
+    Week,
+    /// Before the first day of a week.
+    WeekOf,
+    /// Before the number of a quarter.
+    Quarter,
+    NothingRunning,
+    /// Before how long the running entry has been running.
+    RunningFor,
+    /// Before the time the running entry started.
+    Since,
+    /// Before the sum of everything above it in a summary.
+    Total,
+    /// Before what some time earned.
+    Earned,
+    /// After the billable part of some time.
+    BillableTime,
+    /// After the part of some time that isn't billable.
+    NonBillableTime,
+}
+
+impl Language {
+    /// The language of a locale like `de_DE.UTF-8`, if there is a translation for it.
+    pub fn from_locale(locale: &str) -> Option<Language> {
+        let code = locale.split(['_', '.', '@', '-']).next().unwrap_or(locale);
+        code.parse().ok()
+    }
+
+    /// The text of `label` in this language.
+    pub fn label(self, label: Label) -> &'static str {
+        use Label::*;
+
+        match self {
+            Language::English => match label {
+                Id => "ID:",
+                StartTime => "Start Time:",
+                StopTime => "Stop Time:",
+                Duration => "Duration:",
+                Goal => "Goal:",
+                Estimate => "Estimate:",
+                Client => "Client:",
+                Billable => "Billable:",
+                Rate => "Rate:",
+                Date => "Date:",
+                Expense => "Expense:",
+                TimeOff => "Time off:",
+                User => "User:",
+                Yes => "yes",
+                No => "no",
+                Result => "Result:",
+                Note => "Note:",
+                Tags => "Tags:",
+                Meta => "Meta:",
+                Running => "running",
+                Over => "over",
+                Under => "under",
+                OnTime => "on time",
+                Week => "Week",
+                WeekOf => "Week of",
+                Quarter => "Q",
+                NothingRunning => "No entry is running.",
+                RunningFor => "Running for",
+                Since => "since",
+                Total => "Total:",
+                Earned => "earned",
+                BillableTime => "billable",
+                NonBillableTime => "non-billable",
+            },
+            Language::German => match label {
+                Id => "ID:",
+                StartTime => "Beginn:",
+                StopTime => "Ende:",
+                Duration => "Dauer:",
+                Goal => "Ziel:",
+                Estimate => "Schätzung:",
+                Client => "Kunde:",
+                Billable => "Abrechenbar:",
+                Rate => "Stundensatz:",
+                Date => "Datum:",
+                Expense => "Auslage:",
+                TimeOff => "Abwesenheit:",
+                User => "Person:",
+                Yes => "ja",
+                No => "nein",
+                Result => "Ergebnis:",
+                Note => "Notiz:",
+                Tags => "Tags:",
+                Meta => "Metadaten:",
+                Running => "läuft",
+                Over => "mehr",
+                Under => "weniger",
+                OnTime => "genau",
+                Week => "Woche",
+                WeekOf => "Woche vom",
+                Quarter => "Q",
+                NothingRunning => "Es läuft kein Eintrag.",
+                RunningFor => "Läuft seit",
+                Since => "Beginn",
+                Total => "Gesamt:",
+                Earned => "verdient",
+                BillableTime => "abrechenbar",
+                NonBillableTime => "nicht abrechenbar",
+            },
+            Language::French => match label {
+                Id => "ID :",
+                StartTime => "Début :",
+                StopTime => "Fin :",
+                Duration => "Durée :",
+                Goal => "Objectif :",
+                Estimate => "Estimation :",
+                Client => "Client :",
+                Billable => "Facturable :",
+                Rate => "Taux horaire :",
+                Date => "Date :",
+                Expense => "Dépense :",
+                TimeOff => "Absence :",
+                User => "Personne :",
+                Yes => "oui",
+                No => "non",
+                Result => "Résultat :",
+                Note => "Note :",
+                Tags => "Étiquettes :",
+                Meta => "Métadonnées :",
+                Running => "en cours",
+                Over => "de plus",
+                Under => "de moins",
+                OnTime => "à l'heure",
+                Week => "Semaine",
+                WeekOf => "Semaine du",
+                Quarter => "T",
+                NothingRunning => "Aucune entrée en cours.",
+                RunningFor => "En cours depuis",
+                Since => "début",
+                Total => "Total :",
+                Earned => "gagné",
+                BillableTime => "facturable",
+                NonBillableTime => "non facturable",
+            },
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    /// Parses a language code like `de`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Language::English),
+            "de" => Ok(Language::German),
+            "fr" => Ok(Language::French),
+            _ => Err(format!("`{}` is not a language, expected en, de or fr", s)),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{Entry, Timezone};
+use chrono::{Duration, NaiveDate};
+use std::{collections::BTreeMap, time::SystemTime};
+#[cfg(feature = "fs")]
+use {
+    crate::Result,
+    log::debug,
+    std::{
+        fs::{self, File},
+        io::{self, BufReader, BufWriter},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Per-day totals of the completed entries in a log, stored next to it.
+///
+/// Entries are attributed to the day they started on in the index's timezone, like `summary`
+/// does. The index remembers the size and modification time of the log it describes, so an index
+/// that was left behind by an external edit of the log can be detected and rebuilt.
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct Index {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_len: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_modified: Option<SystemTime>,
+    #[serde(default)]
+    timezone: Timezone,
+    /// Nanoseconds tracked per day.
+    days: BTreeMap<NaiveDate, i64>,
+}
+
+impl Index {
+    /// An empty index grouping entries into days in `timezone`.
+    pub fn new(timezone: Timezone) -> Self {
+        Index {
+            timezone,
+            ..Index::default()
+        }
+    }
+
+    pub fn timezone(&self) -> Timezone {
+        self.timezone
+    }
+
+    /// Adds the duration of `entry` to the day it started on.
+    pub fn add(&mut self, entry: &Entry) {
+        if let Some((date, nanos)) = self.day_nanos(entry) {
+            *self.days.entry(date).or_insert(0) += nanos;
+        }
+    }
+
+    /// Undoes a previous `add` of `entry`.
+    pub fn remove(&mut self, entry: &Entry) {
+        if let Some((date, nanos)) = self.day_nanos(entry) {
+            let total = self.days.entry(date).or_insert(0);
+            *total -= nanos;
+            if *total == 0 {
+                self.days.remove(&date);
+            }
+        }
+    }
+
+    /// The total time tracked on each day, in chronological order.
+    pub fn days(&self) -> impl Iterator<Item = (NaiveDate, Duration)> + '_ {
+        self.days
+            .iter()
+            .map(|(date, nanos)| (*date, Duration::nanoseconds(*nanos)))
+    }
+
+    fn day_nanos(&self, entry: &Entry) -> Option<(NaiveDate, i64)> {
+        let date = self.timezone.date(&entry.start?);
+        Some((date, entry.duration()?.num_nanoseconds()?))
+    }
+}
+
+/// Storing the index next to the log.
+#[cfg(feature = "fs")]
+impl Index {
+    /// The path of the index belonging to the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".index");
+        PathBuf::from(path)
+    }
+
+    /// Reads the index at `path`, returning `None` if there is none.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Option<Index>> {
+        let path = path.as_ref();
+        match File::open(path) {
+            Ok(file) => {
+                debug!("reading the index {}", path.display());
+                Ok(Some(serde_json::from_reader(BufReader::new(file))?))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("there is no index at {}", path.display());
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the index to `path`, recording the current state of the log at `log_path`.
+    pub fn write<P: AsRef<Path>, L: AsRef<Path>>(&mut self, path: P, log_path: L) -> Result<()> {
+        let meta = fs::metadata(log_path)?;
+        self.log_len = Some(meta.len());
+        self.log_modified = meta.modified().ok();
+
+        let path = path.as_ref();
+        debug!("writing the index {}", path.display());
+        let file = File::create(path)?;
+        serde_json::to_writer(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Whether the index still describes the log at `log_path`.
+    pub fn is_fresh<L: AsRef<Path>>(&self, log_path: L) -> bool {
+        match fs::metadata(log_path) {
+            Ok(meta) => {
+                self.log_len == Some(meta.len())
+                    && self.log_modified.is_some()
+                    && self.log_modified == meta.modified().ok()
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, FixedOffset};
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn entry(start: &str, stop: Option<&str>) -> Entry {
+        let time = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        Entry {
+            start: Some(time(start)),
+            stop: stop.map(time),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let a = entry("2020-01-01T08:00:00Z", Some("2020-01-01T09:00:00Z"));
+        let b = entry("2020-01-01T10:00:00Z", Some("2020-01-01T10:30:00Z"));
+        let c = entry("2020-01-02T23:30:00Z", Some("2020-01-03T01:00:00Z"));
+        let mut index = Index::new(Timezone::Utc);
+        for e in &[&a, &b, &c] {
+            index.add(e);
+        }
+        assert_eq!(
+            index.days().collect::<Vec<_>>(),
+            [
+                (date("2020-01-01"), Duration::minutes(90)),
+                (date("2020-01-02"), Duration::minutes(90)),
+            ]
+        );
+
+        index.remove(&a);
+        assert_eq!(
+            index.days().next(),
+            Some((date("2020-01-01"), Duration::minutes(30)))
+        );
+    }
+
+    #[test]
+    fn days_without_time_are_removed() {
+        let a = entry("2020-01-01T08:00:00Z", Some("2020-01-01T09:00:00Z"));
+        let b = entry("2020-01-02T08:00:00Z", Some("2020-01-02T09:00:00Z"));
+        let mut index = Index::new(Timezone::Utc);
+        index.add(&a);
+        index.add(&b);
+        index.remove(&a);
+        assert_eq!(
+            index.days().collect::<Vec<_>>(),
+            [(date("2020-01-02"), Duration::hours(1))]
+        );
+        index.remove(&b);
+        assert_eq!(index.days().count(), 0);
+    }
+
+    #[test]
+    fn active_entries_are_left_out() {
+        let mut index = Index::new(Timezone::Utc);
+        let running = entry("2020-01-01T08:00:00Z", None);
+        index.add(&running);
+        assert_eq!(index.days().count(), 0);
+        index.remove(&running);
+        assert_eq!(index.days().count(), 0);
+    }
+
+    #[test]
+    fn days_are_in_the_timezone_of_the_index() {
+        let e = entry("2020-01-01T23:30:00Z", Some("2020-01-02T00:30:00Z"));
+        let plus_one = Timezone::Fixed(FixedOffset::east_opt(3600).unwrap());
+        let mut index = Index::new(plus_one);
+        index.add(&e);
+        assert_eq!(index.timezone(), plus_one);
+        assert_eq!(
+            index.days().collect::<Vec<_>>(),
+            [(date("2020-01-02"), Duration::hours(1))]
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn write_and_read() {
+        use std::io::Write;
+
+        let log = std::env::temp_dir().join(format!("timelog-index-{}.json", std::process::id()));
+        let path = Index::path_for(&log);
+        assert!(path.to_string_lossy().ends_with(".json.index"));
+        fs::write(&log, "[]").unwrap();
+        assert!(Index::read(&path).unwrap().is_none());
+
+        let mut index = Index::new(Timezone::Utc);
+        index.add(&entry("2020-01-01T08:00:00Z", Some("2020-01-01T09:00:00Z")));
+        index.write(&path, &log).unwrap();
+        let read = Index::read(&path).unwrap().unwrap();
+        assert_eq!(
+            read.days().collect::<Vec<_>>(),
+            index.days().collect::<Vec<_>>()
+        );
+        assert_eq!(read.timezone(), Timezone::Utc);
+        assert!(read.is_fresh(&log));
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .unwrap()
+            .write_all(b"\n")
+            .unwrap();
+        assert!(!read.is_fresh(&log));
+        assert!(!Index::new(Timezone::Utc).is_fresh(&log));
+
+        fs::remove_file(&log).unwrap();
+        assert!(!read.is_fresh(&log));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,158 +1,645 @@
-#![feature(custom_attribute)]
 #[macro_use]
 extern crate serde_derive;
 
-use chrono::{DateTime, Duration, Local};
-use itertools::{EitherOrBoth, Itertools};
+mod absence;
+#[cfg(feature = "fs")]
+mod audit;
+mod builder;
+#[cfg(feature = "fs")]
+mod checksum;
+#[cfg(all(unix, feature = "fs"))]
+mod daemon;
+mod duration;
+mod entries;
+mod error;
+mod expense;
+mod format;
+mod formatter;
+#[cfg(feature = "graphql")]
+mod graphql;
+mod i18n;
+mod index;
+#[cfg(feature = "fs")]
+mod lock;
+mod markdown;
+mod money;
+mod period;
+mod rate;
+mod recover;
+#[cfg(feature = "rhai")]
+mod script;
+mod storage;
+mod style;
+mod summary;
+#[cfg(feature = "fs")]
+mod tail;
+mod template;
+mod time_format;
+mod timelog;
+mod timezone;
+#[cfg(feature = "fs")]
+mod trash;
+mod validate;
+
+pub use crate::{
+    absence::{Absence, AbsenceKind},
+    builder::EntryBuilder,
+    duration::{format_dur, parse_dur, DurationFormat, DurationStyle, RoundDirection, Rounding},
+    entries::{Entries, LastMut},
+    error::{Error, Result},
+    expense::Expense,
+    format::{read_entries_from, write_entries_to, Format},
+    formatter::{EntryFormatter, Layout},
+    i18n::{Label, Language},
+    index::Index,
+    money::{Earnings, Money, MoneyFormat},
+    period::{group_by_period, Period},
+    rate::Rate,
+    recover::{read_entries_lenient, BrokenEntry, Recovered},
+    storage::StorageBackend,
+    style::{Painted, Style},
+    summary::{
+        summarize, summarize_clients, summarize_earnings, summarize_estimates, summarize_projects,
+        summarize_users, Bucket, ClientTotal, GoalEstimate, ProjectTotal, Range, Summary,
+        UserTotal,
+    },
+    template::{EntryTemplate, Template},
+    time_format::{parse_locale, TimeFormat},
+    timelog::Timelog,
+    timezone::Timezone,
+    validate::{validate, validate_at, EntryRef, ValidationIssue},
+};
+
+#[cfg(feature = "fs")]
+pub use crate::{
+    audit::AuditRecord,
+    checksum::Checksum,
+    lock::Lock,
+    storage::FileStorage,
+    tail::Tail,
+    trash::{Trash, TrashedEntry},
+};
+
+#[cfg(all(unix, feature = "fs"))]
+pub use crate::daemon::{Client, Request, Response};
+
+#[cfg(feature = "graphql")]
+pub use crate::graphql::{graphql_schema, GraphQlSchema};
+
+#[cfg(feature = "rhai")]
+pub use crate::script::Script;
+
+#[cfg(feature = "tokio")]
+pub use crate::storage::AsyncStorageBackend;
+
+use chrono::{DateTime, Duration, FixedOffset, TimeZone};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use std::{
     cmp::Ordering,
-    collections::BinaryHeap,
-    fmt::{self, Display, Write},
-    io,
-    iter::once,
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead, BufReader},
 };
 
-#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialOrd, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, Deserialize, Eq, PartialEq)]
 pub struct Entry {
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub start: Option<DateTime<Local>>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub stop: Option<DateTime<Local>>,
-    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub start: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub stop: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
     pub goal: String,
-    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[serde(default)]
     pub result: String,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
     pub notes: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Free-form data attached by integrations, like issue IDs or invoice numbers.
+    #[serde(default)]
+    pub meta: BTreeMap<String, String>,
+    /// How long the entry was expected to take, stored in seconds.
+    #[serde(default, deserialize_with = "duration::deserialize_seconds")]
+    pub estimate: Option<Duration>,
+    /// Who the work was done for.
+    #[serde(default)]
+    pub client: Option<String>,
+    /// Whether the time can be billed to the client.
+    #[serde(default)]
+    pub billable: bool,
+    /// What an hour of the work is worth.
+    #[serde(default)]
+    pub rate: Option<Rate>,
+    /// Money spent instead of time. Expenses have no start or stop time, so they never count as
+    /// time worked and are never running.
+    #[serde(default)]
+    pub expense: Option<Expense>,
+    /// Time off instead of time worked. Like expenses, absences have no start or stop time.
+    #[serde(default)]
+    pub absence: Option<Absence>,
+    /// Who did the work, for logs that are shared by several people.
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
-impl Ord for Entry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self.start, other.start) {
-            (Some(a), Some(b)) => a.cmp(&b),
-            (Some(_), None) => Ordering::Greater,
-            (None, _) => Ordering::Less,
+/// Empty fields are left out in human-readable formats. Other formats, like bincode, may rely on
+/// every field being there, so they get all of them.
+impl Serialize for Entry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let all = !serializer.is_human_readable();
+        let mut s = serializer.serialize_struct("Entry", 15)?;
+        macro_rules! field {
+            ($name:ident, $empty:expr) => {
+                field!($name, $empty, &self.$name)
+            };
+            ($name:ident, $empty:expr, $value:expr) => {
+                if all || !$empty {
+                    s.serialize_field(stringify!($name), $value)?;
+                } else {
+                    s.skip_field(stringify!($name))?;
+                }
+            };
         }
+        field!(id, self.id.is_none());
+        field!(start, self.start.is_none());
+        field!(stop, self.stop.is_none());
+        field!(goal, self.goal.is_empty());
+        field!(result, self.result.is_empty());
+        field!(notes, self.notes.is_empty());
+        field!(tags, self.tags.is_empty());
+        field!(meta, self.meta.is_empty());
+        field!(
+            estimate,
+            self.estimate.is_none(),
+            &self.estimate.map(|d| d.num_seconds())
+        );
+        field!(client, self.client.is_none());
+        field!(billable, !self.billable);
+        field!(rate, self.rate.is_none());
+        field!(expense, self.expense.is_none());
+        field!(absence, self.absence.is_none());
+        field!(user, self.user.is_none());
+        s.end()
     }
 }
 
-fn fmt_str_title_pad(text: &str, title: &str, pad: usize) -> Result<String, fmt::Error> {
-    let mut s = String::new();
-    let it = once(title).zip_longest(text.lines()).enumerate();
-    for (i, either_or_both) in it {
-        if i != 0 {
-            writeln!(s)?;
+impl Entry {
+    /// A builder for an entry, which checks that the entry makes sense.
+    pub fn builder() -> EntryBuilder {
+        EntryBuilder::default()
+    }
+
+    /// How long the entry ran, if it has been stopped.
+    pub fn duration(&self) -> Option<Duration> {
+        match (self.start, self.stop) {
+            (Some(start), Some(stop)) => Some(stop - start),
+            _ => None,
         }
+    }
+
+    /// The start time in `tz`.
+    pub fn start_in<Tz: TimeZone>(&self, tz: &Tz) -> Option<DateTime<Tz>> {
+        self.start.map(|t| t.with_timezone(tz))
+    }
+
+    /// The stop time in `tz`.
+    pub fn stop_in<Tz: TimeZone>(&self, tz: &Tz) -> Option<DateTime<Tz>> {
+        self.stop.map(|t| t.with_timezone(tz))
+    }
+
+    /// How much longer than estimated the entry ran, or a negative duration if it ran shorter.
+    ///
+    /// This is only known for stopped entries with an estimate.
+    pub fn variance(&self) -> Option<Duration> {
+        Some(self.duration()? - self.estimate?)
+    }
 
-        let (left, right) = match either_or_both {
-            EitherOrBoth::Both(left, right) => (left, right),
-            EitherOrBoth::Left(left) => (left, ""),
-            EitherOrBoth::Right(right) => ("", right),
+    /// Splits the entry at every midnight in `timezone` that it runs across, into one entry per
+    /// day.
+    ///
+    /// The first piece keeps the ID, notes and estimate, and the last one the result. Every piece
+    /// has the goal, tags, meta, client, billable flag, rate and user, but the pieces after the
+    /// first have no ID yet. Running entries, and entries within one day, are returned as they are.
+    pub fn split_at_midnight(&self, timezone: Timezone) -> Vec<Entry> {
+        let (mut start, stop) = match (self.start, self.stop) {
+            (Some(start), Some(stop)) => (start, stop),
+            _ => return vec![self.clone()],
         };
-        write!(s, "{l:<width$}{r}", l = left, width = pad, r = right)?;
+        let mut pieces: Vec<Entry> = Vec::new();
+        loop {
+            let midnight = timezone.next_midnight(&start);
+            pieces.push(Entry {
+                start: Some(start),
+                stop: Some(stop.min(midnight)),
+                goal: self.goal.clone(),
+                tags: self.tags.clone(),
+                meta: self.meta.clone(),
+                client: self.client.clone(),
+                billable: self.billable,
+                rate: self.rate.clone(),
+                user: self.user.clone(),
+                ..Entry::default()
+            });
+            if stop <= midnight {
+                break;
+            }
+            start = midnight;
+        }
+        let first = &mut pieces[0];
+        first.id = self.id;
+        first.notes = self.notes.clone();
+        first.estimate = self.estimate;
+        let last = pieces.last_mut().expect("there is at least one piece");
+        last.result = self.result.clone();
+        pieces
+    }
+
+    /// Whether the entry is still running. Expenses and absences never are.
+    pub fn is_active(&self) -> bool {
+        self.stop.is_none() && self.expense.is_none() && self.absence.is_none()
     }
 
-    Ok(s)
+    /// Whether the two entries ran at the same time at some point.
+    ///
+    /// Running entries are treated as running forever, and entries without a start time never
+    /// overlap anything.
+    pub fn overlaps(&self, other: &Entry) -> bool {
+        match (self.start, other.start) {
+            (Some(a), Some(b)) => {
+                other.stop.is_none_or(|stop| a < stop) && self.stop.is_none_or(|stop| b < stop)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the entry was running at `time`.
+    pub fn contains<Tz: TimeZone>(&self, time: DateTime<Tz>) -> bool {
+        match self.start {
+            Some(start) => start <= time && self.stop.is_none_or(|stop| time < stop),
+            None => false,
+        }
+    }
 }
 
-fn fmt_option_title_pad<T: Display>(
-    text: &Option<T>,
-    title: &str,
-    pad: usize,
-) -> Result<String, fmt::Error> {
-    let mut s = String::new();
-    write!(s, "{:<width$}", title, width = pad)?;
-    if let Some(right) = text {
-        write!(s, "{}", right)?;
-    } else {
-        write!(s, "--")?;
+/// Entries are ordered by their start time, with entries that haven't started first. Ties are
+/// broken by the stop time (running entries first), then by the expense and the absence, so that
+/// expenses and absences, which have no start, are sorted by their date. After that come the goal
+/// and the ID, and finally the rest of the fields, so that only equal entries compare as equal.
+///
+/// Logs are always stored in this order.
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.start
+            .cmp(&other.start)
+            .then_with(|| self.stop.cmp(&other.stop))
+            .then_with(|| self.expense.cmp(&other.expense))
+            .then_with(|| self.absence.cmp(&other.absence))
+            .then_with(|| self.goal.cmp(&other.goal))
+            .then_with(|| self.id.cmp(&other.id))
+            .then_with(|| self.result.cmp(&other.result))
+            .then_with(|| self.notes.cmp(&other.notes))
+            .then_with(|| self.tags.cmp(&other.tags))
+            .then_with(|| self.meta.cmp(&other.meta))
+            .then_with(|| self.estimate.cmp(&other.estimate))
+            .then_with(|| self.client.cmp(&other.client))
+            .then_with(|| self.billable.cmp(&other.billable))
+            .then_with(|| self.rate.cmp(&other.rate))
+            .then_with(|| self.user.cmp(&other.user))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
-    Ok(s)
 }
 
 impl fmt::Display for Entry {
+    /// Formats the entry in the detailed layout, or in the compact layout for `{:#}`.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let duration = if let (Some(start), Some(stop)) = (self.start, self.stop) {
-            Some(stop - start)
+        let layout = if f.alternate() {
+            Layout::Compact
         } else {
-            None
+            Layout::Detailed
         };
+        EntryFormatter::new(layout).write(self, f)
+    }
+}
 
-        enum Data<'a> {
-            OpDt(Option<DateTime<Local>>),
-            OpSt(Option<String>),
-            St(&'a str),
-        };
+/// An iterator over the entries of a log, parsed one at a time.
+///
+/// Only a single entry is held in memory at once, so a full pass over a large log runs in
+/// constant memory. Entries are yielded in the order they appear in the log.
+pub struct EntryIter<R> {
+    reader: Counting<R>,
+    state: IterState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IterState {
+    Start,
+    First,
+    Rest,
+    Done,
+}
 
-        let duration = duration.map(|x| format_dur(x));
-        let mut to_output = vec![
-            ("Start Time:", Data::OpDt(self.start)),
-            ("Stop Time:", Data::OpDt(self.stop)),
-            ("Duration:", Data::OpSt(duration)),
-            ("Goal:", Data::St(&self.goal)),
-            ("Result:", Data::St(&self.result)),
-        ];
-        for note in &self.notes {
-            to_output.push(("Note:", Data::St(note)));
+impl<R: BufRead> EntryIter<R> {
+    pub fn new(reader: R) -> Self {
+        EntryIter {
+            reader: Counting {
+                inner: reader,
+                line: 1,
+                column: 1,
+            },
+            state: IterState::Start,
         }
+    }
 
-        let pad = to_output.iter().map(|x| x.0.len()).max().unwrap() + 1;
+    fn peek_non_whitespace(&mut self) -> Result<Option<u8>, Error> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(i) => {
+                    let b = buf[i];
+                    self.reader.consume(i);
+                    return Ok(Some(b));
+                }
+                None if buf.is_empty() => return Ok(None),
+                None => {
+                    let len = buf.len();
+                    self.reader.consume(len);
+                }
+            }
+        }
+    }
 
-        for (i, (title, data)) in to_output.iter().enumerate() {
-            if i != 0 {
-                writeln!(f)?;
+    fn expect(&mut self, expected: &[u8]) -> Result<u8, Error> {
+        let (line, column) = (self.reader.line, self.reader.column);
+        match self.peek_non_whitespace()? {
+            Some(b) if expected.contains(&b) => {
+                self.reader.consume(1);
+                Ok(b)
             }
+            Some(b) => Err(Error::parse(
+                self.reader.line,
+                self.reader.column,
+                format!("unexpected character `{}`", b as char),
+            )),
+            None => Err(Error::parse(line, column, "unexpected end of log")),
+        }
+    }
 
-            match data {
-                Data::OpDt(x) => write!(f, "{}", fmt_option_title_pad(x, title, pad)?)?,
-                Data::OpSt(x) => write!(f, "{}", fmt_option_title_pad(x, title, pad)?)?,
-                Data::St(x) => write!(f, "{}", fmt_str_title_pad(x, title, pad)?)?,
+    fn next_entry(&mut self) -> Result<Option<Entry>, Error> {
+        match self.state {
+            IterState::Start => {
+                self.expect(b"[")?;
+                self.state = IterState::First;
+                return self.next_entry();
             }
+            IterState::First => {
+                if self.peek_non_whitespace()? == Some(b']') {
+                    self.reader.consume(1);
+                    self.state = IterState::Done;
+                    return Ok(None);
+                }
+            }
+            IterState::Rest => {
+                if self.expect(b",]")? == b']' {
+                    self.state = IterState::Done;
+                    return Ok(None);
+                }
+            }
+            IterState::Done => return Ok(None),
         }
 
-        Ok(())
+        let (line, column) = (self.reader.line, self.reader.column);
+        let mut de = serde_json::Deserializer::from_reader(&mut self.reader);
+        let entry = Entry::deserialize(&mut de).map_err(|e| match Error::from(e) {
+            // Positions reported by the deserializer are relative to the start of the entry.
+            Error::Parse {
+                line: l,
+                column: c,
+                message,
+            } if l != 0 => Error::Parse {
+                line: line + l - 1,
+                column: if l == 1 { column + c - 1 } else { c },
+                message,
+            },
+            e => e,
+        })?;
+        self.state = IterState::Rest;
+        Ok(Some(entry))
     }
 }
 
-pub fn read_entries<R: io::Read>(
-    reader: Option<R>,
-) -> Result<BinaryHeap<Entry>, serde_json::Error> {
+impl<R: BufRead> Iterator for EntryIter<R> {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(e) => {
+                self.state = IterState::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A reader that keeps track of the line and column of the next byte it will yield.
+struct Counting<R> {
+    inner: R,
+    line: usize,
+    column: usize,
+}
+
+fn advance(mut line: usize, mut column: usize, bytes: &[u8]) -> (usize, usize) {
+    for &b in bytes {
+        if b == b'\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+impl<R: BufRead> io::Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let (line, column) = advance(self.line, self.column, &buf[..n]);
+        self.line = line;
+        self.column = column;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Counting<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            let (line, column) = advance(self.line, self.column, &buf[..amt.min(buf.len())]);
+            self.line = line;
+            self.column = column;
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Reads the entries of a log one at a time, in the order they appear in it.
+///
+/// Unlike `read_entries`, this never holds more than one entry in memory.
+pub fn read_entries_iter<R: io::Read>(reader: R) -> EntryIter<BufReader<R>> {
+    EntryIter::new(BufReader::new(reader))
+}
+
+pub fn read_entries<R: io::Read>(reader: Option<R>) -> Result<Entries, Error> {
     if let Some(reader) = reader {
-        Ok(serde_json::from_reader(reader)?)
+        read_entries_iter(reader).collect()
     } else {
-        Ok(BinaryHeap::default())
+        Ok(Entries::default())
     }
 }
 
-pub fn write_entries<W: io::Write>(
-    writer: W,
-    entries: BinaryHeap<Entry>,
-) -> Result<(), serde_json::Error> {
-    let entries = entries.into_sorted_vec();
+/// Writes `entries` as a log, sorted by start time no matter what order they are given in.
+pub fn write_entries<'a, W, I>(writer: W, entries: I) -> Result<(), Error>
+where
+    W: io::Write,
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort();
     serde_json::to_writer_pretty(writer, &entries)?;
     Ok(())
 }
 
-pub fn format_dur(mut dur: Duration) -> String {
-    let mut out = String::new();
-    let d = dur.num_days();
-    if d != 0 {
-        out += &format!("{}d", d);
-        dur = dur - Duration::days(d);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    fn entry(start: &str, stop: Option<&str>) -> Entry {
+        Entry {
+            start: Some(time(start)),
+            stop: stop.map(time),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn duration() {
+        let e = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:30:00Z"));
+        assert_eq!(e.duration(), Some(Duration::minutes(90)));
+
+        let e = entry("2020-01-01T09:00:00Z", Some("2020-01-01T09:00:00Z"));
+        assert_eq!(e.duration(), Some(Duration::zero()));
+
+        let e = entry("2020-01-01T09:00:00Z", None);
+        assert_eq!(e.duration(), None);
+    }
+
+    #[test]
+    fn duration_across_offsets() {
+        let e = entry("2020-01-01T09:00:00+01:00", Some("2020-01-01T09:00:00Z"));
+        assert_eq!(e.duration(), Some(Duration::hours(1)));
+    }
+
+    #[test]
+    fn duration_of_a_reversed_entry_is_negative() {
+        let e = entry("2020-01-01T10:00:00Z", Some("2020-01-01T09:00:00Z"));
+        assert_eq!(e.duration(), Some(Duration::hours(-1)));
+    }
+
+    #[test]
+    fn is_active() {
+        assert!(entry("2020-01-01T09:00:00Z", None).is_active());
+        assert!(!entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z")).is_active());
+
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let absence = Entry {
+            absence: Some(Absence::new(date, AbsenceKind::Sick, Duration::hours(8))),
+            ..Entry::default()
+        };
+        assert!(!absence.is_active());
     }
-    let h = dur.num_hours();
-    if h != 0 {
-        out += &format!("{}h", h);
-        dur = dur - Duration::hours(h);
+
+    #[test]
+    fn overlaps() {
+        let a = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        let b = entry("2020-01-01T09:59:59Z", Some("2020-01-01T11:00:00Z"));
+        assert!(a.overlaps(&b));
+        assert!(b.overlaps(&a));
+        assert!(a.overlaps(&a));
+    }
+
+    #[test]
+    fn entries_that_touch_do_not_overlap() {
+        let a = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        let b = entry("2020-01-01T10:00:00Z", Some("2020-01-01T11:00:00Z"));
+        assert!(!a.overlaps(&b));
+        assert!(!b.overlaps(&a));
     }
-    let m = dur.num_minutes();
-    if m != 0 {
-        out += &format!("{}m", m);
-        dur = dur - Duration::minutes(m);
+
+    #[test]
+    fn running_entries_overlap_everything_after_their_start() {
+        let running = entry("2020-01-01T10:00:00Z", None);
+        let later = entry("2020-01-02T10:00:00Z", Some("2020-01-02T11:00:00Z"));
+        let before = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        assert!(running.overlaps(&later));
+        assert!(later.overlaps(&running));
+        assert!(!running.overlaps(&before));
+        assert!(!before.overlaps(&running));
+        assert!(running.overlaps(&entry("2020-01-03T00:00:00Z", None)));
     }
-    let s = dur.num_seconds();
-    if s != 0 {
-        out += &format!("{}s", s);
+
+    #[test]
+    fn entries_without_a_start_overlap_nothing() {
+        let a = entry("2020-01-01T09:00:00Z", None);
+        let b = Entry::default();
+        assert!(!a.overlaps(&b));
+        assert!(!b.overlaps(&a));
+        assert!(!b.overlaps(&b));
+    }
+
+    #[test]
+    fn reversed_entries_do_not_overlap_the_time_in_between() {
+        let reversed = entry("2020-01-01T10:00:00Z", Some("2020-01-01T09:00:00Z"));
+        let between = entry("2020-01-01T09:15:00Z", Some("2020-01-01T09:45:00Z"));
+        assert!(!reversed.overlaps(&between));
+        assert!(!between.overlaps(&reversed));
+    }
+
+    #[test]
+    fn contains() {
+        let e = entry("2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        assert!(!e.contains(time("2020-01-01T08:59:59Z")));
+        assert!(e.contains(time("2020-01-01T09:00:00Z")));
+        assert!(e.contains(time("2020-01-01T09:59:59Z")));
+        assert!(!e.contains(time("2020-01-01T10:00:00Z")));
+        assert!(e.contains(time("2020-01-01T10:30:00+01:00")));
+    }
+
+    #[test]
+    fn contains_for_running_and_reversed_entries() {
+        let running = entry("2020-01-01T09:00:00Z", None);
+        assert!(running.contains(time("2020-01-01T09:00:00Z")));
+        assert!(running.contains(time("2030-01-01T00:00:00Z")));
+        assert!(!running.contains(time("2020-01-01T08:59:59Z")));
+
+        let reversed = entry("2020-01-01T10:00:00Z", Some("2020-01-01T09:00:00Z"));
+        assert!(!reversed.contains(time("2020-01-01T09:30:00Z")));
+        assert!(!reversed.contains(time("2020-01-01T10:00:00Z")));
+
+        assert!(!Entry::default().contains(time("2020-01-01T09:00:00Z")));
     }
-    out
 }
@@ -0,0 +1,63 @@
+use crate::{Entry, Result, Timezone};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use log::debug;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// The last day of the periods that were signed off, like after they were invoiced, kept next to
+/// the log. The entries of those days shouldn't change anymore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Lock {
+    /// The last locked day.
+    pub through: NaiveDate,
+    /// When the lock was set.
+    pub locked: DateTime<FixedOffset>,
+}
+
+impl Lock {
+    /// A lock of every day up to and including `through`, set now.
+    pub fn new(through: NaiveDate) -> Self {
+        Lock {
+            through,
+            locked: Local::now().fixed_offset(),
+        }
+    }
+
+    /// The path of the lock belonging to the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".locked");
+        PathBuf::from(path)
+    }
+
+    /// Reads the lock at `path`, returning `None` if there is none.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Option<Lock>> {
+        match File::open(path) {
+            Ok(file) => Ok(Some(serde_json::from_reader(BufReader::new(file))?)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        debug!("locking through {} in {}", self.through, path.display());
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Whether `entry` is in the locked days, going by the local day it started on, or the day of
+    /// an expense or absence.
+    pub fn covers(&self, entry: &Entry) -> bool {
+        let day = match (&entry.expense, &entry.absence) {
+            (Some(expense), _) => Some(expense.date),
+            (None, Some(absence)) => Some(absence.date),
+            (None, None) => entry.start.map(|start| Timezone::Local.date(&start)),
+        };
+        day.is_some_and(|day| day <= self.through)
+    }
+}
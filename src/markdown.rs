@@ -0,0 +1,145 @@
+use crate::{Painted, Style};
+use std::fmt::Write;
+
+/// Renders `text` as markdown for a terminal, returning one string per line.
+///
+/// Markers like `**` are replaced by styles, or just removed unless `color` is set. Bullets are
+/// drawn as `•` and fenced code blocks are kept as they are, indented by two columns. `wrap`
+/// splits a line of text that will be indented by the given number of columns, and is used for
+/// everything but code.
+pub(crate) fn render<'a, F>(text: &'a str, color: bool, wrap: F) -> Vec<String>
+where
+    F: Fn(&'a str, usize) -> Vec<&'a str>,
+{
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => fence = None,
+            Some(_) => lines.push(format!("  {}", paint(Some(Style::Code), line, color))),
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                fence = Some(&trimmed[..3]);
+            }
+            None => {
+                let (first, rest, text, style) = if let Some(item) = list_item(trimmed) {
+                    let first = format!("{}• ", &line[..indent]);
+                    (first, " ".repeat(indent + 2), item, None)
+                } else if let Some(heading) = heading(trimmed) {
+                    (String::new(), String::new(), heading, Some(Style::Bold))
+                } else {
+                    (String::new(), String::new(), line, None)
+                };
+                let mut inline = Inline::default();
+                for (i, piece) in wrap(text, first.chars().count()).into_iter().enumerate() {
+                    let mut rendered = if i == 0 { first.clone() } else { rest.clone() };
+                    inline.render(piece, style, color, &mut rendered);
+                    lines.push(rendered);
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// The text of a bulleted list item, without its bullet.
+fn list_item(line: &str) -> Option<&str> {
+    line.strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+}
+
+/// The text of a heading, without its `#`s.
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    if text.len() < line.len() && line.len() - text.len() <= 6 && text.starts_with(' ') {
+        Some(text.trim())
+    } else {
+        None
+    }
+}
+
+fn paint(style: Option<Style>, text: &str, color: bool) -> Painted<&str> {
+    Painted::new(style.filter(|_| color), text)
+}
+
+/// The inline styles that are open, which carry over from one wrapped piece of a line to the
+/// next.
+#[derive(Default)]
+struct Inline {
+    bold: bool,
+    italic: bool,
+    code: bool,
+}
+
+impl Inline {
+    fn style(&self, base: Option<Style>) -> Option<Style> {
+        if self.code {
+            Some(Style::Code)
+        } else if self.bold {
+            Some(Style::Bold)
+        } else if self.italic {
+            Some(Style::Italic)
+        } else {
+            base
+        }
+    }
+
+    /// Renders the inline markup of `text` onto `out`, with `base` as the style of plain text.
+    fn render(&mut self, text: &str, base: Option<Style>, color: bool, out: &mut String) {
+        let chars: Vec<char> = text.chars().collect();
+        let mut segment = String::new();
+        let mut flush = |inline: &Inline, segment: &mut String| {
+            if segment.is_empty() {
+                return;
+            }
+            let painted = paint(inline.style(base), segment, color);
+            write!(out, "{}", painted).expect("writing to a string can't fail");
+            segment.clear();
+        };
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1).copied();
+            if self.code {
+                if c == '`' {
+                    flush(self, &mut segment);
+                    self.code = false;
+                } else {
+                    segment.push(c);
+                }
+            } else if c == '`' {
+                flush(self, &mut segment);
+                self.code = true;
+            } else if c == '\\' && next.is_some_and(|n| n.is_ascii_punctuation()) {
+                segment.push(chars[i + 1]);
+                i += 1;
+            } else if (c == '*' || c == '_') && next == Some(c) {
+                flush(self, &mut segment);
+                self.bold = !self.bold;
+                i += 1;
+            } else if (c == '*' || c == '_') && self.toggles_italic(c, prev, next) {
+                flush(self, &mut segment);
+                self.italic = !self.italic;
+            } else {
+                segment.push(c);
+            }
+            i += 1;
+        }
+        flush(self, &mut segment);
+    }
+
+    /// Whether the `*` or `_` between `prev` and `next` opens or closes emphasis, so that
+    /// `snake_case` and a lone `*` stay as they are.
+    fn toggles_italic(&self, c: char, prev: Option<char>, next: Option<char>) -> bool {
+        let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        let space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        if self.italic {
+            !space(prev) && (c == '*' || !word(next))
+        } else {
+            !space(next) && (c == '*' || !word(prev))
+        }
+    }
+}
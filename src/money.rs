@@ -0,0 +1,313 @@
+use chrono::Locale;
+use pure_rust_locales::locale_match;
+use std::{collections::BTreeMap, convert::TryFrom, fmt, str::FromStr};
+
+/// An amount of money, like `1200.50 EUR`.
+///
+/// Amounts are read with a currency code and at most two decimals, like they are written by
+/// `Display`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Money {
+    /// The amount in hundredths of the currency.
+    pub cents: i64,
+    /// The code of the currency, like `EUR`.
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new<S: Into<String>>(cents: i64, currency: S) -> Self {
+        Money {
+            cents,
+            currency: currency.into(),
+        }
+    }
+
+    /// `percent` of the amount, rounded to the nearest hundredth.
+    pub fn percent(&self, percent: f64) -> Money {
+        let cents = (self.cents as f64 * percent / 100.0).round() as i64;
+        Money::new(cents, self.currency.clone())
+    }
+}
+
+/// Reads an amount and a currency code, like `120` and `EUR` in `120 EUR`, returning the amount
+/// in hundredths.
+pub(crate) fn parse_amount(s: &str) -> Option<(i64, String)> {
+    let (amount, currency) = s.trim().split_once(' ')?;
+    let currency = currency.trim();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = |d: &str| !d.is_empty() && d.chars().all(|c| c.is_ascii_digit());
+    if !digits(whole) || (!fraction.is_empty() && !digits(fraction)) || fraction.len() > 2 {
+        return None;
+    }
+    let whole: i64 = whole.parse().ok()?;
+    let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+    let cents = whole.checked_mul(100)?.checked_add(fraction)?;
+    Some((cents, currency.to_ascii_uppercase()))
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "`{}` is not an amount of money, expected an amount and a currency like 30 EUR",
+                s
+            )
+        };
+        let trimmed = s.trim();
+        let (negative, amount) = match trimmed.strip_prefix('-') {
+            Some(amount) => (true, amount),
+            None => (false, trimmed),
+        };
+        let (cents, currency) = parse_amount(amount).ok_or_else(error)?;
+        Ok(Money::new(if negative { -cents } else { cents }, currency))
+    }
+}
+
+/// Like `1200.50 EUR`, the way `MoneyFormat::default` writes it.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&MoneyFormat::default().format(self))
+    }
+}
+
+impl TryFrom<String> for Money {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Money> for String {
+    fn from(money: Money) -> String {
+        money.to_string()
+    }
+}
+
+/// How amounts of money are written, following the conventions of a locale.
+///
+/// The locale decides the decimal separator, how the digits are grouped, and whether the currency
+/// goes before or after the amount. Amounts in the currency of the locale get its symbol, like
+/// `1.200,50 €` for `de_DE`, and other currencies their code, like `1.200,50 USD`. The default, and
+/// the POSIX locale, write amounts like `1200.50 EUR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoneyFormat {
+    pub locale: Locale,
+}
+
+impl Default for MoneyFormat {
+    fn default() -> Self {
+        MoneyFormat {
+            locale: Locale::POSIX,
+        }
+    }
+}
+
+impl MoneyFormat {
+    pub fn new() -> Self {
+        MoneyFormat::default()
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Formats `money`, starting with a `-` if it is negative.
+    pub fn format(&self, money: &Money) -> String {
+        let point = locale_match!(self.locale => LC_MONETARY::MON_DECIMAL_POINT);
+        let separator = locale_match!(self.locale => LC_MONETARY::MON_THOUSANDS_SEP);
+        let grouping = locale_match!(self.locale => LC_MONETARY::MON_GROUPING);
+        let precedes = locale_match!(self.locale => LC_MONETARY::P_CS_PRECEDES) == 1;
+        let local = locale_match!(self.locale => LC_MONETARY::INT_CURR_SYMBOL).trim();
+        let symbol = locale_match!(self.locale => LC_MONETARY::CURRENCY_SYMBOL);
+
+        let cents = money.cents.unsigned_abs();
+        let number = format!(
+            "{}{}{:02}",
+            group(&(cents / 100).to_string(), separator, grouping),
+            if point.is_empty() { "." } else { point },
+            cents % 100
+        );
+        let sign = if money.cents < 0 { "-" } else { "" };
+        let (currency, space) = if money.currency == local && !symbol.is_empty() {
+            let space = locale_match!(self.locale => LC_MONETARY::P_SEP_BY_SPACE) == 1;
+            (symbol, if space { " " } else { "" })
+        } else {
+            (money.currency.as_str(), " ")
+        };
+        if precedes {
+            format!("{}{}{}{}", sign, currency, space, number)
+        } else {
+            format!("{}{}{}{}", sign, number, space, currency)
+        }
+    }
+
+    /// Formats every currency of `earnings`, like `1200.50 EUR + 80.00 USD`, or `0.00` if nothing
+    /// was earned.
+    pub fn format_earnings(&self, earnings: &Earnings) -> String {
+        if earnings.is_empty() {
+            return "0.00".to_string();
+        }
+        let amounts: Vec<_> = earnings.amounts().map(|m| self.format(&m)).collect();
+        amounts.join(" + ")
+    }
+}
+
+/// Separates the groups of `digits` with `separator`, with the sizes of the groups from the right
+/// in `grouping`, like locales give them. The last size repeats, and a size below one ends the
+/// grouping.
+fn group(digits: &str, separator: &str, grouping: &[i64]) -> String {
+    if separator.is_empty() {
+        return digits.to_string();
+    }
+    let mut groups = Vec::new();
+    let mut rest = digits;
+    let mut sizes = grouping.iter().copied();
+    let mut size = sizes.next().unwrap_or(-1);
+    while size > 0 && rest.len() > size as usize {
+        let (head, tail) = rest.split_at(rest.len() - size as usize);
+        groups.push(tail);
+        rest = head;
+        size = sizes.next().unwrap_or(size);
+    }
+    groups.push(rest);
+    groups.reverse();
+    groups.join(separator)
+}
+
+/// Amounts of money earned, kept apart per currency.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Earnings(BTreeMap<String, i64>);
+
+impl Earnings {
+    pub fn new() -> Self {
+        Earnings::default()
+    }
+
+    pub fn add(&mut self, money: Money) {
+        let cents = self.0.entry(money.currency).or_insert(0);
+        *cents = cents.saturating_add(money.cents);
+    }
+
+    /// Adds all of `other`.
+    pub fn extend(&mut self, other: &Earnings) {
+        for money in other.amounts() {
+            self.add(money);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The amount in each currency, ordered by currency code.
+    pub fn amounts(&self) -> impl Iterator<Item = Money> + '_ {
+        self.0
+            .iter()
+            .map(|(currency, &cents)| Money::new(cents, currency.clone()))
+    }
+}
+
+/// Like `1200.50 EUR + 80.00 USD`, the way `MoneyFormat::default` writes it.
+impl fmt::Display for Earnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&MoneyFormat::default().format_earnings(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(s: &str) -> Result<Money, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn parse_amounts() {
+        assert_eq!(parse_amount("120 EUR"), Some((12000, "EUR".to_string())));
+        assert_eq!(parse_amount(" 1.5 usd "), Some((150, "USD".to_string())));
+        assert_eq!(parse_amount("0.05 EUR"), Some((5, "EUR".to_string())));
+        assert_eq!(parse_amount("120"), None);
+        assert_eq!(parse_amount("120 EURO"), None);
+        assert_eq!(parse_amount("120 E1R"), None);
+        assert_eq!(parse_amount("1.505 EUR"), None);
+        assert_eq!(parse_amount("1. EUR"), Some((100, "EUR".to_string())));
+        assert_eq!(parse_amount(".5 EUR"), None);
+        assert_eq!(parse_amount("-1 EUR"), None);
+        assert_eq!(parse_amount("1,5 EUR"), None);
+        assert_eq!(parse_amount("99999999999999999 EUR"), None);
+    }
+
+    #[test]
+    fn parse_money() {
+        assert_eq!(money("1.5 EUR"), Ok(Money::new(150, "EUR")));
+        assert_eq!(money("1200.50 EUR"), Ok(Money::new(120050, "EUR")));
+        assert_eq!(money("-30 EUR"), Ok(Money::new(-3000, "EUR")));
+        assert_eq!(money("-0.05 EUR"), Ok(Money::new(-5, "EUR")));
+        assert!(money("1.505 EUR").is_err());
+        assert!(money("--1 EUR").is_err());
+        assert!(money("30 €").is_err());
+        assert!(money("30 EURO").is_err());
+        assert!(money("30").is_err());
+    }
+
+    #[test]
+    fn display_reads_back() {
+        for s in &["1200.50 EUR", "-0.05 USD", "0.00 CHF"] {
+            assert_eq!(money(s).unwrap().to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn group_digits() {
+        assert_eq!(group("1234567", ",", &[3, 3]), "1,234,567");
+        assert_eq!(group("123", ",", &[3, 3]), "123");
+        assert_eq!(group("1234", ",", &[3]), "1,234");
+        // Indian grouping.
+        assert_eq!(group("12345678", ",", &[3, 2]), "1,23,45,678");
+        // A size below one stops grouping.
+        assert_eq!(group("1234567", ".", &[3, -1]), "1234.567");
+        assert_eq!(group("1234567", ",", &[]), "1234567");
+        assert_eq!(group("1234567", "", &[3]), "1234567");
+    }
+
+    #[test]
+    fn format_posix() {
+        let format = MoneyFormat::new();
+        assert_eq!(
+            format.format(&Money::new(123456789, "EUR")),
+            "1234567.89 EUR"
+        );
+        assert_eq!(format.format(&Money::new(-5, "USD")), "-0.05 USD");
+        assert_eq!(format.locale(Locale::POSIX), format);
+    }
+
+    #[test]
+    fn format_de_de() {
+        let format = MoneyFormat::new().locale(Locale::de_DE);
+        assert_eq!(format.format(&Money::new(120050, "EUR")), "1.200,50 €");
+        assert_eq!(format.format(&Money::new(-120050, "EUR")), "-1.200,50 €");
+        assert_eq!(format.format(&Money::new(120050, "USD")), "1.200,50 USD");
+        assert_eq!(format.format(&Money::new(5, "EUR")), "0,05 €");
+    }
+
+    #[test]
+    fn format_earnings() {
+        let mut earnings = Earnings::new();
+        assert_eq!(earnings.to_string(), "0.00");
+        earnings.add(Money::new(8000, "USD"));
+        earnings.add(Money::new(100000, "EUR"));
+        earnings.add(Money::new(20050, "EUR"));
+        assert_eq!(earnings.to_string(), "1200.50 EUR + 80.00 USD");
+        let format = MoneyFormat::new().locale(Locale::de_DE);
+        assert_eq!(format.format_earnings(&earnings), "1.200,50 € + 80,00 USD");
+    }
+}
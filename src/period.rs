@@ -0,0 +1,220 @@
+use crate::Range;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+/// How long the buckets of a summary are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    /// A week beginning on `start`.
+    Week {
+        start: Weekday,
+    },
+    Month,
+    Quarter,
+    Year,
+    /// A fixed number of days, rounded down to whole days but at least one, e.g. for sprints.
+    Custom(Duration),
+}
+
+impl Period {
+    /// A week beginning on Monday, like ISO weeks do.
+    pub const ISO_WEEK: Period = Period::Week {
+        start: Weekday::Mon,
+    };
+
+    /// The period named `daily`, `weekly`, `monthly`, `quarterly` or `yearly`, with weeks
+    /// beginning on `week_start`.
+    pub fn from_name(name: &str, week_start: Weekday) -> Option<Period> {
+        match name {
+            "daily" => Some(Period::Day),
+            "weekly" => Some(Period::Week { start: week_start }),
+            "monthly" => Some(Period::Month),
+            "quarterly" => Some(Period::Quarter),
+            "yearly" => Some(Period::Year),
+            _ => None,
+        }
+    }
+
+    /// The first day of the period that `date` falls in.
+    ///
+    /// Custom periods are counted from `anchor`, which the other periods ignore.
+    pub fn start_of(self, date: NaiveDate, anchor: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week { start } => {
+                let days = date.weekday().days_since(start);
+                date - Duration::days(days.into())
+            }
+            Period::Month => date.with_day(1).expect("every month has a first day"),
+            Period::Quarter => {
+                let month = date.month0() / 3 * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1)
+                    .expect("every quarter has a first day")
+            }
+            Period::Year => date.with_ordinal(1).expect("every year has a first day"),
+            Period::Custom(len) => {
+                let len = len.num_days().max(1);
+                let periods = (date - anchor).num_days().div_euclid(len);
+                anchor + Duration::days(periods * len)
+            }
+        }
+    }
+}
+
+/// Groups dated items into the periods they fall in, keyed by the first day of each period.
+///
+/// Items outside of `range` are left out. Custom periods are counted from the start of `range`,
+/// or from 1970-01-01 if it has none.
+pub fn group_by_period<I, T>(items: I, period: Period, range: Range) -> BTreeMap<NaiveDate, Vec<T>>
+where
+    I: IntoIterator<Item = (NaiveDate, T)>,
+{
+    let anchor = range.from.unwrap_or_default();
+    let mut groups = BTreeMap::new();
+    for (date, item) in items {
+        if range.contains(date) {
+            groups
+                .entry(period.start_of(date, anchor))
+                .or_insert_with(Vec::new)
+                .push(item);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn start_of(period: Period, d: &str) -> NaiveDate {
+        period.start_of(date(d), date("2020-01-01"))
+    }
+
+    #[test]
+    fn calendar_periods() {
+        // 2020-05-14 is a Thursday.
+        assert_eq!(start_of(Period::Day, "2020-05-14"), date("2020-05-14"));
+        assert_eq!(start_of(Period::ISO_WEEK, "2020-05-14"), date("2020-05-11"));
+        assert_eq!(start_of(Period::Month, "2020-05-14"), date("2020-05-01"));
+        assert_eq!(start_of(Period::Quarter, "2020-05-14"), date("2020-04-01"));
+        assert_eq!(start_of(Period::Quarter, "2020-12-31"), date("2020-10-01"));
+        assert_eq!(start_of(Period::Year, "2020-05-14"), date("2020-01-01"));
+    }
+
+    #[test]
+    fn weeks_beginning_on_other_days() {
+        let sunday = Period::Week {
+            start: Weekday::Sun,
+        };
+        assert_eq!(start_of(sunday, "2020-05-14"), date("2020-05-10"));
+        assert_eq!(start_of(sunday, "2020-05-10"), date("2020-05-10"));
+        assert_eq!(start_of(sunday, "2020-05-09"), date("2020-05-03"));
+
+        let friday = Period::Week {
+            start: Weekday::Fri,
+        };
+        assert_eq!(start_of(friday, "2020-05-14"), date("2020-05-08"));
+        assert_eq!(start_of(friday, "2020-05-15"), date("2020-05-15"));
+        // Across the turn of the year.
+        assert_eq!(start_of(friday, "2020-01-02"), date("2019-12-27"));
+    }
+
+    #[test]
+    fn custom_periods_count_from_the_anchor() {
+        let sprint = Period::Custom(Duration::days(14));
+        assert_eq!(start_of(sprint, "2020-01-01"), date("2020-01-01"));
+        assert_eq!(start_of(sprint, "2020-01-14"), date("2020-01-01"));
+        assert_eq!(start_of(sprint, "2020-01-15"), date("2020-01-15"));
+    }
+
+    #[test]
+    fn custom_periods_before_the_anchor() {
+        let sprint = Period::Custom(Duration::days(14));
+        assert_eq!(start_of(sprint, "2019-12-31"), date("2019-12-18"));
+        assert_eq!(start_of(sprint, "2019-12-18"), date("2019-12-18"));
+        assert_eq!(start_of(sprint, "2019-12-17"), date("2019-12-04"));
+    }
+
+    #[test]
+    fn custom_periods_are_whole_days() {
+        let short = Period::Custom(Duration::hours(36));
+        assert_eq!(start_of(short, "2020-01-02"), date("2020-01-02"));
+        let none = Period::Custom(Duration::hours(1));
+        assert_eq!(start_of(none, "2019-12-31"), date("2019-12-31"));
+        let zero = Period::Custom(Duration::zero());
+        assert_eq!(start_of(zero, "2020-01-03"), date("2020-01-03"));
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(
+            Period::from_name("weekly", Weekday::Sun),
+            Some(Period::Week {
+                start: Weekday::Sun
+            })
+        );
+        assert_eq!(
+            Period::from_name("monthly", Weekday::Mon),
+            Some(Period::Month)
+        );
+        assert_eq!(Period::from_name("fortnightly", Weekday::Mon), None);
+    }
+
+    #[test]
+    fn group_by_month_within_a_range() {
+        let items = vec![
+            (date("2020-01-31"), 1),
+            (date("2020-02-01"), 2),
+            (date("2020-02-29"), 3),
+            (date("2020-03-01"), 4),
+        ];
+        let range = Range::new(Some(date("2020-01-31")), Some(date("2020-03-01")));
+        let groups = group_by_period(items, Period::Month, range);
+        let groups: Vec<_> = groups.into_iter().collect();
+        assert_eq!(
+            groups,
+            [
+                (date("2020-01-01"), vec![1]),
+                (date("2020-02-01"), vec![2, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn group_custom_periods_from_the_start_of_the_range() {
+        let items = vec![
+            (date("2020-01-03"), 'a'),
+            (date("2020-01-09"), 'b'),
+            (date("2020-01-10"), 'c'),
+        ];
+        let week = Period::Custom(Duration::days(7));
+        let range = Range::new(Some(date("2020-01-03")), None);
+        let groups: Vec<_> = group_by_period(items.clone(), week, range)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (date("2020-01-03"), vec!['a', 'b']),
+                (date("2020-01-10"), vec!['c']),
+            ]
+        );
+
+        // Without a start, they are counted from 1970-01-01, which was a Thursday.
+        let groups: Vec<_> = group_by_period(items, week, Range::all())
+            .into_iter()
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (date("2020-01-02"), vec!['a']),
+                (date("2020-01-09"), vec!['b', 'c']),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{money::parse_amount, Money};
+use chrono::Duration;
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// An hourly rate, like `120.50 EUR/h`.
+///
+/// Rates are written as an amount and a currency code, and read with or without the `/h`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rate {
+    /// The amount per hour, in hundredths of the currency.
+    pub cents: i64,
+    /// The code of the currency, like `EUR`.
+    pub currency: String,
+}
+
+impl Rate {
+    pub fn new<S: Into<String>>(cents: i64, currency: S) -> Self {
+        Rate {
+            cents,
+            currency: currency.into(),
+        }
+    }
+
+    /// The amount per hour.
+    pub fn per_hour(&self) -> Money {
+        Money::new(self.cents, self.currency.clone())
+    }
+
+    /// What `duration` of work is worth at this rate, rounded to the nearest hundredth.
+    pub fn earnings(&self, duration: Duration) -> Money {
+        let millis = i128::from(self.cents) * i128::from(duration.num_milliseconds());
+        let cents = (millis + millis.signum() * 1_800_000) / 3_600_000;
+        let cents = i64::try_from(cents).unwrap_or(if cents < 0 { i64::MIN } else { i64::MAX });
+        Money::new(cents, self.currency.clone())
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "`{}` is not a rate, expected an amount and a currency like 120 EUR",
+                s
+            )
+        };
+        let s = s.trim();
+        let (cents, currency) =
+            parse_amount(s.strip_suffix("/h").unwrap_or(s)).ok_or_else(error)?;
+        Ok(Rate::new(cents, currency))
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:02} {}/h",
+            self.cents / 100,
+            self.cents % 100,
+            self.currency
+        )
+    }
+}
+
+impl TryFrom<String> for Rate {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Rate> for String {
+    fn from(rate: Rate) -> String {
+        rate.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn earned(cents: i64, duration: Duration) -> i64 {
+        Rate::new(cents, "EUR").earnings(duration).cents
+    }
+
+    #[test]
+    fn earnings() {
+        assert_eq!(earned(12050, Duration::hours(2)), 24100);
+        assert_eq!(earned(12000, Duration::minutes(90)), 18000);
+        assert_eq!(earned(10000, Duration::zero()), 0);
+        assert_eq!(
+            Rate::new(100, "USD").earnings(Duration::hours(1)),
+            Money::new(100, "USD")
+        );
+    }
+
+    #[test]
+    fn earnings_round_half_away_from_zero() {
+        // A cent per 36 seconds at 1.00 EUR/h.
+        assert_eq!(earned(100, Duration::seconds(18)), 1);
+        assert_eq!(earned(100, Duration::milliseconds(17_999)), 0);
+        assert_eq!(earned(100, Duration::seconds(54)), 2);
+        assert_eq!(earned(100, Duration::seconds(-18)), -1);
+        assert_eq!(earned(100, Duration::milliseconds(-17_999)), 0);
+        assert_eq!(earned(-100, Duration::seconds(18)), -1);
+    }
+
+    #[test]
+    fn earnings_below_a_cent() {
+        assert_eq!(earned(1, Duration::minutes(1)), 0);
+        assert_eq!(earned(1, Duration::minutes(30)), 1);
+        assert_eq!(earned(1, Duration::milliseconds(1)), 0);
+    }
+
+    #[test]
+    fn earnings_of_negative_durations() {
+        assert_eq!(earned(12000, Duration::hours(-2)), -24000);
+        assert_eq!(earned(-12000, Duration::hours(-2)), 24000);
+    }
+
+    #[test]
+    fn earnings_saturate() {
+        let long = Duration::milliseconds(i64::MAX);
+        assert_eq!(earned(i64::MAX, long), i64::MAX);
+        assert_eq!(earned(i64::MIN, long), i64::MIN);
+        assert_eq!(earned(i64::MAX, -long), i64::MIN);
+        // The product of the milliseconds overflows i64, but the earnings fit.
+        assert_eq!(earned(i64::MAX, Duration::hours(1)), i64::MAX);
+    }
+
+    #[test]
+    fn read_and_write() {
+        assert_eq!("120.50 EUR/h".parse(), Ok(Rate::new(12050, "EUR")));
+        assert_eq!("120 eur".parse(), Ok(Rate::new(12000, "EUR")));
+        assert!("120 EUR/d".parse::<Rate>().is_err());
+        assert!("120/h".parse::<Rate>().is_err());
+        assert_eq!(Rate::new(12005, "EUR").to_string(), "120.05 EUR/h");
+        assert_eq!(Rate::new(12050, "EUR").per_hour(), Money::new(12050, "EUR"));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{error::json_message, Entry, Result};
+use std::io::Read;
+
+/// A piece of a log that could not be parsed as an entry.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BrokenEntry {
+    /// The byte offset of the fragment in the log.
+    pub offset: usize,
+    /// The 1-based line of the fragment in the log.
+    pub line: usize,
+    /// The 1-based column of the fragment in the log.
+    pub column: usize,
+    pub error: String,
+    pub text: String,
+}
+
+/// The result of leniently reading a log.
+#[derive(Default, Debug, Clone)]
+pub struct Recovered {
+    pub entries: Vec<Entry>,
+    pub broken: Vec<BrokenEntry>,
+}
+
+/// Reads a log, collecting every entry that can be parsed instead of failing on the first error.
+///
+/// The log is split into top-level fragments without fully parsing it, so a malformed entry (or
+/// a missing comma, or a truncated file) only loses that entry. Raw newlines can't appear inside
+/// JSON strings, so an unterminated string is assumed to end at the end of its line, and the entry
+/// it is in at the next top-level comma or object.
+pub fn read_entries_lenient<R: Read>(mut reader: R) -> Result<Recovered> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+
+    let mut recovered = Recovered::default();
+    for (offset, fragment) in split_fragments(&text) {
+        match serde_json::from_str(fragment) {
+            Ok(entry) => recovered.entries.push(entry),
+            Err(e) => {
+                let before = &text[..offset];
+                let line = before.matches('\n').count() + 1;
+                let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                recovered.broken.push(BrokenEntry {
+                    offset,
+                    line,
+                    column,
+                    error: json_message(&e),
+                    text: fragment.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(recovered)
+}
+
+fn split_fragments(text: &str) -> Vec<(usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut fragments = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let fragment = &text[start..end];
+        let trimmed = fragment.trim_start();
+        let offset = start + fragment.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        if !trimmed.is_empty() {
+            fragments.push((offset, trimmed));
+        }
+    };
+
+    let first = bytes.iter().position(|b| !b.is_ascii_whitespace());
+    let mut start = match first {
+        Some(i) if bytes[i] == b'[' => i + 1,
+        Some(_) => {
+            // Not an array at all, so there are no entries to salvage.
+            push(0, text.len());
+            return fragments;
+        }
+        None => return fragments,
+    };
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escape = false;
+    let mut i = start;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_string {
+            if escape {
+                escape = false;
+            } else if b == b'\\' {
+                escape = true;
+            } else if b == b'"' {
+                in_string = false;
+            } else if b == b'\n' {
+                // The brackets after an unterminated string can't be trusted either, so the
+                // entry it is in is given up on and the next one is looked for.
+                in_string = false;
+                depth = 0;
+            }
+        } else {
+            match b {
+                b'"' => in_string = true,
+                // A new object at the top level means a separator is missing.
+                b'{' if depth == 0 && !text[start..i].trim().is_empty() => {
+                    push(start, i);
+                    start = i;
+                    depth += 1;
+                }
+                b'{' | b'[' => depth += 1,
+                b']' if depth == 0 => {
+                    push(start, i);
+                    // Anything after the end of the array is junk as well.
+                    start = i + 1;
+                    break;
+                }
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                b',' if depth == 0 => {
+                    push(start, i);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    push(start, text.len());
+
+    fragments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recover(text: &str) -> Recovered {
+        read_entries_lenient(text.as_bytes()).unwrap()
+    }
+
+    fn goals(recovered: &Recovered) -> Vec<&str> {
+        recovered.entries.iter().map(|e| e.goal.as_str()).collect()
+    }
+
+    #[test]
+    fn valid_log() {
+        let recovered = recover("[\n  {\"goal\": \"a\"},\n  {\"goal\": \"b, [c]\"}\n]\n");
+        assert_eq!(goals(&recovered), ["a", "b, [c]"]);
+        assert!(recovered.broken.is_empty());
+    }
+
+    #[test]
+    fn empty_logs() {
+        for text in &["", "  \n", "[]", "[\n]\n"] {
+            let recovered = recover(text);
+            assert!(recovered.entries.is_empty(), "{:?}", text);
+            assert!(recovered.broken.is_empty(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn broken_entry_in_the_middle() {
+        let recovered =
+            recover("[\n  {\"goal\": \"a\"},\n  {\"goal\": 1},\n  {\"goal\": \"c\"}\n]");
+        assert_eq!(goals(&recovered), ["a", "c"]);
+        assert_eq!(recovered.broken.len(), 1);
+        let broken = &recovered.broken[0];
+        assert_eq!((broken.line, broken.column, broken.offset), (3, 3, 21));
+        assert_eq!(broken.text, "{\"goal\": 1}");
+    }
+
+    #[test]
+    fn missing_comma() {
+        let recovered = recover("[{\"goal\": \"a\"} {\"goal\": \"b\"}]");
+        assert_eq!(goals(&recovered), ["a", "b"]);
+        assert!(recovered.broken.is_empty());
+    }
+
+    #[test]
+    fn truncated_log() {
+        let recovered = recover("[\n  {\"goal\": \"a\"},\n  {\"goal\": \"b");
+        assert_eq!(goals(&recovered), ["a"]);
+        assert_eq!(recovered.broken.len(), 1);
+        assert_eq!(recovered.broken[0].line, 3);
+    }
+
+    #[test]
+    fn unterminated_string_ends_at_the_end_of_its_line() {
+        let recovered = recover("[\n  {\"goal\": \"a},\n  {\"goal\": \"b\"}\n]");
+        assert_eq!(goals(&recovered), ["b"]);
+        assert_eq!(recovered.broken.len(), 1);
+
+        let text = concat!(
+            "[\n  {\n    \"goal\": \"a,\n    \"result\": \"]\"\n  },\n",
+            "  {\n    \"goal\": \"b\"\n  }\n]",
+        );
+        let recovered = recover(text);
+        assert_eq!(goals(&recovered), ["b"]);
+        assert_eq!(recovered.broken.len(), 1);
+    }
+
+    #[test]
+    fn junk_after_the_log() {
+        let recovered = recover("[{\"goal\": \"a\"}]\ngarbage");
+        assert_eq!(goals(&recovered), ["a"]);
+        assert_eq!(recovered.broken.len(), 1);
+        assert_eq!(recovered.broken[0].text, "garbage");
+        assert_eq!(
+            (recovered.broken[0].line, recovered.broken[0].column),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn not_an_array() {
+        let recovered = recover("not a log");
+        assert!(recovered.entries.is_empty());
+        assert_eq!(recovered.broken.len(), 1);
+        assert_eq!(recovered.broken[0].offset, 0);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{format_dur, summarize, Entry, Error, Period, Range, Result, Timezone};
+use chrono::{Duration, Local, Weekday};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::convert::TryFrom;
+
+/// A rhai script for a custom report.
+///
+/// Scripts see the log as `entries`, an array of entries in order, and their arguments as
+/// `args`. Entries have the fields `id`, `goal`, `result`, `notes`, `tags`, `meta`, `client`,
+/// `billable`, `rate`, like `120.00 EUR/h`, `expense`, like `30.00 EUR`, `absence`, the kind of
+/// time off, like `vacation`, `user`, `active`, `start` and `stop` as Unix timestamps, `day`, the
+/// local date they started on or the date of the expense or absence, and `estimate` and `duration`
+/// in seconds, with `()` for missing values.
+///
+/// `summary(entries, "weekly")` totals entries per period like `timelog summary` does, as an
+/// array of `#{start, duration}`, and `format_duration(seconds)` formats a duration.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    /// Compiles `source`, with weeks in summaries beginning on `week_start`.
+    pub fn compile(source: &str, week_start: Weekday) -> Result<Script> {
+        let mut engine = Engine::new();
+        engine
+            .register_type_with_name::<Entry>("Entry")
+            .register_get("id", |e: &mut Entry| {
+                optional(e.id.and_then(|id| i64::try_from(id).ok()))
+            })
+            .register_get("goal", |e: &mut Entry| e.goal.clone())
+            .register_get("result", |e: &mut Entry| e.result.clone())
+            .register_get("notes", |e: &mut Entry| strings(&e.notes))
+            .register_get("tags", |e: &mut Entry| strings(&e.tags))
+            .register_get("meta", |e: &mut Entry| {
+                let meta = e.meta.iter().map(|(k, v)| (k.into(), v.clone().into()));
+                meta.collect::<Map>()
+            })
+            .register_get("client", |e: &mut Entry| optional(e.client.clone()))
+            .register_get("billable", |e: &mut Entry| e.billable)
+            .register_get("rate", |e: &mut Entry| {
+                optional(e.rate.as_ref().map(|r| r.to_string()))
+            })
+            .register_get("expense", |e: &mut Entry| {
+                optional(e.expense.as_ref().map(|x| x.amount.to_string()))
+            })
+            .register_get("absence", |e: &mut Entry| {
+                optional(e.absence.as_ref().map(|x| x.kind.to_string()))
+            })
+            .register_get("user", |e: &mut Entry| optional(e.user.clone()))
+            .register_get("active", |e: &mut Entry| e.is_active())
+            .register_get("start", |e: &mut Entry| {
+                optional(e.start.map(|t| t.timestamp()))
+            })
+            .register_get("stop", |e: &mut Entry| {
+                optional(e.stop.map(|t| t.timestamp()))
+            })
+            .register_get("day", |e: &mut Entry| {
+                let day = e.start.map(|t| Timezone::Local.date(&t));
+                optional(
+                    day.or(e.expense.as_ref().map(|x| x.date))
+                        .or(e.absence.as_ref().map(|x| x.date))
+                        .map(|d| d.to_string()),
+                )
+            })
+            .register_get("estimate", |e: &mut Entry| {
+                optional(e.estimate.map(|d| d.num_seconds()))
+            })
+            .register_get("duration", |e: &mut Entry| {
+                let running = || Some(Local::now().fixed_offset() - e.start?);
+                let duration = e.duration().or_else(|| running().filter(|_| e.is_active()));
+                optional(duration.map(|d| d.num_seconds()))
+            });
+        engine.register_fn("format_duration", |seconds: i64| {
+            format_dur(Duration::seconds(seconds))
+        });
+        engine.register_fn("summary", move |entries: Array, name: &str| {
+            summary(entries, name, week_start)
+        });
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| Error::Script(e.to_string()))?;
+        Ok(Script { engine, ast })
+    }
+
+    /// Runs the script on `entries`, passing it `args`.
+    pub fn run(&self, entries: Vec<Entry>, args: Vec<String>) -> Result<()> {
+        let mut scope = Scope::new();
+        scope.push(
+            "entries",
+            entries.into_iter().map(Dynamic::from).collect::<Array>(),
+        );
+        scope.push("args", strings(&args));
+        self.engine
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| Error::Script(e.to_string()))
+    }
+}
+
+/// Totals `entries` per period named like `weekly`, as an array of `#{start, duration}`.
+fn summary(
+    entries: Array,
+    name: &str,
+    week_start: Weekday,
+) -> std::result::Result<Array, Box<EvalAltResult>> {
+    let period = Period::from_name(name, week_start).ok_or_else(|| {
+        format!(
+            "`{}` is not a period, expected daily, weekly, monthly, quarterly or yearly",
+            name
+        )
+    })?;
+    let entries: Vec<Entry> = entries.into_iter().filter_map(|e| e.try_cast()).collect();
+    let summary = summarize(&entries, period, Range::all(), Timezone::Local);
+    let buckets = summary.buckets.iter().map(|b| {
+        let mut bucket = Map::new();
+        bucket.insert("start".into(), b.start.to_string().into());
+        bucket.insert("duration".into(), b.total.num_seconds().into());
+        Dynamic::from_map(bucket)
+    });
+    Ok(buckets.collect())
+}
+
+fn optional<T: Into<Dynamic>>(value: Option<T>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Into::into)
+}
+
+fn strings(values: &[String]) -> Array {
+    values.iter().cloned().map(Dynamic::from).collect()
+}
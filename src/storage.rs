@@ -0,0 +1,112 @@
+use crate::{Entries, Result};
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "fs")]
+use {
+    crate::{read_entries_from, write_entries_to, Format},
+    log::debug,
+    std::{
+        fs::File,
+        io::{self, BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Somewhere a log is kept.
+pub trait StorageBackend {
+    /// Reads every entry of the log. A log that doesn't exist yet has no entries.
+    fn load(&self) -> Result<Entries>;
+
+    /// Replaces the log with `entries`.
+    fn save(&self, entries: &Entries) -> Result<()>;
+}
+
+/// Like `StorageBackend`, but for backends that shouldn't block the thread they run on, like
+/// files read through tokio or logs kept on a server.
+///
+/// Only available with the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub trait AsyncStorageBackend {
+    /// Reads every entry of the log. A log that doesn't exist yet has no entries.
+    fn load(&self) -> impl Future<Output = Result<Entries>> + Send;
+
+    /// Replaces the log with `entries`.
+    fn save(&self, entries: &Entries) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// A log kept in a file.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStorage {
+    pub path: PathBuf,
+    pub format: Format,
+}
+
+#[cfg(feature = "fs")]
+impl FileStorage {
+    /// A JSON log at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileStorage {
+            path: path.as_ref().to_path_buf(),
+            format: Format::Json,
+        }
+    }
+
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[cfg(feature = "fs")]
+impl StorageBackend for FileStorage {
+    fn load(&self) -> Result<Entries> {
+        match File::open(&self.path) {
+            Ok(file) => {
+                let entries = read_entries_from(BufReader::new(file), self.format)?;
+                debug!(
+                    "loaded {} entries from {}",
+                    entries.len(),
+                    self.path.display()
+                );
+                Ok(entries)
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("{} doesn't exist yet", self.path.display());
+                Ok(Entries::new())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, entries: &Entries) -> Result<()> {
+        debug!(
+            "saving {} entries to {}",
+            entries.len(),
+            self.path.display()
+        );
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        write_entries_to(&mut writer, entries, self.format)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The file is read and written with `tokio::fs`, and parsed in memory.
+#[cfg(feature = "tokio")]
+impl AsyncStorageBackend for FileStorage {
+    async fn load(&self) -> Result<Entries> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => read_entries_from(&bytes[..], self.format),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Entries::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, entries: &Entries) -> Result<()> {
+        let mut bytes = Vec::new();
+        write_entries_to(&mut bytes, entries, self.format)?;
+        tokio::fs::write(&self.path, bytes).await?;
+        Ok(())
+    }
+}
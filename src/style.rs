@@ -0,0 +1,59 @@
+use std::fmt::{self, Display};
+
+/// The styles of terminal output, drawn with ANSI escape codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Titles and labels, which are dimmed.
+    Title,
+    /// Durations, which are highlighted.
+    Duration,
+    /// The running entry, which is green.
+    Active,
+    /// Strong emphasis in markdown.
+    Bold,
+    /// Emphasis in markdown.
+    Italic,
+    /// Code spans and blocks in markdown.
+    Code,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Title => "2",
+            Style::Duration => "1;36",
+            Style::Active => "32",
+            Style::Bold => "1",
+            Style::Italic => "3",
+            Style::Code => "33",
+        }
+    }
+
+    /// Something that displays `value` in this style, or without any style unless `color` is set.
+    pub fn paint<T: Display>(self, value: T, color: bool) -> Painted<T> {
+        Painted::new(Some(self).filter(|_| color), value)
+    }
+}
+
+/// A value displayed in a style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Painted<T> {
+    style: Option<Style>,
+    value: T,
+}
+
+impl<T> Painted<T> {
+    /// `value` in `style`, if there is one.
+    pub(crate) fn new(style: Option<Style>, value: T) -> Self {
+        Painted { style, value }
+    }
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "\x1b[{}m{}\x1b[0m", style.code(), self.value),
+            None => self.value.fmt(f),
+        }
+    }
+}
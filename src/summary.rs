@@ -0,0 +1,445 @@
+use crate::{group_by_period, Earnings, Entry, Index, Period, Timezone};
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+/// The days a summary covers, from `from` up to but not including `to`.
+///
+/// A missing bound leaves that side of the range open.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl Range {
+    /// A range covering every day.
+    pub fn all() -> Self {
+        Range::default()
+    }
+
+    pub fn new(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        Range { from, to }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date < to)
+    }
+}
+
+/// The time tracked in one period of a summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    /// The first day of the period.
+    pub start: NaiveDate,
+    pub total: Duration,
+}
+
+/// The time tracked per period, in chronological order.
+///
+/// Periods without any tracked time are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub period: Period,
+    pub buckets: Vec<Bucket>,
+}
+
+impl Summary {
+    /// Summarizes per-day totals, like the ones kept in an `Index`.
+    pub fn from_days<I>(days: I, period: Period, range: Range) -> Self
+    where
+        I: IntoIterator<Item = (NaiveDate, Duration)>,
+    {
+        let buckets = group_by_period(days, period, range)
+            .into_iter()
+            .map(|(start, totals)| Bucket {
+                start,
+                total: totals.into_iter().fold(Duration::zero(), |a, b| a + b),
+            })
+            .collect();
+        Summary { period, buckets }
+    }
+
+    /// The time tracked over the whole summary.
+    pub fn total(&self) -> Duration {
+        self.buckets
+            .iter()
+            .fold(Duration::zero(), |total, b| total + b.total)
+    }
+}
+
+/// Summarizes the completed `entries` per `period`.
+///
+/// Entries count towards the day they started on in `timezone`, and only days in `range` are
+/// included.
+pub fn summarize<'a, I>(entries: I, period: Period, range: Range, timezone: Timezone) -> Summary
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut index = Index::new(timezone);
+    for e in entries {
+        index.add(e);
+    }
+    Summary::from_days(index.days(), period, range)
+}
+
+/// The time tracked for one client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientTotal {
+    /// The client, or `None` for the entries without one.
+    pub client: Option<String>,
+    /// The number of stopped entries.
+    pub entries: usize,
+    pub total: Duration,
+    /// The part of the total that is billable.
+    pub billable: Duration,
+    /// What the billable entries with a rate earned.
+    pub earnings: Earnings,
+}
+
+/// Totals the time of the stopped `entries` per client, sorted by client, with the entries
+/// without a client first.
+pub fn summarize_clients<'a, I>(entries: I) -> Vec<ClientTotal>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut clients = BTreeMap::new();
+    for e in entries {
+        if let Some(duration) = e.duration() {
+            let c = clients
+                .entry(e.client.clone())
+                .or_insert_with(|| ClientTotal {
+                    client: e.client.clone(),
+                    entries: 0,
+                    total: Duration::zero(),
+                    billable: Duration::zero(),
+                    earnings: Earnings::new(),
+                });
+            c.entries += 1;
+            c.total += duration;
+            if e.billable {
+                c.billable += duration;
+                if let Some(rate) = &e.rate {
+                    c.earnings.add(rate.earnings(duration));
+                }
+            }
+        }
+    }
+    clients.into_values().collect()
+}
+
+/// The time tracked for one project, going by the `project` meta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectTotal {
+    /// The project, or `None` for the entries without one.
+    pub project: Option<String>,
+    /// The number of stopped entries.
+    pub entries: usize,
+    pub total: Duration,
+}
+
+/// Totals the time of the stopped `entries` per `project` meta, sorted by project, with the
+/// entries without a project first.
+pub fn summarize_projects<'a, I>(entries: I) -> Vec<ProjectTotal>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut projects = BTreeMap::new();
+    for e in entries {
+        if let Some(duration) = e.duration() {
+            let project = e.meta.get("project");
+            let p = projects.entry(project).or_insert_with(|| ProjectTotal {
+                project: project.cloned(),
+                entries: 0,
+                total: Duration::zero(),
+            });
+            p.entries += 1;
+            p.total += duration;
+        }
+    }
+    projects.into_values().collect()
+}
+
+/// The time tracked by one person, going by the `user` of the entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserTotal {
+    /// The person, or `None` for the entries without a user.
+    pub user: Option<String>,
+    /// The number of stopped entries.
+    pub entries: usize,
+    pub total: Duration,
+}
+
+/// Totals the time of the stopped `entries` per user, sorted by user, with the entries without
+/// one first.
+pub fn summarize_users<'a, I>(entries: I) -> Vec<UserTotal>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut users = BTreeMap::new();
+    for e in entries {
+        if let Some(duration) = e.duration() {
+            let u = users.entry(&e.user).or_insert_with(|| UserTotal {
+                user: e.user.clone(),
+                entries: 0,
+                total: Duration::zero(),
+            });
+            u.entries += 1;
+            u.total += duration;
+        }
+    }
+    users.into_values().collect()
+}
+
+/// Totals what the stopped, billable `entries` with a rate earned per `period`, keyed by the
+/// first day of each period.
+///
+/// Entries count towards the day they started on in `timezone`, and only days in `range` are
+/// included, like in `summarize`.
+pub fn summarize_earnings<'a, I>(
+    entries: I,
+    period: Period,
+    range: Range,
+    timezone: Timezone,
+) -> BTreeMap<NaiveDate, Earnings>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let earned = entries.into_iter().filter_map(|e| {
+        let rate = e.rate.as_ref().filter(|_| e.billable)?;
+        let mut earnings = Earnings::new();
+        earnings.add(rate.earnings(e.duration()?));
+        Some((timezone.date(&e.start?), earnings))
+    });
+    group_by_period(earned, period, range)
+        .into_iter()
+        .map(|(start, earned)| {
+            let mut total = Earnings::new();
+            for earnings in &earned {
+                total.extend(earnings);
+            }
+            (start, total)
+        })
+        .collect()
+}
+
+/// How the estimates for one goal compare to the time actually spent on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoalEstimate {
+    pub goal: String,
+    /// The number of stopped entries with an estimate.
+    pub entries: usize,
+    pub estimated: Duration,
+    pub actual: Duration,
+}
+
+impl GoalEstimate {
+    /// How much longer than estimated the goal took, or a negative duration if it took less.
+    pub fn variance(&self) -> Duration {
+        self.actual - self.estimated
+    }
+}
+
+/// Totals the estimated and actual time of the stopped, estimated `entries` per goal, sorted by
+/// goal.
+///
+/// Entries are grouped by the first line of their goal.
+pub fn summarize_estimates<'a, I>(entries: I) -> Vec<GoalEstimate>
+where
+    I: IntoIterator<Item = &'a Entry>,
+{
+    let mut goals = BTreeMap::new();
+    for e in entries {
+        if let (Some(estimate), Some(actual)) = (e.estimate, e.duration()) {
+            let goal = e.goal.lines().next().unwrap_or("").trim();
+            let g = goals.entry(goal).or_insert_with(|| GoalEstimate {
+                goal: goal.to_string(),
+                entries: 0,
+                estimated: Duration::zero(),
+                actual: Duration::zero(),
+            });
+            g.entries += 1;
+            g.estimated += estimate;
+            g.actual += actual;
+        }
+    }
+    goals.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Money, Rate};
+    use chrono::DateTime;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn entry(start: &str, stop: Option<&str>) -> Entry {
+        let time = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        Entry {
+            start: Some(time(start)),
+            stop: stop.map(time),
+            ..Entry::default()
+        }
+    }
+
+    /// An entry on `day` of January 2020 that took `hours`.
+    fn hours_on(day: u32, hours: u32) -> Entry {
+        entry(
+            &format!("2020-01-{:02}T08:00:00Z", day),
+            Some(&format!("2020-01-{:02}T{:02}:00:00Z", day, 8 + hours)),
+        )
+    }
+
+    #[test]
+    fn range_is_half_open() {
+        let range = Range::new(Some(date("2020-01-02")), Some(date("2020-01-04")));
+        assert!(!range.contains(date("2020-01-01")));
+        assert!(range.contains(date("2020-01-02")));
+        assert!(range.contains(date("2020-01-03")));
+        assert!(!range.contains(date("2020-01-04")));
+        assert!(Range::all().contains(date("1900-01-01")));
+        assert!(Range::new(None, Some(date("2020-01-04"))).contains(date("1900-01-01")));
+    }
+
+    #[test]
+    fn summarize_by_day() {
+        let entries = [hours_on(1, 1), hours_on(1, 2), hours_on(3, 4)];
+        let summary = summarize(&entries, Period::Day, Range::all(), Timezone::Utc);
+        let buckets: Vec<_> = summary.buckets.iter().map(|b| (b.start, b.total)).collect();
+        assert_eq!(
+            buckets,
+            [
+                (date("2020-01-01"), Duration::hours(3)),
+                (date("2020-01-03"), Duration::hours(4)),
+            ]
+        );
+        assert_eq!(summary.total(), Duration::hours(7));
+    }
+
+    #[test]
+    fn summarize_within_a_range() {
+        let entries = [hours_on(1, 1), hours_on(2, 2), hours_on(3, 4)];
+        let range = Range::new(Some(date("2020-01-02")), Some(date("2020-01-03")));
+        let summary = summarize(&entries, Period::Day, range, Timezone::Utc);
+        assert_eq!(summary.buckets.len(), 1);
+        assert_eq!(summary.buckets[0].start, date("2020-01-02"));
+        assert_eq!(summary.total(), Duration::hours(2));
+    }
+
+    #[test]
+    fn summarize_by_start_day_in_the_timezone() {
+        let entries = [entry("2020-01-01T23:30:00Z", Some("2020-01-02T00:30:00Z"))];
+        let summary = summarize(&entries, Period::Day, Range::all(), Timezone::Utc);
+        assert_eq!(summary.buckets[0].start, date("2020-01-01"));
+
+        let plus_one = Timezone::Fixed(chrono::FixedOffset::east_opt(3600).unwrap());
+        let summary = summarize(&entries, Period::Day, Range::all(), plus_one);
+        assert_eq!(summary.buckets[0].start, date("2020-01-02"));
+    }
+
+    #[test]
+    fn active_entries_are_left_out() {
+        let mut running = entry("2020-01-01T08:00:00Z", None);
+        running.client = Some("acme".to_string());
+        running.user = Some("alice".to_string());
+        running.estimate = Some(Duration::hours(1));
+        running.meta.insert("project".to_string(), "p".to_string());
+        running.billable = true;
+        running.rate = Some(Rate::new(10000, "EUR"));
+        let entries = [running];
+
+        let summary = summarize(&entries, Period::Day, Range::all(), Timezone::Utc);
+        assert!(summary.buckets.is_empty());
+        assert!(summarize_clients(&entries).is_empty());
+        assert!(summarize_projects(&entries).is_empty());
+        assert!(summarize_users(&entries).is_empty());
+        assert!(summarize_estimates(&entries).is_empty());
+        assert!(summarize_earnings(&entries, Period::Day, Range::all(), Timezone::Utc).is_empty());
+    }
+
+    #[test]
+    fn clients_without_a_name_sort_first() {
+        let mut entries = vec![hours_on(1, 1), hours_on(2, 2), hours_on(3, 4)];
+        entries[0].client = Some("globex".to_string());
+        entries[2].client = Some("acme".to_string());
+        entries[2].billable = true;
+        entries[2].rate = Some(Rate::new(1000, "EUR"));
+
+        let clients = summarize_clients(&entries);
+        let names: Vec<_> = clients.iter().map(|c| c.client.as_deref()).collect();
+        assert_eq!(names, [None, Some("acme"), Some("globex")]);
+        assert_eq!(clients[0].total, Duration::hours(2));
+        assert_eq!(clients[1].billable, Duration::hours(4));
+        assert_eq!(
+            clients[1].earnings.amounts().collect::<Vec<_>>(),
+            [Money::new(4000, "EUR")]
+        );
+        assert_eq!(clients[2].billable, Duration::zero());
+        assert!(clients[2].earnings.is_empty());
+    }
+
+    #[test]
+    fn users_and_projects_without_a_name_sort_first() {
+        let mut entries = vec![hours_on(1, 1), hours_on(2, 2), hours_on(3, 4)];
+        entries[0].user = Some("bob".to_string());
+        entries[1].user = Some("alice".to_string());
+        entries[1]
+            .meta
+            .insert("project".to_string(), "timelog".to_string());
+
+        let users = summarize_users(&entries);
+        let names: Vec<_> = users.iter().map(|u| u.user.as_deref()).collect();
+        assert_eq!(names, [None, Some("alice"), Some("bob")]);
+        assert_eq!(users[0].total, Duration::hours(4));
+
+        let projects = summarize_projects(&entries);
+        let names: Vec<_> = projects.iter().map(|p| p.project.as_deref()).collect();
+        assert_eq!(names, [None, Some("timelog")]);
+        assert_eq!(projects[0].entries, 2);
+    }
+
+    #[test]
+    fn earnings_only_count_billable_time_with_a_rate() {
+        let mut entries = vec![hours_on(1, 1), hours_on(1, 2), hours_on(8, 4)];
+        for e in &mut entries {
+            e.rate = Some(Rate::new(1000, "EUR"));
+            e.billable = true;
+        }
+        entries[1].billable = false;
+        entries.push(hours_on(2, 8));
+        entries[3].billable = true;
+
+        let earned = summarize_earnings(&entries, Period::ISO_WEEK, Range::all(), Timezone::Utc);
+        let weeks: Vec<_> = earned
+            .iter()
+            .map(|(start, e)| (*start, e.amounts().collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(
+            weeks,
+            [
+                (date("2019-12-30"), vec![Money::new(1000, "EUR")]),
+                (date("2020-01-06"), vec![Money::new(4000, "EUR")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_are_grouped_by_the_first_line_of_the_goal() {
+        let mut entries = vec![hours_on(1, 1), hours_on(2, 3), hours_on(3, 2)];
+        entries[0].goal = "write tests\nfor summary".to_string();
+        entries[1].goal = "write tests".to_string();
+        entries[0].estimate = Some(Duration::hours(2));
+        entries[1].estimate = Some(Duration::hours(1));
+        entries[2].goal = "not estimated".to_string();
+
+        let estimates = summarize_estimates(&entries);
+        assert_eq!(estimates.len(), 1);
+        let g = &estimates[0];
+        assert_eq!((g.goal.as_str(), g.entries), ("write tests", 2));
+        assert_eq!(g.estimated, Duration::hours(3));
+        assert_eq!(g.actual, Duration::hours(4));
+        assert_eq!(g.variance(), Duration::hours(1));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{Entry, Result};
+use log::debug;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+/// The marker that begins every top-level entry in a log written by `write_entries`.
+///
+/// Strings are always escaped by `serde_json`, so a raw newline followed by a two-space indent
+/// can only ever appear in front of a top-level entry.
+const ENTRY_MARKER: &[u8] = b"\n  {";
+
+const INITIAL_CHUNK: u64 = 4096;
+
+/// The last entry of a log file, along with where it lives in the file.
+///
+/// This allows the commands that only touch the end of the log (`start`, `stop`, `note`) to
+/// rewrite a few hundred bytes instead of reserializing every entry.
+#[derive(Debug, Clone)]
+pub struct Tail {
+    /// The offset of the newline that starts the last entry.
+    offset: u64,
+    /// Whether the last entry is also the first one, i.e. it directly follows the `[`.
+    is_first: bool,
+    pub entry: Entry,
+}
+
+impl Tail {
+    /// Locates and parses the last entry of `file`.
+    ///
+    /// Returns `Ok(None)` when the file is empty or isn't laid out the way `write_entries` lays
+    /// it out (e.g. it was compacted or edited by hand). Callers should then fall back to reading
+    /// and writing the whole log.
+    pub fn read(file: &mut File) -> Result<Option<Tail>> {
+        let len = file.seek(SeekFrom::End(0))?;
+        let mut chunk = INITIAL_CHUNK;
+
+        loop {
+            let offset = len.saturating_sub(chunk);
+            let mut buf = Vec::with_capacity((len - offset) as usize);
+            file.seek(SeekFrom::Start(offset))?;
+            file.take(len - offset).read_to_end(&mut buf)?;
+
+            let marker = buf
+                .windows(ENTRY_MARKER.len())
+                .rposition(|w| w == ENTRY_MARKER);
+
+            match marker {
+                // The marker must be preceded by the `,` or `[` that we are going to keep.
+                Some(i) if i > 0 => {
+                    let tail = Tail::parse(offset + i as u64, &buf[i - 1..]);
+                    match &tail {
+                        Some(tail) => debug!("found the last entry at byte {}", tail.offset),
+                        None => debug!("the end of the log isn't laid out like timelog writes it"),
+                    }
+                    return Ok(tail);
+                }
+                _ if offset == 0 => {
+                    debug!("found no entries at the end of the log");
+                    return Ok(None);
+                }
+                _ => chunk *= 2,
+            }
+        }
+    }
+
+    fn parse(offset: u64, buf: &[u8]) -> Option<Tail> {
+        let is_first = match buf[0] {
+            b'[' => true,
+            b',' => false,
+            _ => return None,
+        };
+
+        let end = buf.iter().rposition(|b| !b.is_ascii_whitespace())?;
+        if buf[end] != b']' {
+            return None;
+        }
+
+        let entry = serde_json::from_slice(&buf[1..end]).ok()?;
+        Some(Tail {
+            offset,
+            is_first,
+            entry,
+        })
+    }
+
+    /// Replaces the last entry of `file` with `entries`, leaving the rest of the file untouched.
+    ///
+    /// `entries` must be sorted and must not sort before the entry being replaced, otherwise the
+    /// log would no longer be in order.
+    pub fn replace(self, file: &mut File, entries: &[Entry]) -> Result<()> {
+        let mut out = Vec::new();
+        let truncate_at = if entries.is_empty() {
+            if self.is_first {
+                out.extend_from_slice(b"]");
+                self.offset
+            } else {
+                out.extend_from_slice(b"\n]");
+                self.offset - 1
+            }
+        } else {
+            for (i, entry) in entries.iter().enumerate() {
+                if i != 0 {
+                    out.push(b',');
+                }
+                out.push(b'\n');
+                let pretty = serde_json::to_string_pretty(entry)?;
+                for (j, line) in pretty.lines().enumerate() {
+                    if j != 0 {
+                        out.push(b'\n');
+                    }
+                    out.extend_from_slice(b"  ");
+                    out.extend_from_slice(line.as_bytes());
+                }
+            }
+            out.extend_from_slice(b"\n]");
+            self.offset
+        };
+
+        debug!(
+            "replacing the log from byte {} with {} entries",
+            truncate_at,
+            entries.len()
+        );
+        file.set_len(truncate_at)?;
+        file.seek(SeekFrom::Start(truncate_at))?;
+        file.write_all(&out)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write_entries;
+    use chrono::DateTime;
+    use std::{
+        fs::{self, OpenOptions},
+        path::PathBuf,
+    };
+
+    /// A log in the temporary directory, removed again when dropped.
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new(name: &str, entries: &[Entry]) -> TempLog {
+            let path = std::env::temp_dir().join(format!(
+                "timelog-tail-{}-{}.json",
+                std::process::id(),
+                name
+            ));
+            write_entries(File::create(&path).unwrap(), entries).unwrap();
+            TempLog(path)
+        }
+
+        fn open(&self) -> File {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&self.0)
+                .unwrap()
+        }
+
+        fn replace_last(&self, entries: &[Entry]) {
+            let mut file = self.open();
+            let tail = Tail::read(&mut file).unwrap().unwrap();
+            tail.replace(&mut file, entries).unwrap();
+        }
+
+        fn contents(&self) -> String {
+            fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn entry(id: u64, start: &str, stop: Option<&str>) -> Entry {
+        let time = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        Entry {
+            id: Some(id),
+            start: Some(time(start)),
+            stop: stop.map(time),
+            goal: format!("goal {}\n\"quoted\"\n  {{", id),
+            ..Entry::default()
+        }
+    }
+
+    /// What writing `entries` from scratch would produce.
+    fn written(entries: &[Entry]) -> String {
+        let mut out = Vec::new();
+        write_entries(&mut out, entries).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn sample() -> Vec<Entry> {
+        let mut first = entry(1, "2020-01-01T09:00:00Z", Some("2020-01-01T10:00:00Z"));
+        first.notes = vec!["a note".to_string()];
+        first
+            .meta
+            .insert("project".to_string(), "timelog".to_string());
+        vec![first, entry(2, "2020-01-01T11:00:00Z", None)]
+    }
+
+    #[test]
+    fn read_finds_the_last_entry() {
+        let entries = sample();
+        let log = TempLog::new("read", &entries);
+        let tail = Tail::read(&mut log.open()).unwrap().unwrap();
+        assert_eq!(tail.entry, entries[1]);
+        assert!(!tail.is_first);
+    }
+
+    #[test]
+    fn read_gives_up_on_other_layouts() {
+        let log = TempLog::new("empty", &[]);
+        assert!(Tail::read(&mut log.open()).unwrap().is_none());
+
+        fs::write(&log.0, serde_json::to_string(&sample()).unwrap()).unwrap();
+        assert!(Tail::read(&mut log.open()).unwrap().is_none());
+
+        fs::write(&log.0, "").unwrap();
+        assert!(Tail::read(&mut log.open()).unwrap().is_none());
+    }
+
+    #[test]
+    fn replace_the_last_entry() {
+        let mut entries = sample();
+        let log = TempLog::new("replace", &entries);
+        entries[1].stop = Some(DateTime::parse_from_rfc3339("2020-01-01T12:00:00Z").unwrap());
+        log.replace_last(&entries[1..]);
+        assert_eq!(log.contents(), written(&entries));
+    }
+
+    #[test]
+    fn replace_the_last_entry_with_several() {
+        let mut entries = sample();
+        let log = TempLog::new("several", &entries);
+        entries[1].stop = Some(DateTime::parse_from_rfc3339("2020-01-01T12:00:00Z").unwrap());
+        entries.push(entry(3, "2020-01-01T12:00:00Z", None));
+        log.replace_last(&entries[1..]);
+        assert_eq!(log.contents(), written(&entries));
+    }
+
+    #[test]
+    fn replace_the_last_entry_with_none() {
+        let entries = sample();
+        let log = TempLog::new("none", &entries);
+        log.replace_last(&[]);
+        assert_eq!(log.contents(), written(&entries[..1]));
+        assert_eq!(
+            Tail::read(&mut log.open()).unwrap().unwrap().entry,
+            entries[0]
+        );
+    }
+
+    #[test]
+    fn replace_the_only_entry() {
+        let entries = sample();
+        let log = TempLog::new("only", &entries[1..]);
+        log.replace_last(&entries);
+        assert_eq!(log.contents(), written(&entries));
+
+        let log = TempLog::new("only-none", &entries[1..]);
+        log.replace_last(&[]);
+        assert_eq!(log.contents(), written(&[]));
+    }
+}
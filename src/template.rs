@@ -0,0 +1,337 @@
+use crate::{DurationFormat, DurationStyle, Entry, Timezone};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Duration, FixedOffset,
+};
+use std::str::FromStr;
+
+/// A piece of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field { name: String, spec: Option<String> },
+}
+
+/// An output format with placeholders for fields, like `{start:%H:%M} {goal}`.
+///
+/// A placeholder is the name of a field in braces, optionally followed by a colon and a format
+/// specification for that field. Literal braces are written as `{{` and `}}`, and `\t` and `\n`
+/// stand for a tab and a newline, so that they can be written in shell arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    /// The fields used in the template, with their format specifications.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.pieces.iter().filter_map(|p| match p {
+            Piece::Text(_) => None,
+            Piece::Field { name, spec } => Some((name.as_str(), spec.as_deref())),
+        })
+    }
+
+    /// Fills in the template, getting the value of each placeholder from `field`.
+    pub fn render<F>(&self, mut field: F) -> String
+    where
+        F: FnMut(&str, Option<&str>) -> String,
+    {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => out += text,
+                Piece::Field { name, spec } => out += &field(name, spec.as_deref()),
+            }
+        }
+        out
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("`{{{}` is missing a closing `}}`", rest))?;
+                    let (name, spec) = match rest[..end].split_once(':') {
+                        Some((name, spec)) => (name, Some(spec.to_string())),
+                        None => (&rest[..end], None),
+                    };
+                    if name.is_empty() {
+                        return Err("a placeholder is missing a field name".to_string());
+                    }
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field {
+                        name: name.to_string(),
+                        spec,
+                    });
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err("`}` has no opening `{`, use `}}` for a brace".to_string()),
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') | None => text.push('\\'),
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Template { pieces })
+    }
+}
+
+/// A template for a single line about an entry, checked to only use fields that entries have.
+///
+/// The fields are `id`, `start`, `stop`, `duration`, `estimate`, `goal`, `result`, `notes`,
+/// `tags`, `client`, `billable`, `rate`, `expense`, `absence` (the kind of time off), `user`,
+/// `active` and `meta.<key>`. Times take a `strftime` format, like `{start:%H:%M}`, and durations
+/// a style, like `{duration:clock}`. Text fields only show their first line, so that every entry
+/// stays on one line. Missing values are left empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryTemplate {
+    template: Template,
+    pub timezone: Timezone,
+    pub duration: DurationFormat,
+}
+
+const ENTRY_FIELDS: &[&str] = &[
+    "id", "start", "stop", "duration", "estimate", "goal", "result", "notes", "tags", "client",
+    "billable", "rate", "expense", "absence", "user", "active",
+];
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+impl EntryTemplate {
+    pub fn timezone(mut self, timezone: Timezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn duration_format(mut self, duration: DurationFormat) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Fills in the template for `entry`.
+    pub fn render(&self, entry: &Entry) -> String {
+        let time = |t: Option<DateTime<FixedOffset>>, spec: Option<&str>| {
+            t.map(|t| {
+                let t = self.timezone.convert(&t);
+                t.format(spec.unwrap_or(TIME_FORMAT)).to_string()
+            })
+            .unwrap_or_default()
+        };
+        let duration = |d: Option<Duration>, spec: Option<&str>| {
+            let mut format = self.duration;
+            if let Some(style) = spec.and_then(|s| s.parse().ok()) {
+                format = format.style(style);
+            }
+            d.map(|d| format.format(d)).unwrap_or_default()
+        };
+        let first_line = |s: &str| s.lines().next().unwrap_or("").trim().to_string();
+
+        self.template.render(|name, spec| match name {
+            "id" => entry.id.map(|id| id.to_string()).unwrap_or_default(),
+            "start" => time(entry.start, spec),
+            "stop" => time(entry.stop, spec),
+            "duration" => duration(entry.duration(), spec),
+            "estimate" => duration(entry.estimate, spec),
+            "goal" => first_line(&entry.goal),
+            "result" => first_line(&entry.result),
+            "notes" => entry
+                .notes
+                .iter()
+                .map(|n| first_line(n))
+                .collect::<Vec<_>>()
+                .join("; "),
+            "tags" => entry.tags.join(","),
+            "client" => entry.client.clone().unwrap_or_default(),
+            "billable" => entry.billable.to_string(),
+            "rate" => entry
+                .rate
+                .as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or_default(),
+            "expense" => entry
+                .expense
+                .as_ref()
+                .map(|e| e.amount.to_string())
+                .unwrap_or_default(),
+            "absence" => entry
+                .absence
+                .as_ref()
+                .map(|a| a.kind.to_string())
+                .unwrap_or_default(),
+            "user" => entry.user.clone().unwrap_or_default(),
+            "active" => entry.is_active().to_string(),
+            _ => name
+                .strip_prefix("meta.")
+                .and_then(|key| entry.meta.get(key))
+                .map(|v| first_line(v))
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl FromStr for EntryTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template: Template = s.parse()?;
+        for (name, spec) in template.fields() {
+            if !ENTRY_FIELDS.contains(&name) && !name.starts_with("meta.") {
+                return Err(format!("`{}` is not a field of entries", name));
+            }
+            match (name, spec) {
+                (_, None) => {}
+                ("start" | "stop", Some(spec)) => {
+                    if StrftimeItems::new(spec).any(|i| i == Item::Error) {
+                        return Err(format!("`{}` is not a valid time format", spec));
+                    }
+                }
+                ("duration" | "estimate", Some(spec)) => {
+                    spec.parse::<DurationStyle>()?;
+                }
+                (name, Some(_)) => return Err(format!("`{}` doesn't take a format", name)),
+            }
+        }
+        Ok(EntryTemplate {
+            template,
+            timezone: Timezone::default(),
+            duration: DurationFormat::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(s: &str) -> Result<Template, String> {
+        s.parse()
+    }
+
+    fn fields(s: &str) -> Vec<(String, Option<String>)> {
+        template(s)
+            .unwrap()
+            .fields()
+            .map(|(name, spec)| (name.to_string(), spec.map(str::to_string)))
+            .collect()
+    }
+
+    fn render(s: &str) -> String {
+        template(s).unwrap().render(|name, spec| match spec {
+            Some(spec) => format!("<{} {}>", name, spec),
+            None => format!("<{}>", name),
+        })
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            fields("{start:%H:%M} {goal}"),
+            [
+                ("start".to_string(), Some("%H:%M".to_string())),
+                ("goal".to_string(), None),
+            ]
+        );
+        assert_eq!(render("{start:%H:%M} {goal}!"), "<start %H:%M> <goal>!");
+        assert_eq!(render("no fields"), "no fields");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{{goal}}}"), "{<goal>}");
+        assert_eq!(render("{{goal}}"), "{goal}");
+        assert!(fields("{{goal}}").is_empty());
+    }
+
+    #[test]
+    fn tabs_and_newlines() {
+        assert_eq!(render(r"{id}\t{goal}\n"), "<id>\t<goal>\n");
+        assert_eq!(render(r"a\\tb"), r"a\tb");
+        assert_eq!(render(r"a\xb\"), r"a\xb\");
+    }
+
+    #[test]
+    fn broken_placeholders() {
+        assert!(template("{goal").is_err());
+        assert!(template("{goal} {start:%H").is_err());
+        assert!(template("goal}").is_err());
+        assert!(template("{}").is_err());
+        assert!(template("{:%H}").is_err());
+    }
+
+    #[test]
+    fn entry_fields() {
+        assert!("{id} {meta.project} {duration:clock} {stop:%F}"
+            .parse::<EntryTemplate>()
+            .is_ok());
+        assert_eq!(
+            "{project}".parse::<EntryTemplate>(),
+            Err("`project` is not a field of entries".to_string())
+        );
+        assert_eq!(
+            "{goal:%H}".parse::<EntryTemplate>(),
+            Err("`goal` doesn't take a format".to_string())
+        );
+        assert!("{start:%Q}".parse::<EntryTemplate>().is_err());
+        assert!("{duration:weeks}".parse::<EntryTemplate>().is_err());
+    }
+
+    #[test]
+    fn render_entries() {
+        let time = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut entry = Entry {
+            id: Some(7),
+            start: Some(time("2020-01-01T08:00:00+01:00")),
+            stop: Some(time("2020-01-01T09:30:00+01:00")),
+            goal: "write tests\nfor templates".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            ..Entry::default()
+        };
+        entry
+            .meta
+            .insert("project".to_string(), "timelog".to_string());
+
+        let template: EntryTemplate = r"{id}\t{start:%H:%M}-{stop:%H:%M}\t{duration:clock}\t{goal}"
+            .parse()
+            .unwrap();
+        let template = template.timezone(Timezone::Utc);
+        assert_eq!(
+            template.render(&entry),
+            "7\t07:00-08:30\t1:30:00\twrite tests"
+        );
+
+        let template: EntryTemplate = "[{client}|{tags}|{meta.project}|{meta.other}|{active}]"
+            .parse()
+            .unwrap();
+        assert_eq!(template.render(&entry), "[|a,b|timelog||false]");
+    }
+}
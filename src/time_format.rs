@@ -0,0 +1,100 @@
+use chrono::{DateTime, Locale, NaiveDate, TimeZone};
+use std::{borrow::Cow, convert::TryFrom, fmt::Display};
+
+/// How times and dates are shown.
+///
+/// The default is a 24-hour clock with English month and day names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeFormat {
+    /// Whether to use a 12-hour clock with AM and PM.
+    pub twelve_hour: bool,
+    /// The language of month and day names, and of AM and PM.
+    pub locale: Locale,
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        TimeFormat {
+            twelve_hour: false,
+            locale: Locale::POSIX,
+        }
+    }
+}
+
+impl TimeFormat {
+    pub fn new() -> Self {
+        TimeFormat::default()
+    }
+
+    pub fn twelve_hour(mut self, twelve_hour: bool) -> Self {
+        self.twelve_hour = twelve_hour;
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// The `strftime` pattern for a time of day, like `%H:%M`.
+    pub fn time(&self) -> &'static str {
+        if self.twelve_hour {
+            "%I:%M %p"
+        } else {
+            "%H:%M"
+        }
+    }
+
+    /// The `strftime` pattern for a date and a time of day, like `%Y-%m-%d %H:%M`.
+    pub fn date_time(&self) -> &'static str {
+        if self.twelve_hour {
+            "%Y-%m-%d %I:%M %p"
+        } else {
+            "%Y-%m-%d %H:%M"
+        }
+    }
+
+    /// The `strftime` pattern for a full timestamp, with fractional seconds and the offset.
+    pub fn timestamp(&self) -> &'static str {
+        if self.twelve_hour {
+            "%Y-%m-%d %I:%M:%S%.f %p %:z"
+        } else {
+            "%Y-%m-%d %H:%M:%S%.f %:z"
+        }
+    }
+
+    /// Formats `t` with the `strftime` `pattern`, using the names of the locale.
+    pub fn format<Tz: TimeZone>(&self, t: &DateTime<Tz>, pattern: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        t.format_localized(&self.localize(pattern), self.locale)
+            .to_string()
+    }
+
+    /// Formats `date` with the `strftime` `pattern`, using the names of the locale.
+    pub fn format_date(&self, date: NaiveDate, pattern: &str) -> String {
+        date.format_localized(&self.localize(pattern), self.locale)
+            .to_string()
+    }
+
+    /// Leaves AM and PM out of `pattern` if the locale has no names for them.
+    fn localize<'a>(&self, pattern: &'a str) -> Cow<'a, str> {
+        let am = DateTime::UNIX_EPOCH.format_localized("%p", self.locale);
+        let am = am.to_string();
+        if am.is_empty() && pattern.contains("%p") {
+            pattern.replace(" %p", "").replace("%p", "").into()
+        } else {
+            pattern.into()
+        }
+    }
+}
+
+/// Parses a locale like `de_DE`, also accepting the form used by `LANG`, like `de_DE.UTF-8`.
+pub fn parse_locale(s: &str) -> Result<Locale, String> {
+    let name = s.split(['.', '@']).next().unwrap_or(s);
+    match name {
+        "C" | "POSIX" | "" => Ok(Locale::POSIX),
+        _ => Locale::try_from(name).map_err(|_| format!("`{}` is not a known locale", s)),
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{read_entries_iter, write_entries, Entries, Entry, Error, LastMut, Result, Timezone};
+use chrono::{DateTime, Local, TimeZone};
+use std::{
+    io::{Read, Write},
+    slice,
+};
+
+/// A log of entries, kept sorted by start time.
+///
+/// This holds the logic behind the `start`, `stop` and `note` commands, so that other programs
+/// can keep a log the same way the command line tool does.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Timelog {
+    entries: Entries,
+    /// IDs that are taken by entries of the log that aren't in `entries`.
+    reserved: u64,
+}
+
+impl Timelog {
+    /// An empty log.
+    pub fn new() -> Self {
+        Timelog::default()
+    }
+
+    /// Reads a log written by `save`.
+    pub fn load<R: Read>(reader: R) -> Result<Timelog> {
+        read_entries_iter(reader).collect()
+    }
+
+    /// Writes the log, one pretty-printed entry after the other.
+    pub fn save<W: Write>(&self, writer: W) -> Result<()> {
+        write_entries(writer, &self.entries)
+    }
+
+    pub fn entries(&self) -> &Entries {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Entries {
+        self.entries
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Entry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The latest entry.
+    pub fn last(&self) -> Option<&Entry> {
+        self.entries.last()
+    }
+
+    /// The latest entry, to change in place.
+    pub fn last_mut(&mut self) -> Option<LastMut<'_>> {
+        self.entries.last_mut()
+    }
+
+    /// The latest entry, if it is still running.
+    pub fn active(&self) -> Option<&Entry> {
+        self.entries.active()
+    }
+
+    /// The latest entry, if it is still running, to change in place.
+    pub fn active_mut(&mut self) -> Option<LastMut<'_>> {
+        self.entries.active_mut()
+    }
+
+    /// The entries that were running at some point between `from` and `to`.
+    ///
+    /// Entries that are still running count as running until now.
+    pub fn iter_range<Tz: TimeZone>(
+        &self,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> impl Iterator<Item = &Entry> {
+        self.iter_range_at(from, to, Local::now())
+    }
+
+    /// Like `iter_range`, but entries that are still running count as running until `now`.
+    pub fn iter_range_at<Tz: TimeZone, Now: TimeZone>(
+        &self,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+        now: DateTime<Now>,
+    ) -> impl Iterator<Item = &Entry> {
+        let (from, to, now) = (from.fixed_offset(), to.fixed_offset(), now.fixed_offset());
+        self.entries.iter().filter(move |e| match e.start {
+            Some(start) => start < to && e.stop.unwrap_or(now) > from,
+            None => false,
+        })
+    }
+
+    /// The ID the next entry gets, one more than the highest ID in the log.
+    ///
+    /// Expenses and absences have no start, so they sort first and the highest ID isn't always
+    /// the one of the latest entry.
+    pub fn next_id(&self) -> u64 {
+        let highest = self.entries.iter().filter_map(|e| e.id).max();
+        highest.unwrap_or(0).max(self.reserved) + 1
+    }
+
+    /// Makes `next_id` skip the IDs up to `id`, for a log that only holds the end of a longer one
+    /// whose other entries have them.
+    pub fn reserve_ids(&mut self, id: u64) {
+        self.reserved = self.reserved.max(id);
+    }
+
+    /// Adds `entry` to the log in its place in the order.
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.insert_sorted(entry);
+    }
+
+    /// Starts a new entry with `goal` now.
+    pub fn start(&mut self, goal: String) -> &Entry {
+        self.start_at(goal, Local::now())
+    }
+
+    /// Starts a new entry with `goal` at `time`.
+    pub fn start_at<Tz: TimeZone>(&mut self, goal: String, time: DateTime<Tz>) -> &Entry {
+        self.start_with(Entry {
+            start: Some(time.fixed_offset()),
+            goal,
+            ..Entry::default()
+        })
+    }
+
+    /// Starts `entry`, giving it the next ID and starting it now unless it already has a start
+    /// time.
+    ///
+    /// Times are always stored with the offset they were given in. Only the methods that default
+    /// to now use the local timezone, so logs kept with the `_at` methods don't depend on the
+    /// machine they are kept on.
+    pub fn start_with(&mut self, mut entry: Entry) -> &Entry {
+        entry.id = Some(self.next_id());
+        entry.start = entry.start.or_else(|| Some(Local::now().fixed_offset()));
+        let i = self.entries.insert_sorted(entry);
+        &self.entries[i]
+    }
+
+    /// Stops the running entry now, recording `result`.
+    pub fn stop(&mut self, result: String) -> Result<&Entry> {
+        self.stop_at(result, Local::now())
+    }
+
+    /// Stops the running entry at `time`, recording `result`.
+    pub fn stop_at<Tz: TimeZone>(&mut self, result: String, time: DateTime<Tz>) -> Result<&Entry> {
+        self.entries.close_active(time.fixed_offset(), result)
+    }
+
+    /// Splits the latest entry at every midnight in `timezone` that it ran across, giving the new
+    /// pieces the next IDs.
+    pub fn split_last_at_midnight(&mut self, timezone: Timezone) {
+        let last = match self.entries.pop() {
+            Some(last) => last,
+            None => return,
+        };
+        for mut piece in last.split_at_midnight(timezone) {
+            if piece.id.is_none() && last.id.is_some() {
+                piece.id = Some(self.next_id());
+            }
+            self.push(piece);
+        }
+    }
+
+    /// Adds `note` to the latest entry.
+    pub fn note(&mut self, note: String) -> Result<&Entry> {
+        self.entries
+            .last_mut()
+            .ok_or(Error::NoEntries)?
+            .notes
+            .push(note);
+        Ok(self.last().expect("the note was just added"))
+    }
+}
+
+impl From<Entries> for Timelog {
+    fn from(entries: Entries) -> Self {
+        Timelog {
+            entries,
+            reserved: 0,
+        }
+    }
+}
+
+impl From<Vec<Entry>> for Timelog {
+    fn from(entries: Vec<Entry>) -> Self {
+        Timelog::from(Entries::from(entries))
+    }
+}
+
+impl std::iter::FromIterator<Entry> for Timelog {
+    fn from_iter<I: IntoIterator<Item = Entry>>(iter: I) -> Self {
+        Timelog::from(iter.into_iter().collect::<Entries>())
+    }
+}
+
+impl IntoIterator for Timelog {
+    type Item = Entry;
+    type IntoIter = <Entries as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Timelog {
+    type Item = &'a Entry;
+    type IntoIter = slice::Iter<'a, Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Expense, Money};
+    use chrono::{FixedOffset, NaiveDate};
+
+    #[test]
+    fn next_id_skips_the_ids_of_expenses() {
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let expense = Entry {
+            id: Some(3),
+            expense: Some(Expense::new(date, Money::new(100, "EUR"))),
+            ..Entry::default()
+        };
+        let mut log = Timelog::from(vec![expense]);
+        let time = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2020, 1, 1, 9, 0, 0);
+        log.start_at("a".to_string(), time.unwrap());
+        assert_eq!(log.last().unwrap().id, Some(4));
+        assert_eq!(log.next_id(), 5);
+    }
+
+    #[test]
+    fn reserved_ids() {
+        let mut log = Timelog::new();
+        assert_eq!(log.next_id(), 1);
+        log.reserve_ids(7);
+        log.reserve_ids(3);
+        assert_eq!(log.next_id(), 8);
+        assert_eq!(log.start("a".to_string()).id, Some(8));
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// The timezone that timestamps are interpreted in when grouping them by day, week, etc.
+///
+/// Entries store the offset they were recorded with, so a log can move between machines in
+/// different timezones without changing which day an entry belongs to, unless asked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timezone {
+    /// The timezone of this machine.
+    #[default]
+    Local,
+    Utc,
+    /// The offset each timestamp was recorded with.
+    Original,
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// `t` in this timezone.
+    pub fn convert(self, t: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            Timezone::Local => t.with_timezone(&Local).fixed_offset(),
+            Timezone::Utc => t.with_timezone(&Utc).fixed_offset(),
+            Timezone::Original => *t,
+            Timezone::Fixed(offset) => t.with_timezone(&offset),
+        }
+    }
+
+    /// The wall-clock time of `t` in this timezone.
+    pub fn naive_local(self, t: &DateTime<FixedOffset>) -> NaiveDateTime {
+        match self {
+            Timezone::Local => t.with_timezone(&Local).naive_local(),
+            Timezone::Utc => t.with_timezone(&Utc).naive_local(),
+            Timezone::Original => t.naive_local(),
+            Timezone::Fixed(offset) => t.with_timezone(&offset).naive_local(),
+        }
+    }
+
+    /// The date of `t` in this timezone.
+    pub fn date(self, t: &DateTime<FixedOffset>) -> NaiveDate {
+        self.naive_local(t).date()
+    }
+
+    /// The start of the day after the one `t` falls on in this timezone.
+    ///
+    /// When midnight is skipped by a change to daylight saving time, the day starts at the same
+    /// offset as `t`.
+    pub fn next_midnight(self, t: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        let midnight = self
+            .date(t)
+            .succ_opt()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .expect("the day after an entry is in range");
+        let local = match self {
+            Timezone::Local => Local.from_local_datetime(&midnight).earliest(),
+            _ => None,
+        };
+        local.map_or_else(
+            || {
+                let offset = *self.convert(t).offset();
+                offset
+                    .from_local_datetime(&midnight)
+                    .single()
+                    .expect("fixed offsets map every time to one instant")
+            },
+            |t| t.fixed_offset(),
+        )
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Timezone::Local),
+            "utc" | "UTC" => Ok(Timezone::Utc),
+            "original" => Ok(Timezone::Original),
+            _ => s.parse().map(Timezone::Fixed).map_err(|_| {
+                format!(
+                    "`{}` isn't a timezone, expected local, utc, original or an offset like +02:00",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Timezone::Local => f.write_str("local"),
+            Timezone::Utc => f.write_str("utc"),
+            Timezone::Original => f.write_str("original"),
+            Timezone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl Serialize for Timezone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timezone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
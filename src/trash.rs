@@ -0,0 +1,60 @@
+use crate::{Entry, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use log::debug;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// An entry that was deleted from the log.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrashedEntry {
+    pub deleted: DateTime<FixedOffset>,
+    pub entry: Entry,
+}
+
+/// Entries deleted from a log, kept next to it so that they can be restored.
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Trash {
+    pub entries: Vec<TrashedEntry>,
+}
+
+impl Trash {
+    /// The path of the trash belonging to the log at `log_path`.
+    pub fn path_for<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".trash");
+        PathBuf::from(path)
+    }
+
+    /// Reads the trash at `path`, which is empty if the file doesn't exist.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Trash> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Trash::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        debug!(
+            "writing {} entries to the trash {}",
+            self.entries.len(),
+            path.display()
+        );
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Moves `entry` into the trash.
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(TrashedEntry {
+            deleted: Local::now().fixed_offset(),
+            entry,
+        });
+    }
+}
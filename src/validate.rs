@@ -0,0 +1,270 @@
+use crate::Entry;
+use chrono::{DateTime, FixedOffset, Local};
+use itertools::Itertools;
+use std::{collections::BTreeMap, fmt};
+
+/// A reference to an entry by its 1-based position in the log and its ID, if it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EntryRef {
+    pub index: usize,
+    pub id: Option<u64>,
+}
+
+impl EntryRef {
+    /// A reference to `entry`, which is at the 0-based index `i`.
+    pub fn new(i: usize, entry: &Entry) -> Self {
+        EntryRef {
+            index: i + 1,
+            id: entry.id,
+        }
+    }
+}
+
+impl fmt::Display for EntryRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.index)?;
+        if let Some(id) = self.id {
+            write!(f, " (ID {})", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem found in a log by `validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    Overlap(EntryRef, EntryRef),
+    StopBeforeStart(EntryRef),
+    MultipleOpen(Vec<EntryRef>),
+    DuplicateId(u64, Vec<EntryRef>),
+    InFuture(EntryRef),
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationIssue::Overlap(a, b) => write!(f, "entry {} overlaps entry {}", a, b),
+            ValidationIssue::StopBeforeStart(e) => write!(f, "entry {} stops before it starts", e),
+            ValidationIssue::MultipleOpen(v) => {
+                write!(f, "entries {} are all still running", v.iter().join(", "))
+            }
+            ValidationIssue::DuplicateId(id, v) => {
+                let v = v.iter().map(|e| format!("#{}", e.index)).join(", ");
+                write!(f, "entries {} share the ID {}", v, id)
+            }
+            ValidationIssue::InFuture(e) => write!(f, "entry {} is in the future", e),
+        }
+    }
+}
+
+/// Finds the problems in `entries`: overlapping entries, entries that stop before they start,
+/// several running entries, duplicate IDs and entries in the future.
+pub fn validate(entries: &[Entry]) -> Vec<ValidationIssue> {
+    validate_at(entries, Local::now().fixed_offset())
+}
+
+/// Like `validate`, but with `now` as the current time.
+pub fn validate_at(entries: &[Entry], now: DateTime<FixedOffset>) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let mut by_start: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| e.start.map(|start| (start, e.stop, EntryRef::new(i, e))))
+        .collect();
+    by_start.sort();
+    let mut latest: Option<(DateTime<FixedOffset>, EntryRef)> = None;
+    for &(start, stop, r) in &by_start {
+        if let Some(stop) = stop {
+            match latest {
+                Some((end, other)) if start < end => {
+                    issues.push(ValidationIssue::Overlap(other, r))
+                }
+                _ => {}
+            }
+            if latest.is_none_or(|(end, _)| stop > end) {
+                latest = Some((stop, r));
+            }
+        }
+    }
+
+    let mut open = Vec::new();
+    let mut ids = BTreeMap::new();
+    for (i, e) in entries.iter().enumerate() {
+        let r = EntryRef::new(i, e);
+        if let (Some(start), Some(stop)) = (e.start, e.stop) {
+            if stop < start {
+                issues.push(ValidationIssue::StopBeforeStart(r));
+            }
+        }
+        if e.is_active() {
+            open.push(r);
+        }
+        if let Some(id) = e.id {
+            ids.entry(id).or_insert_with(Vec::new).push(r);
+        }
+        if e.start.is_some_and(|t| t > now) || e.stop.is_some_and(|t| t > now) {
+            issues.push(ValidationIssue::InFuture(r));
+        }
+    }
+    if open.len() > 1 {
+        issues.push(ValidationIssue::MultipleOpen(open));
+    }
+    for (id, v) in ids {
+        if v.len() > 1 {
+            issues.push(ValidationIssue::DuplicateId(id, v));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Absence, AbsenceKind};
+    use chrono::Duration;
+
+    fn time(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(&format!("2020-01-01T{}:00Z", s)).unwrap()
+    }
+
+    fn entry(start: &str, stop: Option<&str>) -> Entry {
+        Entry {
+            start: Some(time(start)),
+            stop: stop.map(time),
+            ..Entry::default()
+        }
+    }
+
+    fn r(index: usize) -> EntryRef {
+        EntryRef { index, id: None }
+    }
+
+    fn check(entries: &[Entry]) -> Vec<ValidationIssue> {
+        validate_at(entries, time("23:00"))
+    }
+
+    #[test]
+    fn valid() {
+        let entries = [
+            entry("08:00", Some("09:00")),
+            entry("09:00", Some("10:00")),
+            entry("11:00", None),
+        ];
+        assert_eq!(check(&entries), []);
+    }
+
+    #[test]
+    fn overlaps() {
+        let entries = [
+            entry("08:00", Some("09:30")),
+            entry("09:00", Some("10:00")),
+            entry("10:00", Some("11:00")),
+        ];
+        assert_eq!(check(&entries), [ValidationIssue::Overlap(r(1), r(2))]);
+    }
+
+    #[test]
+    fn overlaps_are_found_out_of_order() {
+        let entries = [entry("09:00", Some("10:00")), entry("08:00", Some("09:30"))];
+        assert_eq!(check(&entries), [ValidationIssue::Overlap(r(2), r(1))]);
+    }
+
+    #[test]
+    fn nested_overlaps() {
+        // The second entry ends before the first one, which still overlaps the third.
+        let entries = [
+            entry("08:00", Some("12:00")),
+            entry("09:00", Some("10:00")),
+            entry("10:30", Some("11:00")),
+            entry("12:00", Some("13:00")),
+        ];
+        assert_eq!(
+            check(&entries),
+            [
+                ValidationIssue::Overlap(r(1), r(2)),
+                ValidationIssue::Overlap(r(1), r(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_before_start() {
+        let entries = [entry("10:00", Some("09:00"))];
+        assert_eq!(check(&entries), [ValidationIssue::StopBeforeStart(r(1))]);
+    }
+
+    #[test]
+    fn multiple_open() {
+        let entries = [
+            entry("08:00", None),
+            entry("09:00", Some("10:00")),
+            entry("11:00", None),
+        ];
+        assert_eq!(
+            check(&entries),
+            [ValidationIssue::MultipleOpen(vec![r(1), r(3)])]
+        );
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let mut entries = vec![
+            entry("08:00", Some("09:00")),
+            entry("09:00", Some("10:00")),
+            entry("10:00", Some("11:00")),
+            entry("11:00", Some("12:00")),
+        ];
+        for (e, id) in entries.iter_mut().zip(&[1, 2, 1, 3]) {
+            e.id = Some(*id);
+        }
+        let refs = vec![
+            EntryRef {
+                index: 1,
+                id: Some(1),
+            },
+            EntryRef {
+                index: 3,
+                id: Some(1),
+            },
+        ];
+        assert_eq!(check(&entries), [ValidationIssue::DuplicateId(1, refs)]);
+        assert_eq!(
+            check(&entries)[0].to_string(),
+            "entries #1, #3 share the ID 1"
+        );
+    }
+
+    #[test]
+    fn in_future() {
+        let entries = [
+            entry("08:00", Some("09:00")),
+            entry("22:00", Some("23:30")),
+            entry("23:30", None),
+        ];
+        assert_eq!(
+            check(&entries),
+            [
+                ValidationIssue::InFuture(r(2)),
+                ValidationIssue::InFuture(r(3))
+            ]
+        );
+        // Stopping right now is fine.
+        assert_eq!(check(&[entry("22:00", Some("23:00"))]), []);
+    }
+
+    #[test]
+    fn absences_are_not_checked_for_overlaps() {
+        let absence = Entry {
+            absence: Some(Absence::new(
+                time("08:00").date_naive(),
+                AbsenceKind::Sick,
+                Duration::hours(8),
+            )),
+            ..Entry::default()
+        };
+        let entries = [absence.clone(), entry("08:00", Some("09:00")), absence];
+        assert_eq!(check(&entries), []);
+    }
+}